@@ -2,6 +2,15 @@ pub use sea_orm_migration::prelude::*;
 
 mod m20220101_000001_create_table;
 mod m20240101_000002_rename_subjects_to_sets;
+mod m20250101_000003_add_problem_search;
+mod m20250101_000004_add_problem_source_window;
+mod m20250101_000005_add_problem_image_hash;
+mod m20250101_000006_create_problem_images;
+mod m20250101_000007_add_problem_image_original;
+mod m20250101_000008_create_sessions;
+mod m20250101_000009_create_study_intervals;
+mod m20250101_000010_create_pomodoros;
+mod m20250101_000011_create_blob_refs;
 pub mod seed;
 
 pub struct Migrator;
@@ -12,6 +21,15 @@ impl MigratorTrait for Migrator {
         vec![
             Box::new(m20220101_000001_create_table::Migration),
             Box::new(m20240101_000002_rename_subjects_to_sets::Migration),
+            Box::new(m20250101_000003_add_problem_search::Migration),
+            Box::new(m20250101_000004_add_problem_source_window::Migration),
+            Box::new(m20250101_000005_add_problem_image_hash::Migration),
+            Box::new(m20250101_000006_create_problem_images::Migration),
+            Box::new(m20250101_000007_add_problem_image_original::Migration),
+            Box::new(m20250101_000008_create_sessions::Migration),
+            Box::new(m20250101_000009_create_study_intervals::Migration),
+            Box::new(m20250101_000010_create_pomodoros::Migration),
+            Box::new(m20250101_000011_create_blob_refs::Migration),
         ]
     }
 }
@@ -0,0 +1,91 @@
+use sea_orm_migration::prelude::*;
+use sea_orm::Statement;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Step 1: Store OCR text on problems so it can be searched
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Problems::Table)
+                    .add_column(ColumnDef::new(Problems::OcrText).string().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Step 2: FTS5 index over the searchable problem columns.
+        // problem_id is kept unindexed so results can be joined back to problems.
+        let db = manager.get_connection();
+        let stmt = Statement::from_sql_and_values(
+            manager.get_database_backend(),
+            "CREATE VIRTUAL TABLE problems_fts USING fts5(
+                problem_id UNINDEXED,
+                title,
+                description,
+                notes,
+                ocr_text,
+                tokenize = 'unicode61 remove_diacritics 2'
+            )",
+            [],
+        );
+        db.execute(stmt).await?;
+
+        // Step 3: Keep the index in sync with the problems table.
+        // These also fire for rows removed by ON DELETE CASCADE.
+        for sql in [
+            "CREATE TRIGGER problems_fts_after_insert AFTER INSERT ON problems BEGIN
+                INSERT INTO problems_fts (problem_id, title, description, notes, ocr_text)
+                VALUES (new.id, new.title, new.description, new.notes, new.ocr_text);
+            END",
+            "CREATE TRIGGER problems_fts_after_update
+            AFTER UPDATE OF title, description, notes, ocr_text ON problems BEGIN
+                DELETE FROM problems_fts WHERE problem_id = old.id;
+                INSERT INTO problems_fts (problem_id, title, description, notes, ocr_text)
+                VALUES (new.id, new.title, new.description, new.notes, new.ocr_text);
+            END",
+            "CREATE TRIGGER problems_fts_after_delete AFTER DELETE ON problems BEGIN
+                DELETE FROM problems_fts WHERE problem_id = old.id;
+            END",
+        ] {
+            let stmt = Statement::from_sql_and_values(manager.get_database_backend(), sql, []);
+            db.execute(stmt).await?;
+        }
+
+        // Step 4: Index existing problems
+        let stmt = Statement::from_sql_and_values(
+            manager.get_database_backend(),
+            "INSERT INTO problems_fts (problem_id, title, description, notes, ocr_text)
+            SELECT id, title, description, notes, ocr_text FROM problems",
+            [],
+        );
+        db.execute(stmt).await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        for sql in [
+            "DROP TRIGGER IF EXISTS problems_fts_after_insert",
+            "DROP TRIGGER IF EXISTS problems_fts_after_update",
+            "DROP TRIGGER IF EXISTS problems_fts_after_delete",
+            "DROP TABLE IF EXISTS problems_fts",
+            "ALTER TABLE problems DROP COLUMN ocr_text",
+        ] {
+            let stmt = Statement::from_sql_and_values(manager.get_database_backend(), sql, []);
+            db.execute(stmt).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Problems {
+    Table,
+    OcrText,
+}
@@ -0,0 +1,138 @@
+use sea_orm::prelude::DateTime;
+use sea_orm::Statement;
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Step 1: Images belonging to a problem, in display order
+        manager
+            .create_table(
+                Table::create()
+                    .table(ProblemImages::Table)
+                    .if_not_exists()
+                    .col(uuid(ProblemImages::Id).primary_key())
+                    .col(uuid(ProblemImages::ProblemId))
+                    .col(string(ProblemImages::Role))
+                    .col(string(ProblemImages::Path))
+                    .col(integer(ProblemImages::SortOrder).default(0))
+                    .col(timestamp(ProblemImages::CreatedAt))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(ProblemImages::Table, ProblemImages::ProblemId)
+                            .to(Problems::Table, Problems::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_problem_images_problem_id")
+                    .table(ProblemImages::Table)
+                    .col(ProblemImages::ProblemId)
+                    .col(ProblemImages::SortOrder)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Step 2: Existing images become each problem's first question image
+        let db = manager.get_connection();
+        let rows = db
+            .query_all(Statement::from_sql_and_values(
+                manager.get_database_backend(),
+                "SELECT id, image_path, created_at FROM problems WHERE image_path IS NOT NULL",
+                [],
+            ))
+            .await?;
+
+        for row in rows {
+            let problem_id: uuid::Uuid = row.try_get("", "id")?;
+            let image_path: String = row.try_get("", "image_path")?;
+            let created_at: DateTime = row.try_get("", "created_at")?;
+
+            let insert = Query::insert()
+                .into_table(ProblemImages::Table)
+                .columns([
+                    ProblemImages::Id,
+                    ProblemImages::ProblemId,
+                    ProblemImages::Role,
+                    ProblemImages::Path,
+                    ProblemImages::SortOrder,
+                    ProblemImages::CreatedAt,
+                ])
+                .values_panic([
+                    uuid::Uuid::new_v4().into(),
+                    problem_id.into(),
+                    "question".into(),
+                    image_path.into(),
+                    0.into(),
+                    created_at.into(),
+                ])
+                .to_owned();
+            manager.exec_stmt(insert).await?;
+        }
+
+        // Step 3: problem_images is now the only record of image paths
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Problems::Table)
+                    .drop_column(Problems::ImagePath)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Problems::Table)
+                    .add_column(ColumnDef::new(Problems::ImagePath).string().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Keep the first question image of each problem; the rest can't be represented
+        let db = manager.get_connection();
+        let stmt = Statement::from_sql_and_values(
+            manager.get_database_backend(),
+            "UPDATE problems SET image_path = (
+                SELECT path FROM problem_images
+                WHERE problem_images.problem_id = problems.id AND role = 'question'
+                ORDER BY sort_order, created_at
+                LIMIT 1
+            )",
+            [],
+        );
+        db.execute(stmt).await?;
+
+        manager
+            .drop_table(Table::drop().table(ProblemImages::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Problems {
+    Table,
+    Id,
+    ImagePath,
+}
+
+#[derive(DeriveIden)]
+enum ProblemImages {
+    Table,
+    Id,
+    ProblemId,
+    Role,
+    Path,
+    SortOrder,
+    CreatedAt,
+}
@@ -0,0 +1,37 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // The image as captured, kept when the cleanup pipeline replaced `path`
+        // with a processed version, so the processing can be undone.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ProblemImages::Table)
+                    .add_column(ColumnDef::new(ProblemImages::OriginalPath).string().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ProblemImages::Table)
+                    .drop_column(ProblemImages::OriginalPath)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ProblemImages {
+    Table,
+    OriginalPath,
+}
@@ -0,0 +1,133 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// The only row of `app_state`
+const APP_STATE_ID: i32 = 1;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Step 1: Saved sessions, removed along with their folder, course or set
+        manager
+            .create_table(
+                Table::create()
+                    .table(Sessions::Table)
+                    .if_not_exists()
+                    .col(uuid(Sessions::Id).primary_key())
+                    .col(string(Sessions::Name))
+                    .col(uuid(Sessions::FolderId))
+                    .col(uuid(Sessions::CourseId))
+                    .col(uuid(Sessions::SetId))
+                    .col(timestamp(Sessions::CreatedAt))
+                    .col(timestamp(Sessions::LastUsed))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Sessions::Table, Sessions::FolderId)
+                            .to(Folders::Table, Folders::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Sessions::Table, Sessions::CourseId)
+                            .to(Courses::Table, Courses::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Sessions::Table, Sessions::SetId)
+                            .to(Sets::Table, Sets::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_sessions_context")
+                    .table(Sessions::Table)
+                    .col(Sessions::FolderId)
+                    .col(Sessions::CourseId)
+                    .col(Sessions::SetId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // Step 2: A single row of app-wide state, starting with the active session
+        manager
+            .create_table(
+                Table::create()
+                    .table(AppState::Table)
+                    .if_not_exists()
+                    .col(integer(AppState::Id).primary_key())
+                    .col(uuid_null(AppState::ActiveSessionId))
+                    .col(timestamp_null(AppState::SessionsImportedAt))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(AppState::Table, AppState::ActiveSessionId)
+                            .to(Sessions::Table, Sessions::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        let insert = Query::insert()
+            .into_table(AppState::Table)
+            .columns([AppState::Id])
+            .values_panic([APP_STATE_ID.into()])
+            .to_owned();
+        manager.exec_stmt(insert).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AppState::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Sessions::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Folders {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Courses {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Sets {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Sessions {
+    Table,
+    Id,
+    Name,
+    FolderId,
+    CourseId,
+    SetId,
+    CreatedAt,
+    LastUsed,
+}
+
+#[derive(DeriveIden)]
+enum AppState {
+    Table,
+    Id,
+    ActiveSessionId,
+    SessionsImportedAt,
+}
@@ -0,0 +1,105 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Time spent in a session. Kept when the session is deleted, since
+        // the folder, course and set say what was studied.
+        manager
+            .create_table(
+                Table::create()
+                    .table(StudyIntervals::Table)
+                    .if_not_exists()
+                    .col(uuid(StudyIntervals::Id).primary_key())
+                    .col(uuid_null(StudyIntervals::SessionId))
+                    .col(uuid(StudyIntervals::FolderId))
+                    .col(uuid(StudyIntervals::CourseId))
+                    .col(uuid(StudyIntervals::SetId))
+                    .col(timestamp(StudyIntervals::StartedAt))
+                    .col(timestamp_null(StudyIntervals::EndedAt))
+                    .col(timestamp(StudyIntervals::LastSeenAt))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(StudyIntervals::Table, StudyIntervals::SessionId)
+                            .to(Sessions::Table, Sessions::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(StudyIntervals::Table, StudyIntervals::FolderId)
+                            .to(Folders::Table, Folders::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(StudyIntervals::Table, StudyIntervals::CourseId)
+                            .to(Courses::Table, Courses::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(StudyIntervals::Table, StudyIntervals::SetId)
+                            .to(Sets::Table, Sets::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_study_intervals_started_at")
+                    .table(StudyIntervals::Table)
+                    .col(StudyIntervals::StartedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(StudyIntervals::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Folders {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Courses {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Sets {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Sessions {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum StudyIntervals {
+    Table,
+    Id,
+    SessionId,
+    FolderId,
+    CourseId,
+    SetId,
+    StartedAt,
+    EndedAt,
+    LastSeenAt,
+}
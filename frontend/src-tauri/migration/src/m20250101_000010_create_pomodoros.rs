@@ -0,0 +1,102 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // A completed focus block, recorded against the set being studied
+        manager
+            .create_table(
+                Table::create()
+                    .table(Pomodoros::Table)
+                    .if_not_exists()
+                    .col(uuid(Pomodoros::Id).primary_key())
+                    .col(uuid_null(Pomodoros::SessionId))
+                    .col(uuid(Pomodoros::FolderId))
+                    .col(uuid(Pomodoros::CourseId))
+                    .col(uuid(Pomodoros::SetId))
+                    .col(integer(Pomodoros::WorkSeconds))
+                    .col(timestamp(Pomodoros::CompletedAt))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Pomodoros::Table, Pomodoros::SessionId)
+                            .to(Sessions::Table, Sessions::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Pomodoros::Table, Pomodoros::FolderId)
+                            .to(Folders::Table, Folders::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Pomodoros::Table, Pomodoros::CourseId)
+                            .to(Courses::Table, Courses::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Pomodoros::Table, Pomodoros::SetId)
+                            .to(Sets::Table, Sets::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_pomodoros_completed_at")
+                    .table(Pomodoros::Table)
+                    .col(Pomodoros::CompletedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Pomodoros::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Folders {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Courses {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Sets {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Sessions {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Pomodoros {
+    Table,
+    Id,
+    SessionId,
+    FolderId,
+    CourseId,
+    SetId,
+    WorkSeconds,
+    CompletedAt,
+}
@@ -0,0 +1,317 @@
+use sea_orm::{ConnectionTrait, Statement};
+use sea_orm_migration::{prelude::*, schema::*};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Directory of the image store, relative to the app data dir
+const BLOB_DIR: &str = "blobs";
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Step 1: Which blob each image uses, as its current file or as its original
+        manager
+            .create_table(
+                Table::create()
+                    .table(BlobRefs::Table)
+                    .if_not_exists()
+                    .col(uuid(BlobRefs::ImageId))
+                    .col(string(BlobRefs::Kind))
+                    .col(uuid(BlobRefs::ProblemId))
+                    .col(string(BlobRefs::Hash))
+                    .col(string(BlobRefs::Path))
+                    .primary_key(
+                        Index::create()
+                            .col(BlobRefs::ImageId)
+                            .col(BlobRefs::Kind),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(BlobRefs::Table, BlobRefs::ImageId)
+                            .to(ProblemImages::Table, ProblemImages::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(BlobRefs::Table, BlobRefs::ProblemId)
+                            .to(Problems::Table, Problems::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_blob_refs_hash")
+                    .table(BlobRefs::Table)
+                    .col(BlobRefs::Hash)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Step 2: Images still under the old `<folder>/<course>/<set>/<name>`
+        // layout are rehashed into the store. Only possible for a database
+        // file, whose directory is the app data dir.
+        let app_data_dir = app_data_dir(manager).await?;
+        let moved_off = match &app_data_dir {
+            Some(dir) => move_legacy_files(manager, dir).await?,
+            None => Vec::new(),
+        };
+
+        // Step 3: Record the blob of every image
+        let rows = image_rows(manager).await?;
+        for row in rows {
+            let files = [("image", Some(row.path)), ("original", row.original_path)];
+            for (kind, path) in files {
+                let Some(path) = path else {
+                    continue;
+                };
+                let Some(hash) = blob_hash(&path) else {
+                    continue;
+                };
+                let insert = Query::insert()
+                    .into_table(BlobRefs::Table)
+                    .columns([
+                        BlobRefs::ImageId,
+                        BlobRefs::Kind,
+                        BlobRefs::ProblemId,
+                        BlobRefs::Hash,
+                        BlobRefs::Path,
+                    ])
+                    .values_panic([
+                        row.id.into(),
+                        kind.into(),
+                        row.problem_id.into(),
+                        hash.into(),
+                        path.into(),
+                    ])
+                    // Rows from an earlier run that failed partway
+                    .on_conflict(
+                        OnConflict::columns([BlobRefs::ImageId, BlobRefs::Kind])
+                            .do_nothing()
+                            .to_owned(),
+                    )
+                    .to_owned();
+                manager.exec_stmt(insert).await?;
+            }
+        }
+
+        // Step 4: Old files go last, once every row that used them points into the store
+        if let Some(app_data_dir) = app_data_dir {
+            for file in moved_off {
+                if let Err(e) = fs::remove_file(&file) {
+                    log::warn!("Failed to remove old image file {}: {}", file.display(), e);
+                } else if let Some(parent) = file.parent() {
+                    remove_empty_dirs(parent, &app_data_dir);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Moved files stay in the store; paths into it keep working
+        manager
+            .drop_table(Table::drop().table(BlobRefs::Table).to_owned())
+            .await
+    }
+}
+
+struct ImageRow {
+    id: uuid::Uuid,
+    problem_id: uuid::Uuid,
+    path: String,
+    original_path: Option<String>,
+}
+
+async fn image_rows(manager: &SchemaManager<'_>) -> Result<Vec<ImageRow>, DbErr> {
+    let rows = manager
+        .get_connection()
+        .query_all(Statement::from_sql_and_values(
+            manager.get_database_backend(),
+            "SELECT id, problem_id, path, original_path FROM problem_images",
+            [],
+        ))
+        .await?;
+
+    rows.into_iter()
+        .map(|row| {
+            Ok(ImageRow {
+                id: row.try_get("", "id")?,
+                problem_id: row.try_get("", "problem_id")?,
+                path: row.try_get("", "path")?,
+                original_path: row.try_get("", "original_path")?,
+            })
+        })
+        .collect()
+}
+
+/// Directory of the main database file, or `None` for an in-memory database
+async fn app_data_dir(manager: &SchemaManager<'_>) -> Result<Option<PathBuf>, DbErr> {
+    let rows = manager
+        .get_connection()
+        .query_all(Statement::from_sql_and_values(
+            manager.get_database_backend(),
+            "PRAGMA database_list",
+            [],
+        ))
+        .await?;
+
+    for row in rows {
+        let name: String = row.try_get("", "name")?;
+        let file: String = row.try_get("", "file")?;
+        if name == "main" && !file.is_empty() {
+            return Ok(Path::new(&file).parent().map(Path::to_path_buf));
+        }
+    }
+    Ok(None)
+}
+
+/// Hash of a path inside the store, from its file name
+fn blob_hash(image_path: &str) -> Option<String> {
+    let path = Path::new(image_path);
+    if !path.starts_with(BLOB_DIR) {
+        return None;
+    }
+    let stem = path.file_stem()?.to_str()?;
+    (stem.len() == 64 && stem.bytes().all(|b| b.is_ascii_hexdigit())).then(|| stem.to_string())
+}
+
+/// File of a legacy path; ones saved without an extension are PNGs
+fn resolve_legacy_file(app_data_dir: &Path, image_path: &str) -> Option<PathBuf> {
+    let path = app_data_dir.join(image_path);
+    if path.is_file() {
+        return Some(path);
+    }
+    let png = app_data_dir.join(format!("{}.png", image_path));
+    png.is_file().then_some(png)
+}
+
+/// Writes `bytes` to `blobs/<aa>/<sha256>.<extension>` unless it is there
+/// already, and returns that path relative to `app_data_dir`
+fn put_blob(app_data_dir: &Path, bytes: &[u8], extension: &str) -> std::io::Result<String> {
+    let hash: String = Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    let relative = PathBuf::from(BLOB_DIR)
+        .join(&hash[..2])
+        .join(format!("{}.{}", hash, extension));
+    let full = app_data_dir.join(&relative);
+
+    if !full.is_file() {
+        let dir = full.parent().expect("blob paths have a parent");
+        fs::create_dir_all(dir)?;
+        let tmp = dir.join(format!("{}.{}.tmp", hash, uuid::Uuid::new_v4()));
+        let written = fs::File::create(&tmp).and_then(|mut file| {
+            file.write_all(bytes)?;
+            file.sync_all()
+        });
+        if let Err(e) = written.and_then(|_| fs::rename(&tmp, &full)) {
+            let _ = fs::remove_file(&tmp);
+            return Err(e);
+        }
+    }
+
+    Ok(relative.to_string_lossy().to_string())
+}
+
+fn remove_empty_dirs(dir: &Path, root: &Path) {
+    let mut current = Some(dir);
+    while let Some(dir) = current {
+        if dir == root || !dir.starts_with(root) || fs::remove_dir(dir).is_err() {
+            break;
+        }
+        current = dir.parent();
+    }
+}
+
+/// Moves every file a legacy path points at into the store and repoints all
+/// rows that use it, current files and originals alike. Returns the old
+/// files, to be removed once the rows are updated. Files that can't be found
+/// or read are left where they are, with their rows.
+async fn move_legacy_files(
+    manager: &SchemaManager<'_>,
+    app_data_dir: &Path,
+) -> Result<Vec<PathBuf>, DbErr> {
+    // Rows of the same problem saved twice share one file, so work per file
+    let mut by_file: BTreeMap<PathBuf, Vec<(uuid::Uuid, ProblemImages)>> = BTreeMap::new();
+    for row in image_rows(manager).await? {
+        let files = [
+            (ProblemImages::Path, Some(row.path)),
+            (ProblemImages::OriginalPath, row.original_path),
+        ];
+        for (column, path) in files {
+            // Paths into the store were moved by an earlier run, or saved there
+            let Some(path) = path.filter(|path| !Path::new(path).starts_with(BLOB_DIR)) else {
+                continue;
+            };
+            match resolve_legacy_file(app_data_dir, &path) {
+                Some(file) => by_file.entry(file).or_default().push((row.id, column)),
+                None => log::warn!("Image file for {} not found, leaving it in place", path),
+            }
+        }
+    }
+
+    let mut moved_off = Vec::new();
+    for (file, users) in by_file {
+        let extension = file
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase())
+            .unwrap_or_else(|| "png".to_string());
+        let blob = match fs::read(&file).and_then(|bytes| put_blob(app_data_dir, &bytes, &extension)) {
+            Ok(blob) => blob,
+            Err(e) => {
+                log::warn!("Failed to move {} into the image store: {}", file.display(), e);
+                continue;
+            }
+        };
+
+        for (id, column) in users {
+            let update = Query::update()
+                .table(ProblemImages::Table)
+                .value(column, blob.clone())
+                .and_where(Expr::col(ProblemImages::Id).eq(id))
+                .to_owned();
+            manager.exec_stmt(update).await?;
+        }
+        moved_off.push(file);
+    }
+
+    Ok(moved_off)
+}
+
+#[derive(DeriveIden)]
+enum Problems {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum ProblemImages {
+    Table,
+    Id,
+    Path,
+    OriginalPath,
+}
+
+#[derive(DeriveIden)]
+enum BlobRefs {
+    Table,
+    ImageId,
+    Kind,
+    ProblemId,
+    Hash,
+    Path,
+}
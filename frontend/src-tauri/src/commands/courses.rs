@@ -1,6 +1,9 @@
+use crate::commands::sessions::reload_sessions;
+use crate::commands::storage::{clean_up_deleted_images, refresh_sidecars_in_scope};
+use crate::db::services::ImageScope;
 use crate::db::{services, Db};
 use serde::{Deserialize, Serialize};
-use tauri::State;
+use tauri::{AppHandle, State};
 use uuid::Uuid;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -69,10 +72,12 @@ pub async fn get_courses_by_folder(
 
 #[tauri::command]
 pub async fn update_course(
+    app: AppHandle,
     db: State<'_, Db>,
     request: UpdateCourseRequest,
 ) -> Result<String, String> {
     let course_id = Uuid::parse_str(&request.id).map_err(|e| e.to_string())?;
+    let renamed = request.name.is_some();
 
     let course = services::update_course(
         db.connection(),
@@ -85,16 +90,27 @@ pub async fn update_course(
     .await
     .map_err(|e| e.to_string())?;
 
+    // The sidecars of its images carry the course name
+    if renamed {
+        refresh_sidecars_in_scope(&app, &db, ImageScope::Course(course_id)).await;
+    }
+
     serde_json::to_string(&course).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub async fn delete_course(db: State<'_, Db>, id: String) -> Result<String, String> {
+pub async fn delete_course(app: AppHandle, db: State<'_, Db>, id: String) -> Result<String, String> {
     let course_id = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
 
-    services::delete_course(db.connection(), course_id)
+    let images = services::delete_with_images(db.connection(), ImageScope::Course(course_id))
         .await
         .map_err(|e| e.to_string())?;
 
+    clean_up_deleted_images(&app, &db, images).await;
+    // Its sessions went with it
+    if let Err(e) = reload_sessions(&app).await {
+        log::warn!("{}", e);
+    }
+
     Ok("Course deleted successfully".to_string())
 }
@@ -0,0 +1,93 @@
+use crate::db::{services, Db};
+use crate::imaging::phash;
+use crate::storage::resolve_image_file;
+use tauri::{AppHandle, Manager, State};
+use uuid::Uuid;
+
+/// Hashes every problem image saved before hashing existed.
+/// Failures are logged and skipped; returns how many problems were hashed.
+pub(crate) async fn backfill_image_hashes(app: &AppHandle) -> Result<usize, String> {
+    let db = app.state::<Db>();
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+
+    let problems = services::get_problems_with_images(db.connection())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut hashed = 0;
+    for (problem, image) in problems {
+        if problem.image_hash.is_some() {
+            continue;
+        }
+        let image_path = image.path;
+
+        let app_data_dir = app_data_dir.clone();
+        let hash = tauri::async_runtime::spawn_blocking(move || {
+            let file = resolve_image_file(&app_data_dir, &image_path)
+                .ok_or_else(|| format!("Image file not found: {}", image_path))?;
+            let image = image::open(&file).map_err(|e| e.to_string())?;
+            Ok::<_, String>(phash::to_db(phash::dhash(&image)))
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+
+        match hash {
+            Ok(hash) => {
+                services::set_problem_image_hash(db.connection(), problem.id, Some(hash))
+                    .await
+                    .map_err(|e| e.to_string())?;
+                hashed += 1;
+            }
+            Err(e) => log::warn!("Skipping image hash for problem {}: {}", problem.id, e),
+        }
+    }
+
+    Ok(hashed)
+}
+
+/// List groups of near-duplicate problems across the whole library, largest
+/// first. `threshold` is the maximum hash distance (default 10 of 64 bits).
+#[tauri::command]
+pub async fn list_duplicate_clusters(
+    db: State<'_, Db>,
+    threshold: Option<u32>,
+) -> Result<String, String> {
+    let clusters = services::find_duplicate_clusters(
+        db.connection(),
+        threshold.unwrap_or(phash::DUPLICATE_THRESHOLD),
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    serde_json::to_string(&clusters).map_err(|e| e.to_string())
+}
+
+/// List problems in the same course that look like the given one, closest first
+#[tauri::command]
+pub async fn find_problem_duplicates(
+    db: State<'_, Db>,
+    problem_id: String,
+    threshold: Option<u32>,
+) -> Result<String, String> {
+    let problem_uuid = Uuid::parse_str(&problem_id).map_err(|e| e.to_string())?;
+
+    let problem = services::get_problem_by_id(db.connection(), problem_uuid)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Problem with id {} not found", problem_uuid))?;
+
+    let duplicates = match problem.image_hash {
+        Some(hash) => services::find_near_duplicates(
+            db.connection(),
+            problem.set_id,
+            hash,
+            threshold.unwrap_or(phash::DUPLICATE_THRESHOLD),
+            Some(problem.id),
+        )
+        .await
+        .map_err(|e| e.to_string())?,
+        None => Vec::new(),
+    };
+
+    serde_json::to_string(&duplicates).map_err(|e| e.to_string())
+}
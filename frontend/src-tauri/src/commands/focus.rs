@@ -0,0 +1,56 @@
+use crate::commands::study::parse_time;
+use crate::db::services::{self, PomodoroCount, StudyGrouping};
+use crate::db::Db;
+use crate::session::{control_focus_timer, FocusCommand, FocusStatus, FocusTimerState};
+use crate::settings::SettingsState;
+use chrono::Local;
+use std::time::Instant;
+use tauri::{AppHandle, State};
+
+#[tauri::command]
+pub async fn get_focus_timer(
+    timer: State<'_, FocusTimerState>,
+    settings: State<'_, SettingsState>,
+) -> Result<FocusStatus, String> {
+    let settings = settings.lock().unwrap().focus.clone();
+    Ok(timer.lock().unwrap().status(Instant::now(), &settings))
+}
+
+/// Starts a work phase, or resumes the paused phase; needs an active session
+#[tauri::command]
+pub async fn start_focus_timer(app: AppHandle) -> Result<FocusStatus, String> {
+    control_focus_timer(&app, FocusCommand::Start)
+}
+
+#[tauri::command]
+pub async fn pause_focus_timer(app: AppHandle) -> Result<FocusStatus, String> {
+    control_focus_timer(&app, FocusCommand::Pause)
+}
+
+/// Ends the current phase early; a skipped work phase isn't recorded
+#[tauri::command]
+pub async fn skip_focus_phase(app: AppHandle) -> Result<FocusStatus, String> {
+    control_focus_timer(&app, FocusCommand::Skip)
+}
+
+#[tauri::command]
+pub async fn stop_focus_timer(app: AppHandle) -> Result<FocusStatus, String> {
+    control_focus_timer(&app, FocusCommand::Stop)
+}
+
+/// Completed pomodoros per day (local calendar days), folder, course or set,
+/// optionally limited to `from`..`to` (RFC 3339)
+#[tauri::command]
+pub async fn get_pomodoro_counts(
+    db: State<'_, Db>,
+    group_by: StudyGrouping,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<Vec<PomodoroCount>, String> {
+    let from = from.as_deref().map(parse_time).transpose()?;
+    let to = to.as_deref().map(parse_time).transpose()?;
+
+    services::get_pomodoro_counts(db.connection(), group_by, from, to, &Local)
+        .await
+        .map_err(|e| e.to_string())
+}
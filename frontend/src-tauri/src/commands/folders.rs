@@ -1,6 +1,9 @@
+use crate::commands::sessions::reload_sessions;
+use crate::commands::storage::{clean_up_deleted_images, refresh_sidecars_in_scope};
+use crate::db::services::ImageScope;
 use crate::db::{services, Db};
 use serde::{Deserialize, Serialize};
-use tauri::State;
+use tauri::{AppHandle, State};
 use uuid::Uuid;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -73,10 +76,12 @@ pub async fn get_folders_by_user(
 
 #[tauri::command]
 pub async fn update_folder(
+    app: AppHandle,
     db: State<'_, Db>,
     request: UpdateFolderRequest,
 ) -> Result<String, String> {
     let folder_id = Uuid::parse_str(&request.id).map_err(|e| e.to_string())?;
+    let renamed = request.name.is_some();
 
     let folder = services::update_folder(
         db.connection(),
@@ -88,17 +93,28 @@ pub async fn update_folder(
     .await
     .map_err(|e| e.to_string())?;
 
+    // The sidecars of its images carry the folder name
+    if renamed {
+        refresh_sidecars_in_scope(&app, &db, ImageScope::Folder(folder_id)).await;
+    }
+
     serde_json::to_string(&folder).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub async fn delete_folder(db: State<'_, Db>, id: String) -> Result<String, String> {
+pub async fn delete_folder(app: AppHandle, db: State<'_, Db>, id: String) -> Result<String, String> {
     let folder_id = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
 
-    services::delete_folder(db.connection(), folder_id)
+    let images = services::delete_with_images(db.connection(), ImageScope::Folder(folder_id))
         .await
         .map_err(|e| e.to_string())?;
 
+    clean_up_deleted_images(&app, &db, images).await;
+    // Its sessions went with it
+    if let Err(e) = reload_sessions(&app).await {
+        log::warn!("{}", e);
+    }
+
     Ok("Folder deleted successfully".to_string())
 }
 
@@ -3,11 +3,27 @@ pub mod courses;
 pub mod sets;
 pub mod problems;
 pub mod problem_attempts;
+pub mod problem_images;
+pub mod duplicates;
+pub mod focus;
+pub mod search;
 pub mod sessions;
+pub mod settings;
+pub mod storage;
+pub mod study;
+pub mod thumbnails;
 
 pub use folders::*;
 pub use courses::*;
 pub use sets::*;
 pub use problems::*;
 pub use problem_attempts::*;
+pub use problem_images::*;
+pub use duplicates::*;
+pub use focus::*;
+pub use search::*;
 pub use sessions::*;
+pub use settings::*;
+pub use storage::*;
+pub use study::*;
+pub use thumbnails::*;
@@ -0,0 +1,322 @@
+use crate::commands::storage::{blob_hashes, refresh_sidecars, remove_unused_files};
+use crate::commands::thumbnails::ensure_thumbnails;
+use crate::db::entities::problem_images::{self, ImageRole};
+use crate::db::{services, Db};
+use crate::imaging::{phash, thumbnail};
+use crate::screenshot::{clean_up_image, write_image_data_url_to_local_fs};
+use crate::storage::resolve_image_file;
+use crate::settings::{CleanupSteps, SettingsState};
+use base64::{engine::general_purpose, Engine};
+use tauri::{AppHandle, Manager, State};
+use uuid::Uuid;
+
+/// Brings a problem's thumbnails and image hash in line with its current
+/// primary image, after images were added, removed or reordered.
+async fn refresh_primary_image(app: &AppHandle, db: &Db, problem_id: Uuid) -> Result<(), String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+
+    let Some(image) = services::get_primary_image(db.connection(), problem_id)
+        .await
+        .map_err(|e| e.to_string())?
+    else {
+        thumbnail::remove(&thumbnail::cache_dir(&app_data_dir), problem_id);
+        return services::set_problem_image_hash(db.connection(), problem_id, None)
+            .await
+            .map_err(|e| e.to_string());
+    };
+
+    ensure_thumbnails(app_data_dir.clone(), problem_id, image.path.clone(), true).await?;
+
+    let hash = tauri::async_runtime::spawn_blocking(move || {
+        let file = resolve_image_file(&app_data_dir, &image.path)
+            .ok_or_else(|| format!("Image file not found: {}", image.path))?;
+        let image = image::open(&file).map_err(|e| e.to_string())?;
+        Ok::<_, String>(phash::to_db(phash::dhash(&image)))
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    services::set_problem_image_hash(db.connection(), problem_id, Some(hash))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_problem_images(db: State<'_, Db>, problem_id: String) -> Result<String, String> {
+    let problem_uuid = Uuid::parse_str(&problem_id).map_err(|e| e.to_string())?;
+
+    let images = services::get_problem_images(db.connection(), problem_uuid)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    serde_json::to_string(&images).map_err(|e| e.to_string())
+}
+
+/// Attach an image (base64 or data URL) to a problem as its question, solution
+/// or work. It is encoded and stored like a capture.
+#[tauri::command]
+pub async fn add_problem_image(
+    app: AppHandle,
+    db: State<'_, Db>,
+    problem_id: String,
+    role: ImageRole,
+    image_url: String,
+) -> Result<String, String> {
+    let problem_uuid = Uuid::parse_str(&problem_id).map_err(|e| e.to_string())?;
+
+    services::get_problem_by_id(db.connection(), problem_uuid)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Problem with id {} not found", problem_uuid))?;
+
+    let previous_primary = services::get_primary_image(db.connection(), problem_uuid)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let encoding = {
+        let settings = app.state::<SettingsState>();
+        let settings = settings.lock().unwrap();
+        settings.encoding.clone()
+    };
+    let metadata =
+        services::get_image_metadata(db.connection(), problem_uuid, role, chrono::Utc::now())
+            .await
+            .map_err(|e| e.to_string())?;
+    let saved = write_image_data_url_to_local_fs(&app, &image_url, &encoding, metadata.as_ref())
+        .map_err(|e| e.to_string())?;
+
+    let image = services::add_problem_image(db.connection(), problem_uuid, role, saved.path)
+        .await
+        .map_err(|e| e.to_string())?;
+    refresh_sidecars(&app, &db, blob_hashes(std::slice::from_ref(&image))).await;
+
+    // A problem's first question image replaces a stand-in of another role
+    let primary = services::get_primary_image(db.connection(), problem_uuid)
+        .await
+        .map_err(|e| e.to_string())?;
+    if primary.map(|i| i.id) != previous_primary.map(|i| i.id) {
+        if let Err(e) = refresh_primary_image(&app, &db, problem_uuid).await {
+            log::warn!(
+                "Failed to refresh primary image of problem {}: {}",
+                problem_uuid,
+                e
+            );
+        }
+    }
+
+    serde_json::to_string(&image).map_err(|e| e.to_string())
+}
+
+/// Detach an image from its problem, deleting its files unless another image
+/// shares them
+#[tauri::command]
+pub async fn remove_problem_image(
+    app: AppHandle,
+    db: State<'_, Db>,
+    image_id: String,
+) -> Result<String, String> {
+    let image_uuid = Uuid::parse_str(&image_id).map_err(|e| e.to_string())?;
+
+    let image = services::get_problem_image_by_id(db.connection(), image_uuid)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Problem image with id {} not found", image_uuid))?;
+    let previous_primary = services::get_primary_image(db.connection(), image.problem_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    services::remove_problem_image(db.connection(), image_uuid)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // The row is gone, so a file left behind is only wasted space
+    let candidates = std::iter::once(image.path.clone())
+        .chain(image.original_path.clone())
+        .collect();
+    if let Err(e) = remove_unused_files(&app, &db, candidates).await {
+        log::warn!("Failed to delete files of image {}: {}", image_uuid, e);
+    }
+    refresh_sidecars(&app, &db, blob_hashes(std::slice::from_ref(&image))).await;
+
+    if previous_primary.map(|i| i.id) == Some(image_uuid) {
+        if let Err(e) = refresh_primary_image(&app, &db, image.problem_id).await {
+            log::warn!(
+                "Failed to refresh primary image of problem {}: {}",
+                image.problem_id,
+                e
+            );
+        }
+    }
+
+    Ok("Problem image removed successfully".to_string())
+}
+
+/// Put a problem's images in the given order; `image_ids` must list each of them once
+#[tauri::command]
+pub async fn reorder_problem_images(
+    app: AppHandle,
+    db: State<'_, Db>,
+    problem_id: String,
+    image_ids: Vec<String>,
+) -> Result<String, String> {
+    let problem_uuid = Uuid::parse_str(&problem_id).map_err(|e| e.to_string())?;
+    let image_uuids = image_ids
+        .iter()
+        .map(|id| Uuid::parse_str(id))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let previous_primary = services::get_primary_image(db.connection(), problem_uuid)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let images = services::reorder_problem_images(db.connection(), problem_uuid, image_uuids)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if services::primary_image(&images).map(|i| i.id) != previous_primary.map(|i| i.id) {
+        if let Err(e) = refresh_primary_image(&app, &db, problem_uuid).await {
+            log::warn!(
+                "Failed to refresh primary image of problem {}: {}",
+                problem_uuid,
+                e
+            );
+        }
+    }
+
+    serde_json::to_string(&images).map_err(|e| e.to_string())
+}
+
+/// Reads a stored image as base64
+async fn read_image_base64(app: &AppHandle, path: String) -> Result<String, String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let file = resolve_image_file(&app_data_dir, &path)
+            .ok_or_else(|| format!("Image file not found: {}", path))?;
+        let bytes = std::fs::read(&file).map_err(|e| e.to_string())?;
+        Ok(general_purpose::STANDARD.encode(bytes))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Points an image at a new file, deletes the file it is moving off if
+/// nothing else uses it, and refreshes the problem's primary image if needed
+async fn replace_image_files(
+    app: &AppHandle,
+    db: &Db,
+    image: problem_images::Model,
+    path: String,
+    original_path: Option<String>,
+) -> Result<problem_images::Model, String> {
+    let updated = services::set_problem_image_files(db.connection(), image.id, path, original_path)
+        .await
+        .map_err(|e| e.to_string())?;
+    // Blobs the image moves off and onto both change hands
+    let hashes = blob_hashes(&[image.clone(), updated.clone()]);
+
+    let dropped: Vec<String> = std::iter::once(image.path)
+        .chain(image.original_path)
+        .filter(|path| *path != updated.path && Some(path) != updated.original_path.as_ref())
+        .collect();
+    if let Err(e) = remove_unused_files(app, db, dropped).await {
+        log::warn!("Failed to delete old files of image {}: {}", image.id, e);
+    }
+    refresh_sidecars(app, db, hashes).await;
+
+    let primary = services::get_primary_image(db.connection(), image.problem_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    if primary.map(|i| i.id) == Some(image.id) {
+        if let Err(e) = refresh_primary_image(app, db, image.problem_id).await {
+            log::warn!(
+                "Failed to refresh primary image of problem {}: {}",
+                image.problem_id,
+                e
+            );
+        }
+    }
+
+    Ok(updated)
+}
+
+/// Run the cleanup pipeline on an image, with `steps` or else its course's
+/// steps. Always starts from the image as captured, so applying again with
+/// different steps doesn't stack them.
+#[tauri::command]
+pub async fn apply_image_cleanup(
+    app: AppHandle,
+    db: State<'_, Db>,
+    image_id: String,
+    steps: Option<CleanupSteps>,
+) -> Result<String, String> {
+    let image_uuid = Uuid::parse_str(&image_id).map_err(|e| e.to_string())?;
+    let image = services::get_problem_image_by_id(db.connection(), image_uuid)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Problem image with id {} not found", image_uuid))?;
+    let problem = services::get_problem_by_id(db.connection(), image.problem_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Problem with id {} not found", image.problem_id))?;
+
+    let steps = match steps {
+        Some(steps) => steps,
+        None => {
+            let set = services::get_set_by_id(db.connection(), problem.set_id)
+                .await
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| format!("Set with id {} not found", problem.set_id))?;
+            let settings = app.state::<SettingsState>();
+            let settings = settings.lock().unwrap();
+            settings.cleanup.steps_for_course(set.course_id)
+        }
+    };
+    if steps.is_empty() {
+        return Err("No cleanup steps are enabled".to_string());
+    }
+
+    let encoding = {
+        let settings = app.state::<SettingsState>();
+        let settings = settings.lock().unwrap();
+        settings.encoding.clone()
+    };
+
+    // The image as captured becomes the original on the first cleanup
+    let original = image.original_path.clone().unwrap_or_else(|| image.path.clone());
+    let cleaned = clean_up_image(read_image_base64(&app, original.clone()).await?, steps).await?;
+    let metadata = services::get_image_metadata(
+        db.connection(),
+        image.problem_id,
+        image.role,
+        image.created_at.and_utc(),
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    let saved = write_image_data_url_to_local_fs(&app, &cleaned, &encoding, metadata.as_ref())
+        .map_err(|e| e.to_string())?;
+
+    let updated = replace_image_files(&app, &db, image, saved.path, Some(original)).await?;
+    serde_json::to_string(&updated).map_err(|e| e.to_string())
+}
+
+/// Undo the cleanup of an image, going back to the image as captured
+#[tauri::command]
+pub async fn revert_image_cleanup(
+    app: AppHandle,
+    db: State<'_, Db>,
+    image_id: String,
+) -> Result<String, String> {
+    let image_uuid = Uuid::parse_str(&image_id).map_err(|e| e.to_string())?;
+    let image = services::get_problem_image_by_id(db.connection(), image_uuid)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Problem image with id {} not found", image_uuid))?;
+    let Some(original) = image.original_path.clone() else {
+        return Err("Image has not been cleaned up".to_string());
+    };
+
+    // The original is a stored file of its own, so it takes over as it is
+    let updated = replace_image_files(&app, &db, image, original, None).await?;
+    serde_json::to_string(&updated).map_err(|e| e.to_string())
+}
@@ -1,6 +1,8 @@
+use crate::commands::storage::{clean_up_deleted_images, refresh_sidecars_in_scope};
+use crate::db::services::ImageScope;
 use crate::db::{services, Db};
 use serde::{Deserialize, Serialize};
-use tauri::State;
+use tauri::{AppHandle, State};
 use uuid::Uuid;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -17,7 +19,6 @@ pub struct UpdateProblemRequest {
     pub id: String,
     pub title: Option<String>,
     pub description: Option<Option<String>>,
-    pub image_path: Option<Option<String>>,
     pub s3_image_key: Option<Option<String>>,
     pub confidence_level: Option<i32>,
     pub notes: Option<Option<String>>,
@@ -77,17 +78,18 @@ pub async fn get_problems_by_set(
 
 #[tauri::command]
 pub async fn update_problem(
+    app: AppHandle,
     db: State<'_, Db>,
     request: UpdateProblemRequest,
 ) -> Result<String, String> {
     let problem_id = Uuid::parse_str(&request.id).map_err(|e| e.to_string())?;
+    let renamed = request.title.is_some();
 
     let problem = services::update_problem(
         db.connection(),
         problem_id,
         request.title,
         request.description,
-        request.image_path,
         request.s3_image_key,
         request.confidence_level,
         request.notes,
@@ -95,6 +97,11 @@ pub async fn update_problem(
     .await
     .map_err(|e| e.to_string())?;
 
+    // Its images' sidecars carry the title
+    if renamed {
+        refresh_sidecars_in_scope(&app, &db, ImageScope::Problem(problem_id)).await;
+    }
+
     serde_json::to_string(&problem).map_err(|e| e.to_string())
 }
 
@@ -117,13 +124,15 @@ pub async fn update_problem_stats(
 }
 
 #[tauri::command]
-pub async fn delete_problem(db: State<'_, Db>, id: String) -> Result<String, String> {
+pub async fn delete_problem(app: AppHandle, db: State<'_, Db>, id: String) -> Result<String, String> {
     let problem_id = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
 
-    services::delete_problem(db.connection(), problem_id)
+    let images = services::delete_with_images(db.connection(), ImageScope::Problem(problem_id))
         .await
         .map_err(|e| e.to_string())?;
 
+    clean_up_deleted_images(&app, &db, images).await;
+
     Ok("Problem deleted successfully".to_string())
 }
 
@@ -163,7 +172,6 @@ mod tests {
             id: "550e8400-e29b-41d4-a716-446655440000".to_string(),
             title: Some("Updated Title".to_string()),
             description: Some(Some("Updated Description".to_string())),
-            image_path: None,
             s3_image_key: None,
             confidence_level: Some(5),
             notes: Some(Some("Some notes".to_string())),
@@ -0,0 +1,50 @@
+use crate::db::{services, Db};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use uuid::Uuid;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchProblemsRequest {
+    pub query: String,
+    pub folder_id: Option<String>,
+    pub course_id: Option<String>,
+    pub set_id: Option<String>,
+    pub limit: Option<u64>,
+}
+
+fn parse_optional_uuid(id: Option<String>) -> Result<Option<Uuid>, String> {
+    id.map(|id| Uuid::parse_str(&id).map_err(|e| e.to_string()))
+        .transpose()
+}
+
+#[tauri::command]
+pub async fn search_problems(
+    db: State<'_, Db>,
+    request: SearchProblemsRequest,
+) -> Result<String, String> {
+    let scope = services::SearchScope {
+        folder_id: parse_optional_uuid(request.folder_id)?,
+        course_id: parse_optional_uuid(request.course_id)?,
+        set_id: parse_optional_uuid(request.set_id)?,
+    };
+
+    let hits = services::search_problems(db.connection(), &request.query, scope, request.limit)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    serde_json::to_string(&hits).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_optional_uuid() {
+        assert_eq!(parse_optional_uuid(None).unwrap(), None);
+        assert!(parse_optional_uuid(Some("550e8400-e29b-41d4-a716-446655440000".to_string()))
+            .unwrap()
+            .is_some());
+        assert!(parse_optional_uuid(Some("not-a-uuid".to_string())).is_err());
+    }
+}
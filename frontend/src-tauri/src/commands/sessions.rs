@@ -1,7 +1,8 @@
+use crate::commands::study::record_active_session;
 use crate::db::{services, Db};
-use crate::session::{SessionManagerState, SessionState};
+use crate::session::{SessionManager, SessionManagerState, SessionState};
 use serde::{Deserialize, Serialize};
-use tauri::{AppHandle, Manager, State};
+use tauri::{AppHandle, Manager, Runtime, State};
 use uuid::Uuid;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -74,8 +75,11 @@ pub async fn get_all_sessions(
 
     let mut responses = Vec::new();
     for session in sessions.iter() {
-        let response = SessionResponse::from_session_state(session, db.connection()).await?;
-        responses.push(response);
+        // One unreadable session shouldn't hide the others
+        match SessionResponse::from_session_state(session, db.connection()).await {
+            Ok(response) => responses.push(response),
+            Err(e) => log::warn!("Skipping session {}: {}", session.id, e),
+        }
     }
 
     Ok(responses)
@@ -103,22 +107,16 @@ pub async fn get_active_session(
 /// Start an existing session by ID
 #[tauri::command]
 pub async fn start_session(
-    session_manager: State<'_, SessionManagerState>,
+    db: State<'_, Db>,
     app: AppHandle,
     session_id: String,
 ) -> Result<(), String> {
     let session_uuid = Uuid::parse_str(&session_id).map_err(|e| e.to_string())?;
 
-    let mut manager = session_manager.lock().unwrap();
-    manager.start_session(session_uuid)?;
-
-    // Persist to file
-    let sessions_path = get_sessions_file_path(&app)?;
-    manager
-        .save_to_file(&sessions_path)
-        .map_err(|e| format!("Failed to save sessions: {}", e))?;
-
-    Ok(())
+    services::start_session(db.connection(), session_uuid)
+        .await
+        .map_err(|e| e.to_string())?;
+    sessions_changed(&app).await
 }
 
 /// Create a new session with folder/course/set (creates entities if they don't exist)
@@ -158,34 +156,29 @@ pub async fn create_and_start_session(
         request.folder_name, request.course_name, request.set_name
     );
 
-    // Create session
-    let session = {
-        let mut manager = session_manager.lock().unwrap();
-
-        // Check if a session with the same folder/course/set already exists
-        if manager.session_exists_for_context(folder.id, course.id, set.id) {
-            return Err(format!(
-                "A session for '{}' already exists. Please select a different folder/course/set combination.",
-                session_name
-            ));
-        }
+    // Check if a session with the same folder/course/set already exists
+    if session_manager
+        .lock()
+        .unwrap()
+        .session_exists_for_context(folder.id, course.id, set.id)
+    {
+        return Err(format!(
+            "A session for '{}' already exists. Please select a different folder/course/set combination.",
+            session_name
+        ));
+    }
 
-        let session = manager.create_session(
-            session_name,
-            folder.id,
-            course.id,
-            set.id,
-            true, // Start immediately
-        );
-
-        // Persist to file
-        let sessions_path = get_sessions_file_path(&app)?;
-        manager
-            .save_to_file(&sessions_path)
-            .map_err(|e| format!("Failed to save sessions: {}", e))?;
-
-        session
-    };
+    // Create session
+    let session = SessionState::new(session_name, folder.id, course.id, set.id);
+    let session: SessionState = services::create_session(
+        db.connection(),
+        session.into(),
+        true, // Start immediately
+    )
+    .await
+    .map_err(|e| e.to_string())?
+    .into();
+    sessions_changed(&app).await?;
 
     // Create response
     let response = SessionResponse::from_session_state(&session, db.connection()).await?;
@@ -194,51 +187,59 @@ pub async fn create_and_start_session(
 
 /// End the current session
 #[tauri::command]
-pub async fn end_session(
-    session_manager: State<'_, SessionManagerState>,
-    app: AppHandle,
-) -> Result<(), String> {
-    let mut manager = session_manager.lock().unwrap();
-    manager.end_session();
-
-    // Persist to file
-    let sessions_path = get_sessions_file_path(&app)?;
-    manager
-        .save_to_file(&sessions_path)
-        .map_err(|e| format!("Failed to save sessions: {}", e))?;
-
-    Ok(())
+pub async fn end_session(app: AppHandle) -> Result<(), String> {
+    end_active_session(&app).await
 }
 
 /// Delete a saved session
 #[tauri::command]
 pub async fn delete_session(
-    session_manager: State<'_, SessionManagerState>,
+    db: State<'_, Db>,
     app: AppHandle,
     session_id: String,
 ) -> Result<(), String> {
     let session_uuid = Uuid::parse_str(&session_id).map_err(|e| e.to_string())?;
 
-    let mut manager = session_manager.lock().unwrap();
-    manager.delete_session(session_uuid)?;
+    services::delete_session(db.connection(), session_uuid)
+        .await
+        .map_err(|e| e.to_string())?;
+    sessions_changed(&app).await
+}
 
-    // Persist to file
-    let sessions_path = get_sessions_file_path(&app)?;
-    manager
-        .save_to_file(&sessions_path)
-        .map_err(|e| format!("Failed to save sessions: {}", e))?;
+/// Ends the active session, if any. Used by the command, the tray and the
+/// idle monitor.
+pub(crate) async fn end_active_session<R: Runtime>(app: &AppHandle<R>) -> Result<(), String> {
+    let db = app
+        .try_state::<Db>()
+        .ok_or_else(|| "Database is not initialized".to_string())?;
+    services::end_session(db.connection())
+        .await
+        .map_err(|e| format!("Failed to end session: {}", e))?;
+    sessions_changed(app).await
+}
 
-    Ok(())
+/// Reloads the session manager after a session was written to the database,
+/// and moves the study log on to the active session
+async fn sessions_changed<R: Runtime>(app: &AppHandle<R>) -> Result<(), String> {
+    reload_sessions(app).await?;
+
+    // Starting, switching and ending sessions is what the study log records
+    record_active_session(app).await
 }
 
-/// Helper function to get the sessions file path
-fn get_sessions_file_path(app: &AppHandle) -> Result<std::path::PathBuf, String> {
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+/// Replaces the session manager's state with the database's. Needed at startup,
+/// after every session write, and after deleting a folder, course or set
+/// removed some of its sessions.
+pub(crate) async fn reload_sessions<R: Runtime>(app: &AppHandle<R>) -> Result<(), String> {
+    let db = app
+        .try_state::<Db>()
+        .ok_or_else(|| "Database is not initialized".to_string())?;
+    let loaded = SessionManager::load_from_db(db.connection())
+        .await
+        .map_err(|e| format!("Failed to load sessions: {}", e))?;
 
-    Ok(app_data_dir.join("sessions.json"))
+    *app.state::<SessionManagerState>().lock().unwrap() = loaded;
+    Ok(())
 }
 
 #[cfg(test)]
@@ -2,7 +2,7 @@
 mod session_commands_tests {
     use crate::commands::sessions::{CreateSessionRequest, SessionResponse};
     use crate::db::services;
-    use crate::session::SessionManager;
+    use crate::session::{SessionManager, SessionState};
     use migration::{Migrator, MigratorTrait};
     use sea_orm::Database;
     use tempfile::tempdir;
@@ -26,6 +26,32 @@ mod session_commands_tests {
         dir.path().join("test_sessions.json")
     }
 
+    /// Saves a session for a new set, the way the create command does
+    async fn create_session(
+        db: &sea_orm::DatabaseConnection,
+        name: &str,
+        start: bool,
+    ) -> SessionState {
+        let user_id = services::get_or_create_default_user(db)
+            .await
+            .expect("Failed to create user");
+        let folder = services::find_or_create_folder(db, user_id, "Math".to_string())
+            .await
+            .expect("Failed to create folder");
+        let course = services::find_or_create_course(db, folder.id, "Calculus".to_string())
+            .await
+            .expect("Failed to create course");
+        let set = services::find_or_create_set(db, course.id, name.to_string())
+            .await
+            .expect("Failed to create set");
+
+        let session = SessionState::new(name.to_string(), folder.id, course.id, set.id);
+        services::create_session(db, session.into(), start)
+            .await
+            .expect("Failed to create session")
+            .into()
+    }
+
     #[tokio::test]
     async fn test_create_and_start_session_new_entities() {
         let db = setup_test_db().await;
@@ -62,14 +88,10 @@ mod session_commands_tests {
             request.folder_name, request.course_name, request.set_name
         );
 
-        let mut session_manager = SessionManager::new();
-        let session = session_manager.create_session(
-            session_name.clone(),
-            folder.id,
-            course.id,
-            set.id,
-            true,
-        );
+        let session = SessionState::new(session_name.clone(), folder.id, course.id, set.id);
+        services::create_session(&db, session.clone().into(), true)
+            .await
+            .expect("Failed to create session");
 
         // Verify session was created correctly
         assert_eq!(session.name, session_name);
@@ -78,8 +100,9 @@ mod session_commands_tests {
         assert_eq!(session.set_id, set.id);
 
         // Verify session is active
+        let session_manager = SessionManager::load_from_db(&db).await.unwrap();
         assert_eq!(session_manager.active_session_id, Some(session.id));
-        assert_eq!(session_manager.sessions.len(), 1);
+        assert_eq!(session_manager.sessions, vec![session]);
     }
 
     #[tokio::test]
@@ -144,16 +167,17 @@ mod session_commands_tests {
             .await
             .expect("Failed to create set");
 
-        let mut session_manager = SessionManager::new();
-
         // Create first session
-        session_manager.create_session(
+        let session = SessionState::new(
             "Physics / Mechanics / Kinematics".to_string(),
             folder.id,
             course.id,
             set.id,
-            true,
         );
+        services::create_session(&db, session.into(), true)
+            .await
+            .expect("Failed to create session");
+        let session_manager = SessionManager::load_from_db(&db).await.unwrap();
 
         // Try to create duplicate session - should detect it exists
         let duplicate_exists =
@@ -166,18 +190,8 @@ mod session_commands_tests {
 
     #[tokio::test]
     async fn test_start_session_updates_timestamp() {
-        let mut session_manager = SessionManager::new();
-        let folder_id = uuid::Uuid::new_v4();
-        let course_id = uuid::Uuid::new_v4();
-        let set_id = uuid::Uuid::new_v4();
-
-        let session = session_manager.create_session(
-            "Test Session".to_string(),
-            folder_id,
-            course_id,
-            set_id,
-            false,
-        );
+        let db = setup_test_db().await;
+        let session = create_session(&db, "Test Session", false).await;
 
         let initial_last_used = session.last_used;
 
@@ -185,11 +199,12 @@ mod session_commands_tests {
         std::thread::sleep(std::time::Duration::from_millis(10));
 
         // Start the session
-        session_manager
-            .start_session(session.id)
+        services::start_session(&db, session.id)
+            .await
             .expect("Failed to start session");
 
         // Verify last_used was updated
+        let session_manager = SessionManager::load_from_db(&db).await.unwrap();
         let updated_session = session_manager.get_active_session().unwrap();
         assert!(
             updated_session.last_used > initial_last_used,
@@ -204,38 +219,33 @@ mod session_commands_tests {
 
     #[tokio::test]
     async fn test_start_session_nonexistent_id() {
-        let mut session_manager = SessionManager::new();
+        let db = setup_test_db().await;
         let nonexistent_id = uuid::Uuid::new_v4();
 
-        let result = session_manager.start_session(nonexistent_id);
+        let result = services::start_session(&db, nonexistent_id).await;
         assert!(result.is_err(), "Should return error for nonexistent ID");
         assert!(result
             .unwrap_err()
+            .to_string()
             .contains("not found"));
     }
 
     #[tokio::test]
     async fn test_end_session() {
-        let mut session_manager = SessionManager::new();
-        let folder_id = uuid::Uuid::new_v4();
-        let course_id = uuid::Uuid::new_v4();
-        let set_id = uuid::Uuid::new_v4();
-
-        let session = session_manager.create_session(
-            "Test Session".to_string(),
-            folder_id,
-            course_id,
-            set_id,
-            true,
-        );
+        let db = setup_test_db().await;
+        let session = create_session(&db, "Test Session", true).await;
 
         // Verify session is active
+        let session_manager = SessionManager::load_from_db(&db).await.unwrap();
         assert!(session_manager.get_active_session().is_some());
 
         // End the session
-        session_manager.end_session();
+        services::end_session(&db)
+            .await
+            .expect("Failed to end session");
 
         // Verify session is no longer active
+        let session_manager = SessionManager::load_from_db(&db).await.unwrap();
         assert!(session_manager.get_active_session().is_none());
 
         // Verify session still exists in saved sessions
@@ -245,68 +255,52 @@ mod session_commands_tests {
 
     #[tokio::test]
     async fn test_delete_session() {
-        let mut session_manager = SessionManager::new();
-        let folder_id = uuid::Uuid::new_v4();
-        let course_id = uuid::Uuid::new_v4();
-        let set_id = uuid::Uuid::new_v4();
-
-        let session = session_manager.create_session(
-            "Test Session".to_string(),
-            folder_id,
-            course_id,
-            set_id,
-            false,
-        );
+        let db = setup_test_db().await;
+        let session = create_session(&db, "Test Session", false).await;
 
         // Verify session exists
+        let session_manager = SessionManager::load_from_db(&db).await.unwrap();
         assert_eq!(session_manager.sessions.len(), 1);
 
         // Delete the session
-        session_manager
-            .delete_session(session.id)
+        services::delete_session(&db, session.id)
+            .await
             .expect("Failed to delete session");
 
         // Verify session was removed
+        let session_manager = SessionManager::load_from_db(&db).await.unwrap();
         assert_eq!(session_manager.sessions.len(), 0);
     }
 
     #[tokio::test]
     async fn test_delete_active_session_clears_active_id() {
-        let mut session_manager = SessionManager::new();
-        let folder_id = uuid::Uuid::new_v4();
-        let course_id = uuid::Uuid::new_v4();
-        let set_id = uuid::Uuid::new_v4();
-
-        let session = session_manager.create_session(
-            "Test Session".to_string(),
-            folder_id,
-            course_id,
-            set_id,
-            true, // Start immediately
-        );
+        let db = setup_test_db().await;
+        let session = create_session(&db, "Test Session", true).await; // Start immediately
 
         // Verify session is active
+        let session_manager = SessionManager::load_from_db(&db).await.unwrap();
         assert!(session_manager.get_active_session().is_some());
         assert_eq!(session_manager.active_session_id, Some(session.id));
 
         // Delete the active session
-        session_manager
-            .delete_session(session.id)
+        services::delete_session(&db, session.id)
+            .await
             .expect("Failed to delete session");
 
         // Verify active_session_id was cleared
+        let session_manager = SessionManager::load_from_db(&db).await.unwrap();
         assert!(session_manager.get_active_session().is_none());
         assert_eq!(session_manager.active_session_id, None);
     }
 
     #[tokio::test]
     async fn test_delete_session_nonexistent_id() {
-        let mut session_manager = SessionManager::new();
+        let db = setup_test_db().await;
         let nonexistent_id = uuid::Uuid::new_v4();
 
-        let result = session_manager.delete_session(nonexistent_id);
+        let result = services::delete_session(&db, nonexistent_id).await;
         assert!(result.is_err(), "Should return error for nonexistent ID");
-        assert!(result.unwrap_err().contains("not found"));
+        assert!(result.unwrap_err().to_string().contains("not found"));
     }
 
     #[tokio::test]
@@ -318,22 +312,14 @@ mod session_commands_tests {
 
     #[tokio::test]
     async fn test_get_all_sessions_multiple() {
-        let mut session_manager = SessionManager::new();
+        let db = setup_test_db().await;
 
         // Create multiple sessions
         for i in 1..=3 {
-            let folder_id = uuid::Uuid::new_v4();
-            let course_id = uuid::Uuid::new_v4();
-            let set_id = uuid::Uuid::new_v4();
-            session_manager.create_session(
-                format!("Session {}", i),
-                folder_id,
-                course_id,
-                set_id,
-                false,
-            );
+            create_session(&db, &format!("Session {}", i), false).await;
         }
 
+        let session_manager = SessionManager::load_from_db(&db).await.unwrap();
         let sessions = session_manager.get_all_sessions();
         assert_eq!(sessions.len(), 3, "Should return all sessions");
     }
@@ -349,19 +335,10 @@ mod session_commands_tests {
 
     #[tokio::test]
     async fn test_get_active_session_some() {
-        let mut session_manager = SessionManager::new();
-        let folder_id = uuid::Uuid::new_v4();
-        let course_id = uuid::Uuid::new_v4();
-        let set_id = uuid::Uuid::new_v4();
-
-        let session = session_manager.create_session(
-            "Active Session".to_string(),
-            folder_id,
-            course_id,
-            set_id,
-            true,
-        );
+        let db = setup_test_db().await;
+        let session = create_session(&db, "Active Session", true).await;
 
+        let session_manager = SessionManager::load_from_db(&db).await.unwrap();
         let active = session_manager.get_active_session();
         assert!(active.is_some(), "Should return active session");
         assert_eq!(active.unwrap().id, session.id);
@@ -377,18 +354,22 @@ mod session_commands_tests {
         let course_id = uuid::Uuid::new_v4();
         let set_id = uuid::Uuid::new_v4();
 
-        session_manager.create_session(
+        let session = SessionState::new(
             "Persistent Session".to_string(),
             folder_id,
             course_id,
             set_id,
-            true,
         );
+        session_manager.active_session_id = Some(session.id);
+        session_manager.sessions.push(session);
 
-        // Save to file
-        session_manager
-            .save_to_file(&sessions_path)
-            .expect("Failed to save sessions");
+        // Save to file, as earlier versions did
+        std::fs::create_dir_all(sessions_path.parent().unwrap()).unwrap();
+        std::fs::write(
+            &sessions_path,
+            serde_json::to_string(&session_manager).unwrap(),
+        )
+        .expect("Failed to save sessions");
 
         // Load from file
         let loaded_manager =
@@ -483,13 +464,11 @@ mod session_commands_tests {
                 .expect("Failed to create set");
 
         // Create session state
-        let mut session_manager = SessionManager::new();
-        let session = session_manager.create_session(
+        let session = SessionState::new(
             "Test Folder / Test Course / Test Set".to_string(),
             folder.id,
             course.id,
             set.id,
-            false,
         );
 
         // Convert to response
@@ -529,13 +508,11 @@ mod session_commands_tests {
                 .await
                 .expect("Failed to create set");
 
-        let mut session_manager = SessionManager::new();
-        let session = session_manager.create_session(
+        let session = SessionState::new(
             "Test Session".to_string(),
             folder.id,
             course.id,
             set.id,
-            false,
         );
 
         // Manually delete the set from DB (simulate deleted entity)
@@ -623,19 +600,10 @@ mod session_commands_tests {
         assert_eq!(session_manager.sessions.len(), 0);
         
         // Should be able to create new sessions after error recovery
-        let folder_id = uuid::Uuid::new_v4();
-        let course_id = uuid::Uuid::new_v4();
-        let set_id = uuid::Uuid::new_v4();
-        
-        let mut manager = session_manager;
-        manager.create_session(
-            "Test Session".to_string(),
-            folder_id,
-            course_id,
-            set_id,
-            true,
-        );
-        
+        let db = setup_test_db().await;
+        create_session(&db, "Test Session", true).await;
+
+        let manager = SessionManager::load_from_db(&db).await.unwrap();
         assert_eq!(manager.sessions.len(), 1);
         assert!(manager.get_active_session().is_some());
     }
@@ -1,6 +1,9 @@
+use crate::commands::sessions::reload_sessions;
+use crate::commands::storage::{clean_up_deleted_images, refresh_sidecars_in_scope};
+use crate::db::services::ImageScope;
 use crate::db::{services, Db};
 use serde::{Deserialize, Serialize};
-use tauri::State;
+use tauri::{AppHandle, State};
 use uuid::Uuid;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -66,10 +69,12 @@ pub async fn get_sets_by_course(
 
 #[tauri::command]
 pub async fn update_set(
+    app: AppHandle,
     db: State<'_, Db>,
     request: UpdateSetRequest,
 ) -> Result<String, String> {
     let set_id = Uuid::parse_str(&request.id).map_err(|e| e.to_string())?;
+    let renamed = request.name.is_some();
 
     let set = services::update_set(
         db.connection(),
@@ -81,16 +86,27 @@ pub async fn update_set(
     .await
     .map_err(|e| e.to_string())?;
 
+    // The sidecars of its images carry the set name
+    if renamed {
+        refresh_sidecars_in_scope(&app, &db, ImageScope::Set(set_id)).await;
+    }
+
     serde_json::to_string(&set).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub async fn delete_set(db: State<'_, Db>, id: String) -> Result<String, String> {
+pub async fn delete_set(app: AppHandle, db: State<'_, Db>, id: String) -> Result<String, String> {
     let set_id = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
 
-    services::delete_set(db.connection(), set_id)
+    let images = services::delete_with_images(db.connection(), ImageScope::Set(set_id))
         .await
         .map_err(|e| e.to_string())?;
 
+    clean_up_deleted_images(&app, &db, images).await;
+    // Its sessions went with it
+    if let Err(e) = reload_sessions(&app).await {
+        log::warn!("{}", e);
+    }
+
     Ok("Set deleted successfully".to_string())
 }
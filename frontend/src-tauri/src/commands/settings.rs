@@ -0,0 +1,116 @@
+use crate::problem_naming::namer::validate_strategy;
+use crate::settings::{AppSettings, CleanupSteps, NamingStrategy, SettingsState};
+use tauri::{AppHandle, Manager, State};
+use uuid::Uuid;
+
+/// Get the current application settings
+#[tauri::command]
+pub async fn get_settings(settings: State<'_, SettingsState>) -> Result<AppSettings, String> {
+    let settings = settings.lock().unwrap();
+    Ok(settings.clone())
+}
+
+/// Replace the application settings and persist them
+#[tauri::command]
+pub async fn update_settings(
+    settings: State<'_, SettingsState>,
+    app: AppHandle,
+    new_settings: AppSettings,
+) -> Result<AppSettings, String> {
+    validate_strategy(&new_settings.naming.strategy)?;
+    for strategy in new_settings.naming.set_strategies.values() {
+        validate_strategy(strategy)?;
+    }
+    new_settings.encoding.validate()?;
+    new_settings.idle.validate()?;
+    new_settings.focus.validate()?;
+
+    let mut current = settings.lock().unwrap();
+    #[cfg(desktop)]
+    if new_settings.capture.all_monitors_shortcut != current.capture.all_monitors_shortcut {
+        crate::screenshot::set_all_monitors_shortcut(
+            &app,
+            new_settings.capture.all_monitors_shortcut,
+        )?;
+    }
+    *current = new_settings;
+
+    // Persist to file
+    let settings_path = get_settings_file_path(&app)?;
+    current
+        .save_to_file(&settings_path)
+        .map_err(|e| format!("Failed to save settings: {}", e))?;
+
+    Ok(current.clone())
+}
+
+/// Set or clear (`None`) the naming strategy override for a set
+#[tauri::command]
+pub async fn set_naming_strategy_for_set(
+    settings: State<'_, SettingsState>,
+    app: AppHandle,
+    set_id: String,
+    strategy: Option<NamingStrategy>,
+) -> Result<AppSettings, String> {
+    let set_uuid = Uuid::parse_str(&set_id).map_err(|e| e.to_string())?;
+    if let Some(strategy) = &strategy {
+        validate_strategy(strategy)?;
+    }
+
+    let mut current = settings.lock().unwrap();
+    match strategy {
+        Some(strategy) => {
+            current.naming.set_strategies.insert(set_uuid, strategy);
+        }
+        None => {
+            current.naming.set_strategies.remove(&set_uuid);
+        }
+    }
+
+    // Persist to file
+    let settings_path = get_settings_file_path(&app)?;
+    current
+        .save_to_file(&settings_path)
+        .map_err(|e| format!("Failed to save settings: {}", e))?;
+
+    Ok(current.clone())
+}
+
+/// Set or clear (`None`) the cleanup steps applied to a course's captures
+#[tauri::command]
+pub async fn set_cleanup_steps_for_course(
+    settings: State<'_, SettingsState>,
+    app: AppHandle,
+    course_id: String,
+    steps: Option<CleanupSteps>,
+) -> Result<AppSettings, String> {
+    let course_uuid = Uuid::parse_str(&course_id).map_err(|e| e.to_string())?;
+
+    let mut current = settings.lock().unwrap();
+    match steps.filter(|steps| !steps.is_empty()) {
+        Some(steps) => {
+            current.cleanup.course_steps.insert(course_uuid, steps);
+        }
+        None => {
+            current.cleanup.course_steps.remove(&course_uuid);
+        }
+    }
+
+    // Persist to file
+    let settings_path = get_settings_file_path(&app)?;
+    current
+        .save_to_file(&settings_path)
+        .map_err(|e| format!("Failed to save settings: {}", e))?;
+
+    Ok(current.clone())
+}
+
+/// Helper function to get the settings file path
+pub(crate) fn get_settings_file_path(app: &AppHandle) -> Result<std::path::PathBuf, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+
+    Ok(app_data_dir.join("settings.json"))
+}
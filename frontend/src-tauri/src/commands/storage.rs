@@ -0,0 +1,259 @@
+use crate::db::entities::problem_images;
+use crate::db::services::ImageScope;
+use crate::db::{services, Db};
+use crate::imaging::thumbnail;
+use crate::storage::cleanup::{self, PendingDeletion};
+use crate::storage::gc::{self, GcReport};
+use crate::storage::recovery::{self, RecoveryReport};
+use crate::storage::{self, sidecar};
+use std::collections::HashSet;
+use std::path::Path;
+use std::time::SystemTime;
+use tauri::{AppHandle, Manager, State};
+use uuid::Uuid;
+
+/// Paths of `candidates` that no image uses any more
+async fn unreferenced_paths(db: &Db, candidates: Vec<String>) -> Result<Vec<String>, String> {
+    let mut paths = Vec::new();
+    for path in candidates {
+        if paths.contains(&path) {
+            continue;
+        }
+        let referenced = services::is_image_path_referenced(db.connection(), &path)
+            .await
+            .map_err(|e| e.to_string())?;
+        if !referenced {
+            paths.push(path);
+        }
+    }
+    Ok(paths)
+}
+
+/// Deletes those of `candidates` no image uses any more, e.g. files an
+/// image was just moved off. Files written within the GC grace period are
+/// left to garbage collection, as a capture may be reusing them. Files that
+/// can't be removed go in the retry record.
+pub(crate) async fn remove_unused_files(
+    app: &AppHandle,
+    db: &Db,
+    candidates: Vec<String>,
+) -> Result<(), String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    let paths = unreferenced_paths(db, candidates).await?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let failed = cleanup::delete_image_files(&app_data_dir, &paths, SystemTime::now());
+        log_and_record_failures(&app_data_dir, &failed);
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Deletes the files and thumbnails of images a delete just took with it,
+/// as returned by `services::delete_with_images`. The delete has committed,
+/// so failures are only logged; files that can't be removed now are retried
+/// later. Files another image still shares are kept.
+pub(crate) async fn clean_up_deleted_images(
+    app: &AppHandle,
+    db: &Db,
+    images: Vec<problem_images::Model>,
+) {
+    let hashes = blob_hashes(&images);
+    if let Err(e) = remove_deleted_images(app, db, images).await {
+        log::warn!("Failed to clean up deleted images: {}", e);
+    }
+    refresh_sidecars(app, db, hashes).await;
+}
+
+async fn remove_deleted_images(
+    app: &AppHandle,
+    db: &Db,
+    images: Vec<problem_images::Model>,
+) -> Result<(), String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+
+    let problem_ids: HashSet<Uuid> = images.iter().map(|image| image.problem_id).collect();
+    tauri::async_runtime::spawn_blocking(move || {
+        let cache_dir = thumbnail::cache_dir(&app_data_dir);
+        for problem_id in problem_ids {
+            thumbnail::remove(&cache_dir, problem_id);
+        }
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let candidates = images
+        .into_iter()
+        .flat_map(|image| std::iter::once(image.path).chain(image.original_path))
+        .collect();
+    remove_unused_files(app, db, candidates).await
+}
+
+/// Hashes of the blobs `images` use, as current files or originals
+pub(crate) fn blob_hashes(images: &[problem_images::Model]) -> HashSet<String> {
+    images
+        .iter()
+        .flat_map(|image| std::iter::once(&image.path).chain(image.original_path.as_ref()))
+        .filter_map(|path| storage::blob_hash(Path::new(path)))
+        .map(str::to_string)
+        .collect()
+}
+
+async fn update_sidecars(
+    app: &AppHandle,
+    db: &Db,
+    hashes: Option<HashSet<String>>,
+) -> Result<usize, String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    let uses = services::get_blob_uses(db.connection(), hashes.as_ref())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        // Without a list, every blob in use and every sidecar on disk
+        let hashes = hashes.unwrap_or_else(|| {
+            let mut hashes = sidecar::sidecar_hashes(&app_data_dir);
+            hashes.extend(uses.iter().map(|u| u.hash.clone()));
+            hashes
+        });
+        sidecar::update_sidecars(&app_data_dir, &hashes, uses).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Rewrites the sidecars of `hashes` from the database, after the images
+/// using those blobs changed. Sidecars are only read by a recovery and are
+/// synced at startup, so a failure is only logged.
+pub(crate) async fn refresh_sidecars(app: &AppHandle, db: &Db, hashes: HashSet<String>) {
+    if hashes.is_empty() {
+        return;
+    }
+    if let Err(e) = update_sidecars(app, db, Some(hashes)).await {
+        log::warn!("Failed to update image sidecars: {}", e);
+    }
+}
+
+/// Rewrites the sidecars of every image in `scope`, after it was renamed
+pub(crate) async fn refresh_sidecars_in_scope(app: &AppHandle, db: &Db, scope: ImageScope) {
+    match services::get_images_in_scope(db.connection(), scope).await {
+        Ok(images) => refresh_sidecars(app, db, blob_hashes(&images)).await,
+        Err(e) => log::warn!("Failed to update image sidecars of {:?}: {}", scope, e),
+    }
+}
+
+/// Brings every sidecar in line with the database and removes those of blobs
+/// no image uses. Returns how many files changed.
+pub(crate) async fn sync_sidecars(app: &AppHandle) -> Result<usize, String> {
+    let db = app.state::<Db>();
+    update_sidecars(app, &db, None).await
+}
+
+fn log_and_record_failures(app_data_dir: &Path, failed: &[PendingDeletion]) {
+    for failure in failed {
+        log::warn!(
+            "Failed to delete image file {}, will retry: {}",
+            failure.path,
+            failure.error
+        );
+    }
+    if let Err(e) = cleanup::record_pending(app_data_dir, failed.to_vec()) {
+        log::error!("Failed to write the image deletion retry record: {}", e);
+    }
+}
+
+/// Retries deletions that failed before, skipping files an image has started
+/// using again since. Returns how many files are still pending.
+pub(crate) async fn retry_pending_deletions(app: &AppHandle) -> Result<usize, String> {
+    let db = app.state::<Db>();
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+
+    let pending = cleanup::read_pending(&app_data_dir);
+    if pending.is_empty() {
+        return Ok(0);
+    }
+    let paths = unreferenced_paths(&db, pending.into_iter().map(|p| p.path).collect()).await?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let failed = cleanup::delete_image_files(&app_data_dir, &paths, SystemTime::now());
+        cleanup::write_pending(&app_data_dir, &failed).map_err(|e| e.to_string())?;
+        Ok(failed.len())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Delete stored images no problem uses any more. With `dry_run`, only
+/// reports what would be deleted.
+#[tauri::command]
+pub async fn collect_image_garbage(
+    app: AppHandle,
+    db: State<'_, Db>,
+    dry_run: Option<bool>,
+) -> Result<GcReport, String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+
+    let referenced = services::get_referenced_blob_hashes(db.connection())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let dry_run = dry_run.unwrap_or(false);
+    let report = tauri::async_runtime::spawn_blocking(move || {
+        gc::collect_garbage(&app_data_dir, &referenced, dry_run, SystemTime::now())
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    log::info!(
+        "Image garbage collection{}: {} of {} blobs unreferenced, {} bytes freed",
+        if dry_run { " (dry run)" } else { "" },
+        report.removed.len(),
+        report.scanned,
+        report.bytes_freed
+    );
+    Ok(report)
+}
+
+/// Rebuild folders, courses, sets and problems from the image store's
+/// sidecars, e.g. after the database was lost. Existing rows are kept; only
+/// what is missing is added.
+#[tauri::command]
+pub async fn recover_from_images(
+    app: AppHandle,
+    db: State<'_, Db>,
+) -> Result<RecoveryReport, String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+
+    let (images, mut report) = tauri::async_runtime::spawn_blocking(move || {
+        let mut report = RecoveryReport::default();
+        let images = recovery::scan_images(&app_data_dir, &mut report);
+        (images, report)
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    recovery::restore_from_images(db.connection(), images, &mut report)
+        .await
+        .map_err(|e| e.to_string())?;
+    log::info!(
+        "Recovered {} problems and {} images from {} stored images",
+        report.restored_problems,
+        report.restored_images,
+        report.scanned
+    );
+
+    // Restored problems have no thumbnails or duplicate hashes yet
+    if report.restored_images > 0 {
+        if let Err(e) = sync_sidecars(&app).await {
+            log::warn!("Image sidecar sync after recovery failed: {}", e);
+        }
+        if let Err(e) = crate::commands::thumbnails::backfill_thumbnails(&app).await {
+            log::warn!("Thumbnail backfill after recovery failed: {}", e);
+        }
+        if let Err(e) = crate::commands::duplicates::backfill_image_hashes(&app).await {
+            log::warn!("Image hash backfill after recovery failed: {}", e);
+        }
+    }
+
+    Ok(report)
+}
@@ -0,0 +1,88 @@
+use crate::db::services::{self, StudyGrouping, StudyTotal};
+use crate::db::Db;
+use crate::session::SessionManagerState;
+use chrono::{DateTime, Local, NaiveDateTime, Utc};
+use std::time::Duration;
+use tauri::{AppHandle, Manager, Runtime, State};
+
+/// How often open intervals are marked as still running, which bounds the
+/// time lost when the app stops without ending the session
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(60);
+
+pub(crate) fn parse_time(value: &str) -> Result<NaiveDateTime, String> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|time| time.naive_utc())
+        .map_err(|e| format!("Invalid time '{}': {}", value, e))
+}
+
+/// Total study time per day (local calendar days), folder, course or set,
+/// optionally limited to `from`..`to` (RFC 3339)
+#[tauri::command]
+pub async fn get_study_time(
+    db: State<'_, Db>,
+    group_by: StudyGrouping,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<Vec<StudyTotal>, String> {
+    let from = from.as_deref().map(parse_time).transpose()?;
+    let to = to.as_deref().map(parse_time).transpose()?;
+
+    services::get_study_totals(
+        db.connection(),
+        group_by,
+        from,
+        to,
+        Utc::now().naive_utc(),
+        &Local,
+    )
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Starts, switches or stops the running study interval to match the active
+/// session
+pub(crate) async fn record_active_session<R: Runtime>(app: &AppHandle<R>) -> Result<(), String> {
+    let db = app
+        .try_state::<Db>()
+        .ok_or_else(|| "Database is not initialized".to_string())?;
+    let context = app
+        .state::<SessionManagerState>()
+        .lock()
+        .unwrap()
+        .get_active_session()
+        .map(|session| session.study_context());
+
+    services::record_study_context(db.connection(), context, Utc::now().naive_utc())
+        .await
+        .map_err(|e| format!("Failed to record study time: {}", e))
+}
+
+/// Closes intervals left open when the app last stopped, resumes the active
+/// session's, and keeps marking open intervals as still running. Call once
+/// the database and sessions are loaded.
+pub(crate) async fn start_study_log<R: Runtime>(app: &AppHandle<R>) -> Result<(), String> {
+    let db = app
+        .try_state::<Db>()
+        .ok_or_else(|| "Database is not initialized".to_string())?
+        .0
+        .clone();
+
+    match services::close_stale_intervals(&db).await {
+        Ok(0) => {}
+        Ok(count) => log::info!("Closed {} study intervals left open", count),
+        Err(e) => return Err(format!("Failed to close open study intervals: {}", e)),
+    }
+    record_active_session(app).await?;
+
+    std::thread::spawn(move || loop {
+        std::thread::sleep(HEARTBEAT_INTERVAL);
+        let touched = tauri::async_runtime::block_on(services::touch_open_intervals(
+            &db,
+            Utc::now().naive_utc(),
+        ));
+        if let Err(e) = touched {
+            log::warn!("Failed to update open study intervals: {}", e);
+        }
+    });
+    Ok(())
+}
@@ -0,0 +1,85 @@
+use crate::db::{services, Db};
+use crate::imaging::thumbnail;
+use crate::protocol;
+use crate::storage::resolve_image_file;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager, State};
+use uuid::Uuid;
+
+/// Generates a problem's thumbnails from its image if any are missing, or
+/// always when `force` is set. Runs on a blocking thread.
+pub(crate) async fn ensure_thumbnails(
+    app_data_dir: PathBuf,
+    problem_id: Uuid,
+    image_path: String,
+    force: bool,
+) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let cache_dir = thumbnail::cache_dir(&app_data_dir);
+        if !force && !thumbnail::is_missing(&cache_dir, problem_id) {
+            return Ok(());
+        }
+
+        let source = resolve_image_file(&app_data_dir, &image_path)
+            .ok_or_else(|| format!("Image file not found: {}", image_path))?;
+        thumbnail::generate(&source, &cache_dir, problem_id)
+            .map_err(|e| format!("Failed to generate thumbnails: {}", e))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Generates thumbnails for every problem image that lacks them.
+/// Failures are logged and skipped; returns how many problems were processed.
+pub(crate) async fn backfill_thumbnails(app: &AppHandle) -> Result<usize, String> {
+    let db = app.state::<Db>();
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    let cache_dir = thumbnail::cache_dir(&app_data_dir);
+
+    let problems = services::get_problems_with_images(db.connection())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut generated = 0;
+    for (problem, image) in problems {
+        if !thumbnail::is_missing(&cache_dir, problem.id) {
+            continue;
+        }
+
+        match ensure_thumbnails(app_data_dir.clone(), problem.id, image.path, false).await {
+            Ok(()) => generated += 1,
+            Err(e) => log::warn!("Skipping thumbnails for problem {}: {}", problem.id, e),
+        }
+    }
+
+    Ok(generated)
+}
+
+/// Get the URL of a problem's thumbnail, generating it if needed.
+/// Thumbnails are made from the problem's primary image.
+/// `size` is snapped to the nearest fixed size at or above it (default 256);
+/// `regenerate` rebuilds all sizes from the current image.
+#[tauri::command]
+pub async fn get_problem_thumbnail(
+    app: AppHandle,
+    db: State<'_, Db>,
+    problem_id: String,
+    size: Option<u32>,
+    regenerate: Option<bool>,
+) -> Result<String, String> {
+    let problem_uuid = Uuid::parse_str(&problem_id).map_err(|e| e.to_string())?;
+
+    let image = services::get_primary_image(db.connection(), problem_uuid)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Problem has no image".to_string())?;
+
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    ensure_thumbnails(app_data_dir, problem_uuid, image.path, regenerate.unwrap_or(false)).await?;
+
+    let size = thumbnail::snap_size(size.unwrap_or(256));
+    Ok(protocol::url(
+        protocol::IMAGE_SCHEME,
+        &format!("{}/thumbnail/{}", problem_uuid, size),
+    ))
+}
@@ -0,0 +1,35 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Id of the table's only row
+pub const ID: i32 = 1;
+
+/// App-wide state, in a single row
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "app_state")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: i32,
+    /// Cleared when the session is deleted
+    pub active_session_id: Option<Uuid>,
+    /// When `sessions.json` was imported; `None` until it has been
+    pub sessions_imported_at: Option<DateTime>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::sessions::Entity",
+        from = "Column::ActiveSessionId",
+        to = "super::sessions::Column::Id"
+    )]
+    ActiveSession,
+}
+
+impl Related<super::sessions::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ActiveSession.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
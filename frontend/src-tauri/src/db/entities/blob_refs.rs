@@ -0,0 +1,54 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Which of an image's files a blob is
+#[derive(Clone, Copy, Debug, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)")]
+#[serde(rename_all = "snake_case")]
+pub enum BlobKind {
+    /// `problem_images.path`
+    #[sea_orm(string_value = "image")]
+    Image,
+    /// `problem_images.original_path`
+    #[sea_orm(string_value = "original")]
+    Original,
+}
+
+/// A problem image's use of a blob in the image store
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "blob_refs")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub image_id: Uuid,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub kind: BlobKind,
+    pub problem_id: Uuid,
+    /// Hex SHA-256 of the blob's bytes
+    pub hash: String,
+    /// Relative to the app data dir, as in `problem_images`
+    pub path: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::problem_images::Entity",
+        from = "Column::ImageId",
+        to = "super::problem_images::Column::Id"
+    )]
+    ProblemImage,
+    #[sea_orm(
+        belongs_to = "super::problems::Entity",
+        from = "Column::ProblemId",
+        to = "super::problems::Column::Id"
+    )]
+    Problem,
+}
+
+impl Related<super::problem_images::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ProblemImage.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -5,6 +5,12 @@ pub mod courses;
 pub mod sets;
 pub mod problems;
 pub mod problem_attempts;
+pub mod problem_images;
+pub mod sessions;
+pub mod app_state;
+pub mod study_intervals;
+pub mod pomodoros;
+pub mod blob_refs;
 
 pub use users::Entity as Users;
 pub use subscriptions::Entity as Subscriptions;
@@ -13,3 +19,9 @@ pub use courses::Entity as Courses;
 pub use sets::Entity as Sets;
 pub use problems::Entity as Problems;
 pub use problem_attempts::Entity as ProblemAttempts;
+pub use problem_images::Entity as ProblemImages;
+pub use sessions::Entity as Sessions;
+pub use app_state::Entity as AppState;
+pub use study_intervals::Entity as StudyIntervals;
+pub use pomodoros::Entity as Pomodoros;
+pub use blob_refs::Entity as BlobRefs;
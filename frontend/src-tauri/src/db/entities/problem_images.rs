@@ -0,0 +1,51 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// What an image of a problem shows
+#[derive(Clone, Copy, Debug, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)")]
+#[serde(rename_all = "snake_case")]
+pub enum ImageRole {
+    /// The problem as captured
+    #[sea_orm(string_value = "question")]
+    Question,
+    /// A worked solution
+    #[sea_orm(string_value = "solution")]
+    Solution,
+    /// A photo or capture of our own work
+    #[sea_orm(string_value = "work")]
+    Work,
+}
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "problem_images")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    pub problem_id: Uuid,
+    pub role: ImageRole,
+    /// Relative to the app data dir, with its extension
+    pub path: String,
+    /// The image as captured, when `path` is a processed version of it
+    pub original_path: Option<String>,
+    pub sort_order: i32,
+    pub created_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::problems::Entity",
+        from = "Column::ProblemId",
+        to = "super::problems::Column::Id"
+    )]
+    Problem,
+}
+
+impl Related<super::problems::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Problem.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -9,10 +9,13 @@ pub struct Model {
     pub set_id: Uuid,
     pub title: String,
     pub description: Option<String>,
-    pub image_path: Option<String>,
     pub s3_image_key: Option<String>,
     pub confidence_level: i32,
     pub notes: Option<String>,
+    pub ocr_text: Option<String>,
+    pub source_window_title: Option<String>,
+    pub source_app_name: Option<String>,
+    pub image_hash: Option<i64>,
     pub created_at: DateTime,
     pub updated_at: DateTime,
     pub last_attempted: Option<DateTime>,
@@ -32,6 +35,8 @@ pub enum Relation {
     Set,
     #[sea_orm(has_many = "super::problem_attempts::Entity")]
     ProblemAttempts,
+    #[sea_orm(has_many = "super::problem_images::Entity")]
+    ProblemImages,
 }
 
 impl Related<super::sets::Entity> for Entity {
@@ -46,4 +51,10 @@ impl Related<super::problem_attempts::Entity> for Entity {
     }
 }
 
+impl Related<super::problem_images::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ProblemImages.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,45 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "sessions")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    pub name: String,
+    pub folder_id: Uuid,
+    pub course_id: Uuid,
+    pub set_id: Uuid,
+    pub created_at: DateTime,
+    pub last_used: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::folders::Entity",
+        from = "Column::FolderId",
+        to = "super::folders::Column::Id"
+    )]
+    Folder,
+    #[sea_orm(
+        belongs_to = "super::courses::Entity",
+        from = "Column::CourseId",
+        to = "super::courses::Column::Id"
+    )]
+    Course,
+    #[sea_orm(
+        belongs_to = "super::sets::Entity",
+        from = "Column::SetId",
+        to = "super::sets::Column::Id"
+    )]
+    Set,
+}
+
+impl Related<super::sets::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Set.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,51 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// A stretch of time spent in a session
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "study_intervals")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    /// Cleared when the session is deleted
+    pub session_id: Option<Uuid>,
+    pub folder_id: Uuid,
+    pub course_id: Uuid,
+    pub set_id: Uuid,
+    pub started_at: DateTime,
+    /// `None` while the interval is running
+    pub ended_at: Option<DateTime>,
+    /// Last time the app was seen running with the interval open; where an
+    /// interval left open by a crash is closed
+    pub last_seen_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::sessions::Entity",
+        from = "Column::SessionId",
+        to = "super::sessions::Column::Id"
+    )]
+    Session,
+    #[sea_orm(
+        belongs_to = "super::sets::Entity",
+        from = "Column::SetId",
+        to = "super::sets::Column::Id"
+    )]
+    Set,
+}
+
+impl Related<super::sessions::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Session.def()
+    }
+}
+
+impl Related<super::sets::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Set.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
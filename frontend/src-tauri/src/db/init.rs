@@ -1,9 +1,12 @@
 use migration::{Migrator, MigratorTrait};
 use sea_orm::Database;
+use std::path::Path;
 use std::sync::Arc;
 use tauri::{AppHandle, Manager};
 
 use super::Db;
+use crate::session::SessionFileImport;
+use crate::notify::notify;
 
 pub async fn init_sqlite(app_handle: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
     // Get the app data directory
@@ -28,8 +31,37 @@ pub async fn init_sqlite(app_handle: &AppHandle) -> Result<(), Box<dyn std::erro
     Migrator::up(&conn, None).await?;
     log::info!("Migrations completed successfully");
 
+    // Sessions kept in sessions.json before they moved into the database
+    match crate::session::import_sessions_file(&conn, &app_data_dir.join("sessions.json")).await {
+        Ok(Some(SessionFileImport::Imported(result))) => log::info!(
+            "Imported {} sessions from sessions.json ({} skipped)",
+            result.imported,
+            result.skipped
+        ),
+        Ok(Some(SessionFileImport::Quarantined(quarantined))) => {
+            notify_damaged_sessions_file(app_handle, &quarantined)
+        }
+        Ok(None) => {}
+        Err(e) => log::warn!("Failed to import sessions.json: {}", e),
+    }
+
     app_handle.manage(Db(Arc::new(conn)));
 
     log::info!("Database initialized successfully");
     Ok(())
 }
+
+/// Tells the user `sessions.json` couldn't be read, where it went, and how to
+/// import it once it is repaired
+fn notify_damaged_sessions_file(app_handle: &AppHandle, quarantined: &Path) {
+    let kept_as = quarantined
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let body = format!(
+        "No sessions could be restored. The damaged file was kept as {}; put a repaired copy back as sessions.json to import it on the next launch.",
+        kept_as
+    );
+
+    notify(app_handle, "Sessions File Damaged", body);
+}
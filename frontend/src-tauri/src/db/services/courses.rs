@@ -79,8 +79,8 @@ pub async fn update_course(
     course.update(db).await
 }
 
-pub async fn delete_course(
-    db: &DatabaseConnection,
+pub async fn delete_course<C: ConnectionTrait>(
+    db: &C,
     id: Uuid,
 ) -> Result<DeleteResult, DbErr> {
     Course::delete_by_id(id).exec(db).await
@@ -0,0 +1,250 @@
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::db::entities::{problems, problems::Entity as Problem};
+use crate::imaging::phash::{from_db, hamming};
+
+/// A problem whose image is perceptually close to another one
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DuplicateMatch {
+    pub problem_id: Uuid,
+    pub title: String,
+    pub set_id: Uuid,
+    pub set_name: String,
+    pub course_id: Uuid,
+    /// Hamming distance between the two hashes; 0 means identical
+    pub distance: u32,
+}
+
+#[derive(Debug, Clone, FromQueryResult)]
+struct HashedProblem {
+    problem_id: Uuid,
+    title: String,
+    image_hash: i64,
+    set_id: Uuid,
+    set_name: String,
+    course_id: Uuid,
+}
+
+impl HashedProblem {
+    fn to_match(&self, distance: u32) -> DuplicateMatch {
+        DuplicateMatch {
+            problem_id: self.problem_id,
+            title: self.title.clone(),
+            set_id: self.set_id,
+            set_name: self.set_name.clone(),
+            course_id: self.course_id,
+            distance,
+        }
+    }
+}
+
+async fn hashed_problems(
+    db: &DatabaseConnection,
+    course_id: Option<Uuid>,
+) -> Result<Vec<HashedProblem>, DbErr> {
+    let mut sql = String::from(
+        "SELECT
+            p.id AS problem_id,
+            p.title AS title,
+            p.image_hash AS image_hash,
+            s.id AS set_id,
+            s.name AS set_name,
+            s.course_id AS course_id
+        FROM problems p
+        JOIN sets s ON s.id = p.set_id
+        WHERE p.image_hash IS NOT NULL",
+    );
+    let mut values: Vec<Value> = Vec::new();
+    if let Some(course_id) = course_id {
+        sql.push_str(" AND s.course_id = ?");
+        values.push(course_id.into());
+    }
+    sql.push_str(" ORDER BY p.created_at");
+
+    HashedProblem::find_by_statement(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        sql,
+        values,
+    ))
+    .all(db)
+    .await
+}
+
+/// Problems in the same course as `set_id` whose image hash is within
+/// `threshold` of `hash`, closest first, with problems in the same set first
+/// among equals. `exclude` skips the problem being checked.
+pub async fn find_near_duplicates(
+    db: &DatabaseConnection,
+    set_id: Uuid,
+    hash: i64,
+    threshold: u32,
+    exclude: Option<Uuid>,
+) -> Result<Vec<DuplicateMatch>, DbErr> {
+    let Some(set) = super::get_set_by_id(db, set_id).await? else {
+        return Ok(Vec::new());
+    };
+
+    let mut matches: Vec<DuplicateMatch> = hashed_problems(db, Some(set.course_id))
+        .await?
+        .iter()
+        .filter(|p| Some(p.problem_id) != exclude)
+        .filter_map(|p| {
+            let distance = hamming(from_db(p.image_hash), from_db(hash));
+            (distance <= threshold).then(|| p.to_match(distance))
+        })
+        .collect();
+
+    matches.sort_by_key(|m| (m.distance, m.set_id != set_id));
+    Ok(matches)
+}
+
+/// Groups of two or more problems whose images are near-duplicates, across
+/// the whole library. Problems are grouped transitively: if A ≈ B and B ≈ C,
+/// all three end up in one cluster.
+pub async fn find_duplicate_clusters(
+    db: &DatabaseConnection,
+    threshold: u32,
+) -> Result<Vec<Vec<DuplicateMatch>>, DbErr> {
+    let problems = hashed_problems(db, None).await?;
+    let hashes: Vec<u64> = problems.iter().map(|p| from_db(p.image_hash)).collect();
+
+    // Union-find over all pairs
+    let mut parent: Vec<usize> = (0..problems.len()).collect();
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+
+    for i in 0..hashes.len() {
+        for j in (i + 1)..hashes.len() {
+            if hamming(hashes[i], hashes[j]) <= threshold {
+                let (a, b) = (root(&mut parent, i), root(&mut parent, j));
+                if a != b {
+                    parent[b] = a;
+                }
+            }
+        }
+    }
+
+    let mut groups: HashMap<usize, Vec<usize>> = HashMap::new();
+    for i in 0..problems.len() {
+        let r = root(&mut parent, i);
+        groups.entry(r).or_default().push(i);
+    }
+
+    // Within a cluster, distances are measured from its oldest problem
+    let mut clusters: Vec<Vec<DuplicateMatch>> = groups
+        .into_values()
+        .filter(|members| members.len() > 1)
+        .map(|members| {
+            let first = hashes[members[0]];
+            members
+                .iter()
+                .map(|&i| problems[i].to_match(hamming(first, hashes[i])))
+                .collect()
+        })
+        .collect();
+
+    clusters.sort_by_key(|cluster| std::cmp::Reverse(cluster.len()));
+    Ok(clusters)
+}
+
+/// Store the perceptual hash of a problem's image, or clear it when the
+/// problem has no image. The hash is derived data, so timestamps and sync
+/// state are left alone.
+pub async fn set_problem_image_hash(
+    db: &DatabaseConnection,
+    problem_id: Uuid,
+    hash: Option<i64>,
+) -> Result<(), DbErr> {
+    let problem = Problem::find_by_id(problem_id)
+        .one(db)
+        .await?
+        .ok_or(DbErr::RecordNotFound("Problem not found".to_string()))?;
+
+    let mut problem: problems::ActiveModel = problem.into();
+    problem.image_hash = Set(hash);
+    problem.update(db).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::services::create_problem;
+    use crate::db::services::test_fixtures::{create_set, setup_test_db};
+
+    async fn hashed_problem(db: &DatabaseConnection, set_id: Uuid, title: &str, hash: u64) -> Uuid {
+        let problem = create_problem(db, set_id, title.to_string(), None, None, None)
+            .await
+            .unwrap();
+        set_problem_image_hash(db, problem.id, Some(hash as i64)).await.unwrap();
+        problem.id
+    }
+
+    #[tokio::test]
+    async fn test_find_near_duplicates_within_course() {
+        let db = setup_test_db().await;
+        let week1 = create_set(&db, "Calculus", "Week 1").await.set_id;
+        let week2 = create_set(&db, "Calculus", "Week 2").await.set_id;
+        let other_course = create_set(&db, "Algebra", "Week 1").await.set_id;
+
+        let hash: u64 = 0xF0F0_F0F0_F0F0_F0F0;
+        let same_set = hashed_problem(&db, week1, "Q1", hash ^ 0b1).await;
+        let other_set = hashed_problem(&db, week2, "Q7", hash).await;
+        hashed_problem(&db, week1, "Different", !hash).await;
+        hashed_problem(&db, other_course, "Elsewhere", hash).await;
+
+        let matches = find_near_duplicates(&db, week1, hash as i64, 4, None)
+            .await
+            .unwrap();
+        let ids: Vec<Uuid> = matches.iter().map(|m| m.problem_id).collect();
+        // Exact match first, then the one-bit difference; other courses are ignored
+        assert_eq!(ids, vec![other_set, same_set]);
+        assert_eq!(matches[0].distance, 0);
+        assert_eq!(matches[1].distance, 1);
+        assert_eq!(matches[0].set_name, "Week 2");
+
+        let matches = find_near_duplicates(&db, week1, hash as i64, 4, Some(other_set))
+            .await
+            .unwrap();
+        assert_eq!(matches.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_find_duplicate_clusters() {
+        let db = setup_test_db().await;
+        let set_id = create_set(&db, "Calculus", "Week 1").await.set_id;
+        let elsewhere = create_set(&db, "Algebra", "Week 1").await.set_id;
+
+        let a: u64 = 0;
+        // a ≈ b ≈ c, transitively
+        hashed_problem(&db, set_id, "A", a).await;
+        hashed_problem(&db, set_id, "B", a ^ 0b111).await;
+        hashed_problem(&db, elsewhere, "C", a ^ 0b111_111).await;
+        // d ≈ e
+        hashed_problem(&db, set_id, "D", u64::MAX).await;
+        hashed_problem(&db, set_id, "E", u64::MAX ^ 0b1).await;
+        // f is alone
+        hashed_problem(&db, set_id, "F", 0xFFFF_FFFF).await;
+        // Unhashed problems are ignored
+        create_problem(&db, set_id, "No hash".to_string(), None, None, None)
+            .await
+            .unwrap();
+
+        let clusters = find_duplicate_clusters(&db, 4).await.unwrap();
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(clusters[0].len(), 3);
+        assert_eq!(clusters[1].len(), 2);
+
+        let mut titles: Vec<&str> = clusters[0].iter().map(|m| m.title.as_str()).collect();
+        titles.sort();
+        assert_eq!(titles, vec!["A", "B", "C"]);
+    }
+}
@@ -73,8 +73,8 @@ pub async fn update_folder(
     folder.update(db).await
 }
 
-pub async fn delete_folder(
-    db: &DatabaseConnection,
+pub async fn delete_folder<C: ConnectionTrait>(
+    db: &C,
     id: Uuid,
 ) -> Result<DeleteResult, DbErr> {
     Folder::delete_by_id(id).exec(db).await
@@ -3,11 +3,25 @@ pub mod courses;
 pub mod sets;
 pub mod problems;
 pub mod problem_attempts;
+pub mod problem_images;
+pub mod pomodoros;
+pub mod duplicates;
 pub mod screenshots;
+pub mod search;
+pub mod sessions;
+pub mod study;
+#[cfg(test)]
+pub(crate) mod test_fixtures;
 
 pub use folders::*;
 pub use courses::*;
 pub use sets::*;
 pub use problems::*;
 pub use problem_attempts::*;
+pub use problem_images::*;
+pub use pomodoros::*;
+pub use duplicates::*;
 pub use screenshots::*;
+pub use search::*;
+pub use sessions::*;
+pub use study::*;
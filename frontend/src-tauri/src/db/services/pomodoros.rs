@@ -0,0 +1,138 @@
+use chrono::{NaiveDateTime, TimeZone};
+use sea_orm::*;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::db::entities::{pomodoros, pomodoros::Entity as Pomodoro};
+use crate::db::services::study::Tally;
+use crate::db::services::{StudyContext, StudyGrouping};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PomodoroCount {
+    /// `YYYY-MM-DD` for days, otherwise the folder, course or set id
+    pub key: String,
+    /// Folder, course or set name; `None` for days
+    pub name: Option<String>,
+    pub count: i64,
+}
+
+/// Records a completed work phase against the session's set
+pub async fn record_pomodoro(
+    db: &DatabaseConnection,
+    context: StudyContext,
+    work_seconds: i32,
+    completed_at: NaiveDateTime,
+) -> Result<pomodoros::Model, DbErr> {
+    pomodoros::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        session_id: Set(Some(context.session_id)),
+        folder_id: Set(context.folder_id),
+        course_id: Set(context.course_id),
+        set_id: Set(context.set_id),
+        work_seconds: Set(work_seconds),
+        completed_at: Set(completed_at),
+    }
+    .insert(db)
+    .await
+}
+
+/// Pomodoros completed between `from` and `to` (either open-ended), counted
+/// per `grouping`. Days are calendar days in `tz`. Days come in date order,
+/// the rest most first.
+pub async fn get_pomodoro_counts<Tz: TimeZone>(
+    db: &DatabaseConnection,
+    grouping: StudyGrouping,
+    from: Option<NaiveDateTime>,
+    to: Option<NaiveDateTime>,
+    tz: &Tz,
+) -> Result<Vec<PomodoroCount>, DbErr> {
+    let mut query = Pomodoro::find();
+    if let Some(from) = from {
+        query = query.filter(pomodoros::Column::CompletedAt.gte(from));
+    }
+    if let Some(to) = to {
+        query = query.filter(pomodoros::Column::CompletedAt.lt(to));
+    }
+
+    let mut tally = Tally::default();
+    for pomodoro in query.all(db).await? {
+        match grouping.id_of(pomodoro.folder_id, pomodoro.course_id, pomodoro.set_id) {
+            Some(id) => tally.add_id(id, 1),
+            None => tally.add_day(tz.from_utc_datetime(&pomodoro.completed_at).date_naive(), 1),
+        }
+    }
+
+    Ok(tally
+        .into_groups(db, grouping)
+        .await?
+        .into_iter()
+        .map(|(key, name, count)| PomodoroCount { key, name, count })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::services::delete_set;
+    use crate::db::services::test_fixtures::{at, setup_test_db, study_context};
+    use chrono::{FixedOffset, Utc};
+
+    async fn record(db: &DatabaseConnection, context: StudyContext, completed_at: &str) {
+        record_pomodoro(db, context, 25 * 60, at(completed_at))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_pomodoro_counts() {
+        let db = setup_test_db().await;
+        let week1 = study_context(&db, "Calculus", "Week 1").await;
+        let week2 = study_context(&db, "Calculus", "Week 2").await;
+        let algebra = study_context(&db, "Algebra", "Week 1").await;
+
+        record(&db, week1, "2026-03-02 09:25").await;
+        record(&db, week2, "2026-03-02 23:40").await;
+        record(&db, algebra, "2026-03-03 10:00").await;
+
+        let by_course = get_pomodoro_counts(&db, StudyGrouping::Course, None, None, &Utc)
+            .await
+            .unwrap();
+        assert_eq!(
+            by_course,
+            vec![
+                PomodoroCount {
+                    key: week1.course_id.to_string(),
+                    name: Some("Calculus".to_string()),
+                    count: 2,
+                },
+                PomodoroCount {
+                    key: algebra.course_id.to_string(),
+                    name: Some("Algebra".to_string()),
+                    count: 1,
+                },
+            ]
+        );
+
+        // 23:40 UTC is the next day an hour east
+        let east = FixedOffset::east_opt(3600).unwrap();
+        let by_day = get_pomodoro_counts(&db, StudyGrouping::Day, None, None, &east)
+            .await
+            .unwrap();
+        let days: Vec<(&str, i64)> = by_day.iter().map(|c| (c.key.as_str(), c.count)).collect();
+        assert_eq!(days, vec![("2026-03-02", 1), ("2026-03-03", 2)]);
+
+        let from = Some(at("2026-03-02 12:00"));
+        let by_set = get_pomodoro_counts(&db, StudyGrouping::Set, from, None, &Utc)
+            .await
+            .unwrap();
+        assert_eq!(by_set.len(), 2);
+        assert!(by_set.iter().all(|c| c.key != week1.set_id.to_string()));
+
+        // Deleting a set deletes its pomodoros
+        delete_set(&db, week2.set_id).await.unwrap();
+        let by_set = get_pomodoro_counts(&db, StudyGrouping::Set, None, None, &Utc)
+            .await
+            .unwrap();
+        assert_eq!(by_set.len(), 2);
+    }
+}
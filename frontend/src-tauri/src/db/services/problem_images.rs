@@ -0,0 +1,818 @@
+use chrono::{DateTime, Utc};
+use sea_orm::*;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use uuid::Uuid;
+
+use crate::db::entities::blob_refs::{self, BlobKind};
+use crate::db::entities::problem_images::{self, ImageRole};
+use crate::db::entities::{
+    blob_refs::Entity as BlobRef, courses, courses::Entity as Course, folders,
+    folders::Entity as Folder, problem_images::Entity as ProblemImage, problems,
+    problems::Entity as Problem, sets, sets::Entity as SetEntity,
+};
+use crate::db::services::{delete_course, delete_folder, delete_problem, delete_set};
+use crate::storage;
+use crate::storage::sidecar::{BlobUse, ImageMetadata};
+
+/// Part of the hierarchy whose images are looked up together, e.g. everything
+/// a delete is about to cascade to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageScope {
+    Problem(Uuid),
+    Set(Uuid),
+    Course(Uuid),
+    Folder(Uuid),
+}
+
+/// The image that stands for a problem in lists, thumbnails and duplicate
+/// checks: its first question image, or its first image of any role.
+/// `images` must be in display order.
+pub fn primary_image(images: &[problem_images::Model]) -> Option<&problem_images::Model> {
+    images
+        .iter()
+        .find(|image| image.role == ImageRole::Question)
+        .or(images.first())
+}
+
+/// A problem's images in display order
+pub async fn get_problem_images(
+    db: &DatabaseConnection,
+    problem_id: Uuid,
+) -> Result<Vec<problem_images::Model>, DbErr> {
+    ProblemImage::find()
+        .filter(problem_images::Column::ProblemId.eq(problem_id))
+        .order_by_asc(problem_images::Column::SortOrder)
+        .order_by_asc(problem_images::Column::CreatedAt)
+        .all(db)
+        .await
+}
+
+pub async fn get_problem_image_by_id(
+    db: &DatabaseConnection,
+    id: Uuid,
+) -> Result<Option<problem_images::Model>, DbErr> {
+    ProblemImage::find_by_id(id).one(db).await
+}
+
+/// Hashes of every blob an image uses, as its current file or its original
+pub async fn get_referenced_blob_hashes(db: &DatabaseConnection) -> Result<HashSet<String>, DbErr> {
+    let hashes: Vec<String> = BlobRef::find()
+        .select_only()
+        .column(blob_refs::Column::Hash)
+        .distinct()
+        .into_tuple()
+        .all(db)
+        .await?;
+    Ok(hashes.into_iter().collect())
+}
+
+/// Every image whose current file is one of `hashes`, or any blob with
+/// `None`, with its problem's title and hierarchy names for the sidecars
+pub async fn get_blob_uses(
+    db: &DatabaseConnection,
+    hashes: Option<&HashSet<String>>,
+) -> Result<Vec<BlobUse>, DbErr> {
+    let mut query = BlobRef::find().filter(blob_refs::Column::Kind.eq(BlobKind::Image));
+    if let Some(hashes) = hashes {
+        query = query.filter(blob_refs::Column::Hash.is_in(hashes.iter().cloned()));
+    }
+    let refs = query.all(db).await?;
+
+    let images: HashMap<Uuid, problem_images::Model> = ProblemImage::find()
+        .filter(problem_images::Column::Id.is_in(refs.iter().map(|r| r.image_id)))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|image| (image.id, image))
+        .collect();
+    let problems: HashMap<Uuid, problems::Model> = Problem::find()
+        .filter(problems::Column::Id.is_in(refs.iter().map(|r| r.problem_id)))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|problem| (problem.id, problem))
+        .collect();
+    let sets: HashMap<Uuid, sets::Model> = SetEntity::find()
+        .filter(sets::Column::Id.is_in(problems.values().map(|p| p.set_id)))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|set| (set.id, set))
+        .collect();
+    let courses: HashMap<Uuid, courses::Model> = Course::find()
+        .filter(courses::Column::Id.is_in(sets.values().map(|s| s.course_id)))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|course| (course.id, course))
+        .collect();
+    let folders: HashMap<Uuid, folders::Model> = Folder::find()
+        .filter(folders::Column::Id.is_in(courses.values().map(|c| c.folder_id)))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|folder| (folder.id, folder))
+        .collect();
+
+    let uses = refs
+        .into_iter()
+        .filter_map(|blob_ref| {
+            let image = images.get(&blob_ref.image_id)?;
+            let problem = problems.get(&blob_ref.problem_id)?;
+            let set = sets.get(&problem.set_id)?;
+            let course = courses.get(&set.course_id)?;
+            let folder = folders.get(&course.folder_id)?;
+            Some(BlobUse {
+                hash: blob_ref.hash,
+                path: blob_ref.path,
+                metadata: ImageMetadata {
+                    problem_id: problem.id,
+                    title: problem.title.clone(),
+                    role: image.role.to_value(),
+                    folder: folder.name.clone(),
+                    course: course.name.clone(),
+                    set: set.name.clone(),
+                    captured_at: image.created_at.and_utc(),
+                },
+            })
+        })
+        .collect();
+    Ok(uses)
+}
+
+/// What a new file for one of a problem's images carries in it: the problem
+/// and the names of its set, course and folder as they are now. `None` if the
+/// problem is gone.
+pub async fn get_image_metadata(
+    db: &DatabaseConnection,
+    problem_id: Uuid,
+    role: ImageRole,
+    captured_at: DateTime<Utc>,
+) -> Result<Option<ImageMetadata>, DbErr> {
+    let Some(problem) = Problem::find_by_id(problem_id).one(db).await? else {
+        return Ok(None);
+    };
+    let Some(set) = SetEntity::find_by_id(problem.set_id).one(db).await? else {
+        return Ok(None);
+    };
+    let Some(course) = Course::find_by_id(set.course_id).one(db).await? else {
+        return Ok(None);
+    };
+    let Some(folder) = Folder::find_by_id(course.folder_id).one(db).await? else {
+        return Ok(None);
+    };
+
+    Ok(Some(ImageMetadata {
+        problem_id: problem.id,
+        title: problem.title,
+        role: role.to_value(),
+        folder: folder.name,
+        course: course.name,
+        set: set.name,
+        captured_at,
+    }))
+}
+
+/// Brings `blob_refs` in line with the files `image` points at. Paths outside
+/// the store have no blob and get no row.
+async fn record_blob_refs<C: ConnectionTrait>(
+    db: &C,
+    image: &problem_images::Model,
+) -> Result<(), DbErr> {
+    BlobRef::delete_many()
+        .filter(blob_refs::Column::ImageId.eq(image.id))
+        .exec(db)
+        .await?;
+
+    let files = [
+        (BlobKind::Image, Some(&image.path)),
+        (BlobKind::Original, image.original_path.as_ref()),
+    ];
+    for (kind, path) in files {
+        let Some(path) = path.filter(|path| storage::is_blob_path(path)) else {
+            continue;
+        };
+        let Some(hash) = storage::blob_hash(Path::new(path)) else {
+            continue;
+        };
+        blob_refs::ActiveModel {
+            image_id: Set(image.id),
+            kind: Set(kind),
+            problem_id: Set(image.problem_id),
+            hash: Set(hash.to_string()),
+            path: Set(path.clone()),
+        }
+        .insert(db)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Every image of every problem in `scope`
+pub async fn get_images_in_scope<C: ConnectionTrait>(
+    db: &C,
+    scope: ImageScope,
+) -> Result<Vec<problem_images::Model>, DbErr> {
+    let course_ids: Vec<Uuid> = match scope {
+        ImageScope::Folder(folder_id) => Course::find()
+            .select_only()
+            .column(courses::Column::Id)
+            .filter(courses::Column::FolderId.eq(folder_id))
+            .into_tuple()
+            .all(db)
+            .await?,
+        ImageScope::Course(course_id) => vec![course_id],
+        _ => Vec::new(),
+    };
+    let set_ids: Vec<Uuid> = match scope {
+        ImageScope::Folder(_) | ImageScope::Course(_) => SetEntity::find()
+            .select_only()
+            .column(sets::Column::Id)
+            .filter(sets::Column::CourseId.is_in(course_ids))
+            .into_tuple()
+            .all(db)
+            .await?,
+        ImageScope::Set(set_id) => vec![set_id],
+        ImageScope::Problem(_) => Vec::new(),
+    };
+    let problem_ids: Vec<Uuid> = match scope {
+        ImageScope::Problem(problem_id) => vec![problem_id],
+        _ => Problem::find()
+            .select_only()
+            .column(problems::Column::Id)
+            .filter(problems::Column::SetId.is_in(set_ids))
+            .into_tuple()
+            .all(db)
+            .await?,
+    };
+
+    ProblemImage::find()
+        .filter(problem_images::Column::ProblemId.is_in(problem_ids))
+        .order_by_asc(problem_images::Column::CreatedAt)
+        .all(db)
+        .await
+}
+
+/// Delete everything in `scope` and return the images that went with it.
+/// They are read in the same transaction, so an image added meanwhile is
+/// either deleted and returned or not deleted at all.
+pub async fn delete_with_images(
+    db: &DatabaseConnection,
+    scope: ImageScope,
+) -> Result<Vec<problem_images::Model>, DbErr> {
+    let txn = db.begin().await?;
+    let images = get_images_in_scope(&txn, scope).await?;
+    match scope {
+        ImageScope::Problem(id) => delete_problem(&txn, id).await?,
+        ImageScope::Set(id) => delete_set(&txn, id).await?,
+        ImageScope::Course(id) => delete_course(&txn, id).await?,
+        ImageScope::Folder(id) => delete_folder(&txn, id).await?,
+    };
+    txn.commit().await?;
+    Ok(images)
+}
+
+/// Point an image at a processed file and keep the one it came from, or with
+/// `original_path` of `None`, at a file that is its own original
+pub async fn set_problem_image_files(
+    db: &DatabaseConnection,
+    id: Uuid,
+    path: String,
+    original_path: Option<String>,
+) -> Result<problem_images::Model, DbErr> {
+    let txn = db.begin().await?;
+    let image = problem_images::ActiveModel {
+        id: Set(id),
+        path: Set(path),
+        original_path: Set(original_path),
+        ..Default::default()
+    }
+    .update(&txn)
+    .await?;
+    record_blob_refs(&txn, &image).await?;
+    txn.commit().await?;
+    Ok(image)
+}
+
+/// Whether any image still uses the file at `path`, as its current file or
+/// as its original. Identical images share one file in the store, so a file
+/// can only go once this is false.
+pub async fn is_image_path_referenced(db: &DatabaseConnection, path: &str) -> Result<bool, DbErr> {
+    Ok(ProblemImage::find()
+        .filter(
+            Condition::any()
+                .add(problem_images::Column::Path.eq(path))
+                .add(problem_images::Column::OriginalPath.eq(path)),
+        )
+        .count(db)
+        .await?
+        > 0)
+}
+
+/// See [`primary_image`]
+pub async fn get_primary_image(
+    db: &DatabaseConnection,
+    problem_id: Uuid,
+) -> Result<Option<problem_images::Model>, DbErr> {
+    let images = get_problem_images(db, problem_id).await?;
+    Ok(primary_image(&images).cloned())
+}
+
+/// Every problem that has an image, with its primary image, oldest problem first
+pub async fn get_problems_with_images(
+    db: &DatabaseConnection,
+) -> Result<Vec<(problems::Model, problem_images::Model)>, DbErr> {
+    let mut images_by_problem: HashMap<Uuid, Vec<problem_images::Model>> = HashMap::new();
+    for image in ProblemImage::find()
+        .order_by_asc(problem_images::Column::SortOrder)
+        .order_by_asc(problem_images::Column::CreatedAt)
+        .all(db)
+        .await?
+    {
+        images_by_problem
+            .entry(image.problem_id)
+            .or_default()
+            .push(image);
+    }
+
+    let problems = Problem::find()
+        .order_by_asc(problems::Column::CreatedAt)
+        .all(db)
+        .await?;
+
+    Ok(problems
+        .into_iter()
+        .filter_map(|problem| {
+            let image = primary_image(images_by_problem.get(&problem.id)?)?.clone();
+            Some((problem, image))
+        })
+        .collect())
+}
+
+/// Attach an image to a problem, after its existing images
+pub async fn add_problem_image<C: ConnectionTrait>(
+    db: &C,
+    problem_id: Uuid,
+    role: ImageRole,
+    path: String,
+) -> Result<problem_images::Model, DbErr> {
+    // Calculate sort_order (max + 1 or 0 if none exist)
+    let max_sort = ProblemImage::find()
+        .filter(problem_images::Column::ProblemId.eq(problem_id))
+        .order_by_desc(problem_images::Column::SortOrder)
+        .one(db)
+        .await?
+        .map(|i| i.sort_order)
+        .unwrap_or(-1);
+
+    let image = problem_images::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        problem_id: Set(problem_id),
+        role: Set(role),
+        path: Set(path),
+        original_path: Set(None),
+        sort_order: Set(max_sort + 1),
+        created_at: Set(chrono::Utc::now().naive_utc()),
+    }
+    .insert(db)
+    .await?;
+    record_blob_refs(db, &image).await?;
+
+    Ok(image)
+}
+
+/// Detach an image from its problem and return it, so the caller can remove the file.
+/// It is read in the same transaction, so the file returned is the one deleted.
+pub async fn remove_problem_image(
+    db: &DatabaseConnection,
+    id: Uuid,
+) -> Result<problem_images::Model, DbErr> {
+    let txn = db.begin().await?;
+    let image = ProblemImage::find_by_id(id)
+        .one(&txn)
+        .await?
+        .ok_or(DbErr::RecordNotFound("Problem image not found".to_string()))?;
+
+    ProblemImage::delete_by_id(id).exec(&txn).await?;
+    txn.commit().await?;
+    Ok(image)
+}
+
+/// Put a problem's images in the given order. `image_ids` must list each of
+/// the problem's images exactly once.
+pub async fn reorder_problem_images(
+    db: &DatabaseConnection,
+    problem_id: Uuid,
+    image_ids: Vec<Uuid>,
+) -> Result<Vec<problem_images::Model>, DbErr> {
+    let images = get_problem_images(db, problem_id).await?;
+
+    let mut current: Vec<Uuid> = images.iter().map(|i| i.id).collect();
+    let mut requested = image_ids.clone();
+    current.sort();
+    requested.sort();
+    if current != requested {
+        return Err(DbErr::Custom(
+            "Image ids must list each of the problem's images exactly once".to_string(),
+        ));
+    }
+
+    let txn = db.begin().await?;
+    for (position, image) in image_ids.iter().enumerate() {
+        problem_images::ActiveModel {
+            id: Set(*image),
+            sort_order: Set(position as i32),
+            ..Default::default()
+        }
+        .update(&txn)
+        .await?;
+    }
+    txn.commit().await?;
+
+    get_problem_images(db, problem_id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::services::test_fixtures::{create_set, setup_test_db};
+    use crate::db::services::{
+        create_problem, delete_problem, find_or_create_course, find_or_create_set, update_course,
+        update_folder, update_set,
+    };
+
+    async fn create_test_problem(db: &DatabaseConnection, image_path: Option<&str>) -> Uuid {
+        let set = create_set(db, "Calculus", "Week 1").await;
+        create_problem(
+            db,
+            set.set_id,
+            "Q1".to_string(),
+            None,
+            image_path.map(|p| p.to_string()),
+            None,
+        )
+        .await
+        .unwrap()
+        .id
+    }
+
+    #[tokio::test]
+    async fn test_renames_and_moves_leave_image_paths_alone() {
+        let db = setup_test_db().await;
+        let problem_id = create_test_problem(&db, Some("blobs/ab/ab12.png")).await;
+        let problem = Problem::find_by_id(problem_id).one(&db).await.unwrap().unwrap();
+        let set = SetEntity::find_by_id(problem.set_id).one(&db).await.unwrap().unwrap();
+        let course = Course::find_by_id(set.course_id).one(&db).await.unwrap().unwrap();
+
+        update_folder(&db, course.folder_id, Some("Maths".to_string()), None, None)
+            .await
+            .unwrap();
+        update_course(&db, course.id, Some("Calculus I".to_string()), None, None, None)
+            .await
+            .unwrap();
+        update_set(&db, set.id, Some("Week 1 (limits)".to_string()), None, None)
+            .await
+            .unwrap();
+
+        // Move the set under another course
+        let other_course = find_or_create_course(&db, course.folder_id, "Analysis".to_string())
+            .await
+            .unwrap();
+        sets::ActiveModel {
+            id: Set(set.id),
+            course_id: Set(other_course.id),
+            ..Default::default()
+        }
+        .update(&db)
+        .await
+        .unwrap();
+
+        // Paths don't contain names, so nothing on disk or in the rows has to follow
+        let images = get_problem_images(&db, problem_id).await.unwrap();
+        assert_eq!(images[0].path, "blobs/ab/ab12.png");
+        assert_eq!(
+            get_images_in_scope(&db, ImageScope::Course(other_course.id))
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(get_images_in_scope(&db, ImageScope::Course(course.id))
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_images_in_scope() {
+        let db = setup_test_db().await;
+        let problem_id = create_test_problem(&db, Some("Q1.png")).await;
+        add_problem_image(&db, problem_id, ImageRole::Work, "Q1_work.png".to_string())
+            .await
+            .unwrap();
+        let problem = Problem::find_by_id(problem_id).one(&db).await.unwrap().unwrap();
+        let set = SetEntity::find_by_id(problem.set_id).one(&db).await.unwrap().unwrap();
+        let course = Course::find_by_id(set.course_id).one(&db).await.unwrap().unwrap();
+
+        // A problem elsewhere in the hierarchy is never included
+        let other_set = find_or_create_set(&db, course.id, "Week 2".to_string())
+            .await
+            .unwrap();
+        create_problem(&db, other_set.id, "Q2".to_string(), None, Some("Q2.png".to_string()), None)
+            .await
+            .unwrap();
+
+        let paths = |images: Vec<problem_images::Model>| {
+            let mut paths: Vec<String> = images.into_iter().map(|i| i.path).collect();
+            paths.sort();
+            paths
+        };
+        assert_eq!(
+            paths(get_images_in_scope(&db, ImageScope::Problem(problem_id)).await.unwrap()),
+            vec!["Q1.png", "Q1_work.png"]
+        );
+        assert_eq!(
+            paths(get_images_in_scope(&db, ImageScope::Set(set.id)).await.unwrap()),
+            vec!["Q1.png", "Q1_work.png"]
+        );
+        assert_eq!(
+            paths(get_images_in_scope(&db, ImageScope::Course(course.id)).await.unwrap()),
+            vec!["Q1.png", "Q1_work.png", "Q2.png"]
+        );
+        assert_eq!(
+            paths(get_images_in_scope(&db, ImageScope::Folder(course.folder_id)).await.unwrap()),
+            vec!["Q1.png", "Q1_work.png", "Q2.png"]
+        );
+        assert!(get_images_in_scope(&db, ImageScope::Folder(Uuid::new_v4()))
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_delete_with_images() {
+        let db = setup_test_db().await;
+        let problem_id = create_test_problem(&db, Some("Q1.png")).await;
+        let problem = Problem::find_by_id(problem_id).one(&db).await.unwrap().unwrap();
+        let set = SetEntity::find_by_id(problem.set_id).one(&db).await.unwrap().unwrap();
+        create_problem(&db, set.id, "Q2".to_string(), None, Some("Q2.png".to_string()), None)
+            .await
+            .unwrap();
+
+        let mut paths: Vec<String> = delete_with_images(&db, ImageScope::Set(set.id))
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|image| image.path)
+            .collect();
+        paths.sort();
+        assert_eq!(paths, vec!["Q1.png", "Q2.png"]);
+        assert!(SetEntity::find_by_id(set.id).one(&db).await.unwrap().is_none());
+        assert!(ProblemImage::find().all(&db).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_create_problem_with_image_adds_question_image() {
+        let db = setup_test_db().await;
+        let problem_id = create_test_problem(&db, Some("Math/Calculus/Week_1/Q1.png")).await;
+
+        let images = get_problem_images(&db, problem_id).await.unwrap();
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].role, ImageRole::Question);
+        assert_eq!(images[0].path, "Math/Calculus/Week_1/Q1.png");
+        assert_eq!(images[0].sort_order, 0);
+    }
+
+    #[tokio::test]
+    async fn test_add_remove_and_reorder_images() {
+        let db = setup_test_db().await;
+        let problem_id = create_test_problem(&db, Some("Q1.png")).await;
+
+        let solution = add_problem_image(
+            &db,
+            problem_id,
+            ImageRole::Solution,
+            "Q1_solution.png".to_string(),
+        )
+        .await
+        .unwrap();
+        let work = add_problem_image(&db, problem_id, ImageRole::Work, "Q1_work.jpg".to_string())
+            .await
+            .unwrap();
+        assert_eq!(solution.sort_order, 1);
+        assert_eq!(work.sort_order, 2);
+
+        let question = get_primary_image(&db, problem_id).await.unwrap().unwrap();
+        assert_eq!(question.path, "Q1.png");
+
+        let reordered =
+            reorder_problem_images(&db, problem_id, vec![work.id, question.id, solution.id])
+                .await
+                .unwrap();
+        let paths: Vec<&str> = reordered.iter().map(|i| i.path.as_str()).collect();
+        assert_eq!(paths, vec!["Q1_work.jpg", "Q1.png", "Q1_solution.png"]);
+
+        // The question image stays primary wherever it is in the order
+        let primary = get_primary_image(&db, problem_id).await.unwrap().unwrap();
+        assert_eq!(primary.id, question.id);
+
+        // Orders that leave out or invent images are rejected
+        assert!(
+            reorder_problem_images(&db, problem_id, vec![work.id, question.id])
+                .await
+                .is_err()
+        );
+        assert!(reorder_problem_images(
+            &db,
+            problem_id,
+            vec![work.id, question.id, Uuid::new_v4()]
+        )
+        .await
+        .is_err());
+
+        let removed = remove_problem_image(&db, question.id).await.unwrap();
+        assert_eq!(removed.path, "Q1.png");
+        // Without a question image, the first image stands in
+        let primary = get_primary_image(&db, problem_id).await.unwrap().unwrap();
+        assert_eq!(primary.id, work.id);
+        assert!(remove_problem_image(&db, question.id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_is_image_path_referenced() {
+        let db = setup_test_db().await;
+        let problem_id = create_test_problem(&db, Some("blobs/ab/ab12.png")).await;
+        // The same bytes attached twice share one file
+        let copy = add_problem_image(
+            &db,
+            problem_id,
+            ImageRole::Work,
+            "blobs/ab/ab12.png".to_string(),
+        )
+        .await
+        .unwrap();
+
+        remove_problem_image(&db, copy.id).await.unwrap();
+        assert!(is_image_path_referenced(&db, "blobs/ab/ab12.png").await.unwrap());
+
+        // A processed image still needs its original
+        let question = get_primary_image(&db, problem_id).await.unwrap().unwrap();
+        set_problem_image_files(
+            &db,
+            question.id,
+            "blobs/cd/cd34.png".to_string(),
+            Some(question.path.clone()),
+        )
+        .await
+        .unwrap();
+        assert!(is_image_path_referenced(&db, "blobs/ab/ab12.png").await.unwrap());
+        assert!(is_image_path_referenced(&db, "blobs/cd/cd34.png").await.unwrap());
+
+        delete_problem(&db, problem_id).await.unwrap();
+        assert!(!is_image_path_referenced(&db, "blobs/ab/ab12.png").await.unwrap());
+        assert!(!is_image_path_referenced(&db, "blobs/cd/cd34.png").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_get_image_metadata() {
+        let db = setup_test_db().await;
+        let problem_id = create_test_problem(&db, None).await;
+        let at = Utc::now();
+
+        let metadata = get_image_metadata(&db, problem_id, ImageRole::Solution, at)
+            .await
+            .unwrap()
+            .unwrap();
+        let problem = Problem::find_by_id(problem_id).one(&db).await.unwrap().unwrap();
+        assert_eq!(metadata.problem_id, problem_id);
+        assert_eq!(metadata.title, problem.title);
+        assert_eq!(metadata.role, "solution");
+        assert_eq!(
+            (metadata.folder.as_str(), metadata.course.as_str(), metadata.set.as_str()),
+            ("Math", "Calculus", "Week 1")
+        );
+        assert_eq!(metadata.captured_at, at);
+
+        assert_eq!(
+            get_image_metadata(&db, Uuid::new_v4(), ImageRole::Question, at)
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_blob_refs_follow_image_files() {
+        let db = setup_test_db().await;
+        let blob = |bytes: &[u8]| {
+            let hash = storage::sha256_hex(bytes);
+            (hash.clone(), storage::blob_path(&hash, "png").to_string_lossy().to_string())
+        };
+        let (captured_hash, captured) = blob(b"captured");
+        let (cleaned_hash, cleaned) = blob(b"cleaned");
+
+        // Paths outside the store have no blob
+        create_test_problem(&db, Some("Math/Calculus/Week_1/Q0.png")).await;
+        let problem_id = create_test_problem(&db, Some(&captured)).await;
+        assert_eq!(
+            get_referenced_blob_hashes(&db).await.unwrap(),
+            [captured_hash.clone()].into_iter().collect()
+        );
+
+        let question = get_primary_image(&db, problem_id).await.unwrap().unwrap();
+        set_problem_image_files(&db, question.id, cleaned, Some(captured.clone()))
+            .await
+            .unwrap();
+        assert_eq!(
+            get_referenced_blob_hashes(&db).await.unwrap(),
+            [captured_hash.clone(), cleaned_hash].into_iter().collect()
+        );
+
+        set_problem_image_files(&db, question.id, captured, None)
+            .await
+            .unwrap();
+        assert_eq!(
+            get_referenced_blob_hashes(&db).await.unwrap(),
+            [captured_hash].into_iter().collect()
+        );
+
+        delete_problem(&db, problem_id).await.unwrap();
+        assert!(get_referenced_blob_hashes(&db).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_blob_uses() {
+        let db = setup_test_db().await;
+        let hash = storage::sha256_hex(b"captured");
+        let path = storage::blob_path(&hash, "png").to_string_lossy().to_string();
+        // The same bytes captured for two problems
+        let q1 = create_test_problem(&db, Some(&path)).await;
+        let q2 = create_test_problem(&db, Some(&path)).await;
+        let original_only = storage::blob_path(&storage::sha256_hex(b"original"), "png");
+        let question = get_primary_image(&db, q1).await.unwrap().unwrap();
+        set_problem_image_files(
+            &db,
+            question.id,
+            path.clone(),
+            Some(original_only.to_string_lossy().to_string()),
+        )
+        .await
+        .unwrap();
+
+        // Originals aren't images of their own, so they get no entry
+        let uses = get_blob_uses(&db, None).await.unwrap();
+        assert_eq!(uses.len(), 2);
+        assert!(uses.iter().all(|u| u.hash == hash && u.path == path));
+        let mut problem_ids: Vec<Uuid> = uses.iter().map(|u| u.metadata.problem_id).collect();
+        problem_ids.sort();
+        let mut expected = vec![q1, q2];
+        expected.sort();
+        assert_eq!(problem_ids, expected);
+
+        // Names are read at call time, so a rename shows up in the next refresh
+        let problem = Problem::find_by_id(q1).one(&db).await.unwrap().unwrap();
+        update_set(&db, problem.set_id, Some("Week 1 (limits)".to_string()), None, None)
+            .await
+            .unwrap();
+        let hashes: HashSet<String> = [hash].into_iter().collect();
+        let uses = get_blob_uses(&db, Some(&hashes)).await.unwrap();
+        assert!(uses.iter().all(|u| u.metadata.set == "Week 1 (limits)"
+            && u.metadata.folder == "Math"
+            && u.metadata.role == "question"));
+        let other: HashSet<String> = ["ff".repeat(32)].into_iter().collect();
+        assert!(get_blob_uses(&db, Some(&other)).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_images_are_deleted_with_their_problem() {
+        let db = setup_test_db().await;
+        let problem_id = create_test_problem(&db, Some("Q1.png")).await;
+        add_problem_image(
+            &db,
+            problem_id,
+            ImageRole::Solution,
+            "Q1_solution.png".to_string(),
+        )
+        .await
+        .unwrap();
+
+        delete_problem(&db, problem_id).await.unwrap();
+        assert!(get_problem_images(&db, problem_id)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_problems_with_images() {
+        let db = setup_test_db().await;
+        create_test_problem(&db, None).await;
+        let with_image = create_test_problem(&db, Some("Q1.png")).await;
+
+        let problems = get_problems_with_images(&db).await.unwrap();
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].0.id, with_image);
+        assert_eq!(problems[0].1.path, "Q1.png");
+    }
+}
@@ -1,7 +1,10 @@
 use sea_orm::*;
-use crate::db::entities::{problems, problems::Entity as Problem};
+use crate::db::entities::{problem_images::ImageRole, problems, problems::Entity as Problem};
+use crate::db::services::add_problem_image;
+use std::collections::HashSet;
 use uuid::Uuid;
 
+/// Create a problem. `image_path`, if given, becomes its question image.
 pub async fn create_problem(
     db: &DatabaseConnection,
     set_id: Uuid,
@@ -17,10 +20,13 @@ pub async fn create_problem(
         set_id: Set(set_id),
         title: Set(title),
         description: Set(description),
-        image_path: Set(image_path),
         s3_image_key: Set(s3_image_key),
         confidence_level: Set(0),
         notes: Set(None),
+        ocr_text: Set(None),
+        source_window_title: Set(None),
+        source_app_name: Set(None),
+        image_hash: Set(None),
         created_at: Set(now),
         updated_at: Set(now),
         last_attempted: Set(None),
@@ -30,7 +36,49 @@ pub async fn create_problem(
         last_modified: Set(now),
     };
 
-    problem.insert(db).await
+    let txn = db.begin().await?;
+    let problem = problem.insert(&txn).await?;
+    if let Some(image_path) = image_path {
+        add_problem_image(&txn, problem.id, ImageRole::Question, image_path).await?;
+    }
+    txn.commit().await?;
+
+    Ok(problem)
+}
+
+/// Recreate a problem with the id and capture time it had before, e.g. from
+/// the metadata in its image file. It starts with no images and no attempts.
+pub async fn restore_problem(
+    db: &DatabaseConnection,
+    id: Uuid,
+    set_id: Uuid,
+    title: String,
+    created_at: chrono::NaiveDateTime,
+) -> Result<problems::Model, DbErr> {
+    let now = chrono::Utc::now().naive_utc();
+
+    problems::ActiveModel {
+        id: Set(id),
+        set_id: Set(set_id),
+        title: Set(title),
+        description: Set(None),
+        s3_image_key: Set(None),
+        confidence_level: Set(0),
+        notes: Set(None),
+        ocr_text: Set(None),
+        source_window_title: Set(None),
+        source_app_name: Set(None),
+        image_hash: Set(None),
+        created_at: Set(created_at),
+        updated_at: Set(now),
+        last_attempted: Set(None),
+        attempt_count: Set(0),
+        success_rate: Set(0.0),
+        is_synced: Set(false),
+        last_modified: Set(now),
+    }
+    .insert(db)
+    .await
 }
 
 pub async fn get_problem_by_id(
@@ -51,12 +99,57 @@ pub async fn get_problems_by_set(
         .await
 }
 
+pub async fn count_problems_by_set(
+    db: &DatabaseConnection,
+    set_id: Uuid,
+) -> Result<u64, DbErr> {
+    Problem::find()
+        .filter(problems::Column::SetId.eq(set_id))
+        .count(db)
+        .await
+}
+
+/// Gives a problem that was just inserted `title`, or `title-2`, `title-3`,
+/// … if another problem in its set already has it. Run it in the transaction
+/// that inserted the problem: the insert holds SQLite's write lock, so a
+/// concurrent save can't claim the same title before this one commits.
+pub async fn claim_problem_title<C: ConnectionTrait>(
+    db: &C,
+    problem: problems::Model,
+) -> Result<problems::Model, DbErr> {
+    let taken: HashSet<String> = Problem::find()
+        .select_only()
+        .column(problems::Column::Title)
+        .filter(problems::Column::SetId.eq(problem.set_id))
+        .filter(problems::Column::Id.ne(problem.id))
+        .into_tuple::<String>()
+        .all(db)
+        .await?
+        .into_iter()
+        .collect();
+
+    if !taken.contains(&problem.title) {
+        return Ok(problem);
+    }
+    let title = (2..)
+        .map(|n| format!("{}-{}", problem.title, n))
+        .find(|candidate| !taken.contains(candidate))
+        .expect("some suffix is free");
+
+    problems::ActiveModel {
+        id: Set(problem.id),
+        title: Set(title),
+        ..Default::default()
+    }
+    .update(db)
+    .await
+}
+
 pub async fn update_problem(
     db: &DatabaseConnection,
     id: Uuid,
     title: Option<String>,
     description: Option<Option<String>>,
-    image_path: Option<Option<String>>,
     s3_image_key: Option<Option<String>>,
     confidence_level: Option<i32>,
     notes: Option<Option<String>>,
@@ -74,9 +167,6 @@ pub async fn update_problem(
     if let Some(d) = description {
         problem.description = Set(d);
     }
-    if let Some(ip) = image_path {
-        problem.image_path = Set(ip);
-    }
     if let Some(s3) = s3_image_key {
         problem.s3_image_key = Set(s3);
     }
@@ -125,8 +215,8 @@ pub async fn update_problem_stats(
     problem.update(db).await
 }
 
-pub async fn delete_problem(
-    db: &DatabaseConnection,
+pub async fn delete_problem<C: ConnectionTrait>(
+    db: &C,
     id: Uuid,
 ) -> Result<DeleteResult, DbErr> {
     Problem::delete_by_id(id).exec(db).await
@@ -339,6 +429,61 @@ mod tests {
         assert_eq!(problems.len(), 3);
     }
 
+    #[tokio::test]
+    async fn test_count_problems_by_set() {
+        let db = setup_test_db().await;
+        let set_id = create_test_set(&db).await;
+
+        assert_eq!(count_problems_by_set(&db, set_id).await.unwrap(), 0);
+
+        create_problem(&db, set_id, "Problem 1".to_string(), None, None, None)
+            .await
+            .expect("Failed to create problem");
+        create_problem(&db, set_id, "Problem 2".to_string(), None, None, None)
+            .await
+            .expect("Failed to create problem");
+
+        assert_eq!(count_problems_by_set(&db, set_id).await.unwrap(), 2);
+        assert_eq!(count_problems_by_set(&db, Uuid::new_v4()).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_claim_problem_title() {
+        let db = setup_test_db().await;
+        let set_id = create_test_set(&db).await;
+        let title = "problem-20260101-120000".to_string();
+
+        let first = create_problem(&db, set_id, title.clone(), None, None, None)
+            .await
+            .expect("Failed to create problem");
+        assert_eq!(claim_problem_title(&db, first).await.unwrap().title, title);
+
+        let second = create_problem(&db, set_id, title.clone(), None, None, None)
+            .await
+            .expect("Failed to create problem");
+        let second = claim_problem_title(&db, second).await.unwrap();
+        assert_eq!(second.title, "problem-20260101-120000-2");
+        assert_eq!(
+            Problem::find_by_id(second.id).one(&db).await.unwrap().unwrap().title,
+            second.title
+        );
+
+        let third = create_problem(&db, set_id, title.clone(), None, None, None)
+            .await
+            .expect("Failed to create problem");
+        assert_eq!(
+            claim_problem_title(&db, third).await.unwrap().title,
+            "problem-20260101-120000-3"
+        );
+
+        // Titles only need to be unique within a set
+        let other_set = create_test_set(&db).await;
+        let elsewhere = create_problem(&db, other_set, title.clone(), None, None, None)
+            .await
+            .expect("Failed to create problem");
+        assert_eq!(claim_problem_title(&db, elsewhere).await.unwrap().title, title);
+    }
+
     #[tokio::test]
     async fn test_update_problem() {
         let db = setup_test_db().await;
@@ -361,7 +506,6 @@ mod tests {
             Some("Updated Title".to_string()),
             Some(Some("New description".to_string())),
             None,
-            None,
             Some(5),
             Some(Some("Some notes".to_string())),
         )
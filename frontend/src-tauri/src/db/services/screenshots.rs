@@ -3,9 +3,11 @@ use crate::db::entities::{
     folders, folders::Entity as Folder,
     courses, courses::Entity as Course,
     sets, sets::Entity as SetEntity,
+    problem_images::ImageRole,
     problems,
     users, users::Entity as User,
 };
+use crate::db::services::{add_problem_image, claim_problem_title};
 use crate::dtos::screenshot::ScreenshotDto;
 use uuid::Uuid;
 
@@ -180,7 +182,9 @@ pub async fn find_or_create_set(
     set.insert(db).await
 }
 
-/// Save screenshot data to database, creating all necessary hierarchy entries
+/// Save screenshot data to database, creating all necessary hierarchy entries.
+/// `image_path` becomes the problem's question image. The problem's title is
+/// `dto.problem_name`, with a suffix if the set already has it.
 pub async fn save_screenshot_to_db(
     db: &DatabaseConnection,
     dto: ScreenshotDto,
@@ -201,14 +205,17 @@ pub async fn save_screenshot_to_db(
     // Create problem with the screenshot
     let now = chrono::Utc::now().naive_utc();
     let problem = problems::ActiveModel {
-        id: Set(Uuid::new_v4()),
+        id: Set(dto.problem_id),
         set_id: Set(set.id),
         title: Set(dto.problem_name),
         description: Set(None),
-        image_path: Set(Some(image_path)),
         s3_image_key: Set(None),
         confidence_level: Set(0),
         notes: Set(None),
+        ocr_text: Set(dto.ocr_text),
+        source_window_title: Set(dto.source_window_title),
+        source_app_name: Set(dto.source_app_name),
+        image_hash: Set(dto.image_hash),
         created_at: Set(now),
         updated_at: Set(now),
         last_attempted: Set(None),
@@ -218,7 +225,15 @@ pub async fn save_screenshot_to_db(
         last_modified: Set(now),
     };
 
-    problem.insert(db).await
+    // Captures in the same second get the same generated name; the insert
+    // comes first so the title is claimed under the write lock
+    let txn = db.begin().await?;
+    let problem = problem.insert(&txn).await?;
+    let problem = claim_problem_title(&txn, problem).await?;
+    add_problem_image(&txn, problem.id, ImageRole::Question, image_path).await?;
+    txn.commit().await?;
+
+    Ok(problem)
 }
 
 #[cfg(test)]
@@ -431,16 +446,46 @@ mod tests {
         assert_eq!(set2.sort_order, 1);
     }
 
+    #[tokio::test]
+    async fn test_save_screenshot_to_db_records_source_window() {
+        let db = setup_test_db().await;
+
+        let dto = ScreenshotDto {
+            problem_id: Uuid::new_v4(),
+            folder_name: "Math".to_string(),
+            course_name: "Calculus".to_string(),
+            set_name: "Week 1".to_string(),
+            problem_name: "Q1".to_string(),
+            base64_data: "test_base64_data".to_string(),
+            ocr_text: None,
+            source_window_title: Some("Stewart Calculus.pdf".to_string()),
+            source_app_name: Some("Preview".to_string()),
+            image_hash: None,
+        };
+
+        let problem = save_screenshot_to_db(&db, dto, "Math/Calculus/Week_1/Q1".to_string())
+            .await
+            .expect("Failed to save screenshot");
+
+        assert_eq!(problem.source_window_title, Some("Stewart Calculus.pdf".to_string()));
+        assert_eq!(problem.source_app_name, Some("Preview".to_string()));
+    }
+
     #[tokio::test]
     async fn test_save_screenshot_to_db_creates_full_hierarchy() {
         let db = setup_test_db().await;
 
         let dto = ScreenshotDto {
+            problem_id: Uuid::new_v4(),
             folder_name: "Computer Science".to_string(),
             course_name: "Data Structures & Algorithms".to_string(),
             set_name: "Binary Trees".to_string(),
             problem_name: "Lowest Common Ancestor".to_string(),
             base64_data: "test_base64_data".to_string(),
+            ocr_text: None,
+            source_window_title: None,
+            source_app_name: None,
+            image_hash: None,
         };
 
         let image_path = "Computer_Science/Data_Structures_&_Algorithms/Binary_Trees/Lowest_Common_Ancestor.png".to_string();
@@ -451,7 +496,12 @@ mod tests {
 
         // Verify problem was created
         assert_eq!(problem.title, "Lowest Common Ancestor");
-        assert_eq!(problem.image_path, Some(image_path.clone()));
+        let images = crate::db::services::get_problem_images(&db, problem.id)
+            .await
+            .expect("Query failed");
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].role, ImageRole::Question);
+        assert_eq!(images[0].path, image_path);
 
         // Verify hierarchy was created
         let user = User::find()
@@ -488,24 +538,81 @@ mod tests {
         assert_eq!(problem.set_id, set.id);
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_concurrent_saves_get_distinct_titles() {
+        // A file, so the saves run on separate connections
+        let dir = tempfile::tempdir().unwrap();
+        let db_url = format!("sqlite://{}?mode=rwc", dir.path().join("plutodesk.db").display());
+        let db = Database::connect(&db_url).await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        let dto = || ScreenshotDto {
+            problem_id: Uuid::new_v4(),
+            folder_name: "Math".to_string(),
+            course_name: "Calculus".to_string(),
+            set_name: "Week 1".to_string(),
+            problem_name: "problem-20260101-120000".to_string(),
+            base64_data: String::new(),
+            ocr_text: None,
+            source_window_title: None,
+            source_app_name: None,
+            image_hash: None,
+        };
+        // The hierarchy exists before the race, as it does for an active session
+        save_screenshot_to_db(&db, dto(), "Q0.png".to_string())
+            .await
+            .unwrap();
+
+        let saves: Vec<_> = (1..=4)
+            .map(|n| {
+                let db = db.clone();
+                let dto = dto();
+                tokio::spawn(async move {
+                    save_screenshot_to_db(&db, dto, format!("Q{}.png", n)).await
+                })
+            })
+            .collect();
+        let mut titles = Vec::new();
+        for save in saves {
+            titles.push(save.await.unwrap().unwrap().title);
+        }
+        titles.sort();
+        assert_eq!(
+            titles,
+            (2..=5)
+                .map(|n| format!("problem-20260101-120000-{}", n))
+                .collect::<Vec<_>>()
+        );
+    }
+
     #[tokio::test]
     async fn test_save_screenshot_to_db_reuses_existing_hierarchy() {
         let db = setup_test_db().await;
 
         let dto1 = ScreenshotDto {
+            problem_id: Uuid::new_v4(),
             folder_name: "Computer Science".to_string(),
             course_name: "Data Structures & Algorithms".to_string(),
             set_name: "Binary Trees".to_string(),
             problem_name: "Problem 1".to_string(),
             base64_data: "test_base64_data_1".to_string(),
+            ocr_text: None,
+            source_window_title: None,
+            source_app_name: None,
+            image_hash: None,
         };
 
         let dto2 = ScreenshotDto {
+            problem_id: Uuid::new_v4(),
             folder_name: "Computer Science".to_string(),
             course_name: "Data Structures & Algorithms".to_string(),
             set_name: "Binary Trees".to_string(),
             problem_name: "Problem 2".to_string(),
             base64_data: "test_base64_data_2".to_string(),
+            ocr_text: None,
+            source_window_title: None,
+            source_app_name: None,
+            image_hash: None,
         };
 
         let image_path1 = "Computer_Science/Data_Structures_&_Algorithms/Binary_Trees/Problem_1.png".to_string();
@@ -0,0 +1,266 @@
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Default and maximum number of hits returned by [`search_problems`]
+pub const DEFAULT_SEARCH_LIMIT: u64 = 50;
+pub const MAX_SEARCH_LIMIT: u64 = 200;
+
+/// Optional restriction of a search to part of the hierarchy.
+/// When several ids are given, all of them must match.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchScope {
+    pub folder_id: Option<Uuid>,
+    pub course_id: Option<Uuid>,
+    pub set_id: Option<Uuid>,
+}
+
+/// A ranked search result with its place in the hierarchy.
+/// `snippet` marks matched terms with `<mark>`…`</mark>`; everything else is raw text.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromQueryResult)]
+pub struct ProblemSearchHit {
+    pub problem_id: Uuid,
+    pub title: String,
+    pub snippet: String,
+    /// bm25 score; lower is a better match
+    pub rank: f64,
+    pub folder_id: Uuid,
+    pub folder_name: String,
+    pub course_id: Uuid,
+    pub course_name: String,
+    pub set_id: Uuid,
+    pub set_name: String,
+}
+
+/// Turns free text into an FTS5 query: every word must match, as a prefix.
+/// Quoting each word keeps FTS5 operators in user input from being interpreted.
+fn to_fts_query(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split_whitespace()
+        .map(|word| word.replace('"', ""))
+        .filter(|word| !word.is_empty())
+        .map(|word| format!("\"{}\"*", word))
+        .collect();
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+/// Full-text search over problem title, description, notes and OCR text
+pub async fn search_problems(
+    db: &DatabaseConnection,
+    query: &str,
+    scope: SearchScope,
+    limit: Option<u64>,
+) -> Result<Vec<ProblemSearchHit>, DbErr> {
+    let Some(fts_query) = to_fts_query(query) else {
+        return Ok(Vec::new());
+    };
+
+    let mut sql = String::from(
+        "SELECT
+            p.id AS problem_id,
+            p.title AS title,
+            snippet(problems_fts, -1, '<mark>', '</mark>', '…', 12) AS snippet,
+            bm25(problems_fts) AS rank,
+            f.id AS folder_id,
+            f.name AS folder_name,
+            c.id AS course_id,
+            c.name AS course_name,
+            s.id AS set_id,
+            s.name AS set_name
+        FROM problems_fts
+        JOIN problems p ON p.id = problems_fts.problem_id
+        JOIN sets s ON s.id = p.set_id
+        JOIN courses c ON c.id = s.course_id
+        JOIN folders f ON f.id = c.folder_id
+        WHERE problems_fts MATCH ?",
+    );
+    let mut values: Vec<Value> = vec![fts_query.into()];
+
+    if let Some(folder_id) = scope.folder_id {
+        sql.push_str(" AND f.id = ?");
+        values.push(folder_id.into());
+    }
+    if let Some(course_id) = scope.course_id {
+        sql.push_str(" AND c.id = ?");
+        values.push(course_id.into());
+    }
+    if let Some(set_id) = scope.set_id {
+        sql.push_str(" AND s.id = ?");
+        values.push(set_id.into());
+    }
+
+    sql.push_str(" ORDER BY rank LIMIT ?");
+    let limit = limit.unwrap_or(DEFAULT_SEARCH_LIMIT).clamp(1, MAX_SEARCH_LIMIT);
+    values.push((limit as i64).into());
+
+    ProblemSearchHit::find_by_statement(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        sql,
+        values,
+    ))
+    .all(db)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::services::test_fixtures::{create_set, setup_test_db};
+    use crate::db::services::{create_problem, delete_problem, delete_set, update_problem};
+
+    #[test]
+    fn test_to_fts_query() {
+        assert_eq!(to_fts_query("chain rule"), Some("\"chain\"* \"rule\"*".to_string()));
+        assert_eq!(to_fts_query("  \"quoted\" OR"), Some("\"quoted\"* \"OR\"*".to_string()));
+        assert_eq!(to_fts_query("   "), None);
+        assert_eq!(to_fts_query("\"\""), None);
+    }
+
+    #[tokio::test]
+    async fn test_search_matches_title_description_and_notes() {
+        let db = setup_test_db().await;
+        let set_id = create_set(&db, "Calculus", "Derivatives").await.set_id;
+
+        let by_title = create_problem(&db, set_id, "Chain rule practice".to_string(), None, None, None)
+            .await
+            .unwrap();
+        let by_description = create_problem(
+            &db,
+            set_id,
+            "Q2".to_string(),
+            Some("Apply the chain rule twice".to_string()),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        let unrelated = create_problem(&db, set_id, "Limits".to_string(), None, None, None)
+            .await
+            .unwrap();
+        update_problem(
+            &db,
+            unrelated.id,
+            None,
+            None,
+            None,
+            None,
+            Some(Some("remember the squeeze theorem".to_string())),
+        )
+        .await
+        .unwrap();
+
+        let hits = search_problems(&db, "chain", SearchScope::default(), None)
+            .await
+            .unwrap();
+        let ids: Vec<Uuid> = hits.iter().map(|h| h.problem_id).collect();
+        assert_eq!(hits.len(), 2);
+        assert!(ids.contains(&by_title.id));
+        assert!(ids.contains(&by_description.id));
+        assert_eq!(hits[0].folder_name, "Math");
+        assert_eq!(hits[0].course_name, "Calculus");
+        assert_eq!(hits[0].set_name, "Derivatives");
+        assert!(hits.iter().any(|h| h.snippet.contains("<mark>")));
+
+        // Notes are indexed after update; prefix matching
+        let hits = search_problems(&db, "squee", SearchScope::default(), None)
+            .await
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].problem_id, unrelated.id);
+    }
+
+    #[tokio::test]
+    async fn test_search_indexes_ocr_text() {
+        let db = setup_test_db().await;
+
+        let dto = crate::dtos::screenshot::ScreenshotDto {
+            problem_id: Uuid::new_v4(),
+            folder_name: "Math".to_string(),
+            course_name: "Calculus".to_string(),
+            set_name: "Integrals".to_string(),
+            problem_name: "problem-20260101-120000".to_string(),
+            base64_data: String::new(),
+            ocr_text: Some("Evaluate the integral by substitution".to_string()),
+            source_window_title: None,
+            source_app_name: None,
+            image_hash: None,
+        };
+        let problem = crate::db::services::save_screenshot_to_db(&db, dto, "x".to_string())
+            .await
+            .unwrap();
+
+        let hits = search_problems(&db, "substitution", SearchScope::default(), None)
+            .await
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].problem_id, problem.id);
+    }
+
+    #[tokio::test]
+    async fn test_search_scope_filters() {
+        let db = setup_test_db().await;
+        let derivatives = create_set(&db, "Calculus", "Derivatives").await.set_id;
+        let vectors = create_set(&db, "Linear Algebra", "Vectors").await.set_id;
+
+        create_problem(&db, derivatives, "Practice one".to_string(), None, None, None)
+            .await
+            .unwrap();
+        create_problem(&db, vectors, "Practice two".to_string(), None, None, None)
+            .await
+            .unwrap();
+
+        let all = search_problems(&db, "practice", SearchScope::default(), None)
+            .await
+            .unwrap();
+        assert_eq!(all.len(), 2);
+
+        let scoped = search_problems(
+            &db,
+            "practice",
+            SearchScope {
+                set_id: Some(vectors),
+                ..Default::default()
+            },
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(scoped.len(), 1);
+        assert_eq!(scoped[0].set_name, "Vectors");
+
+        let limited = search_problems(&db, "practice", SearchScope::default(), Some(1))
+            .await
+            .unwrap();
+        assert_eq!(limited.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_search_index_follows_deletes() {
+        let db = setup_test_db().await;
+        let set_id = create_set(&db, "Calculus", "Derivatives").await.set_id;
+
+        let problem = create_problem(&db, set_id, "Chain rule".to_string(), None, None, None)
+            .await
+            .unwrap();
+        delete_problem(&db, problem.id).await.unwrap();
+        assert!(search_problems(&db, "chain", SearchScope::default(), None)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // Cascade delete through the set
+        create_problem(&db, set_id, "Product rule".to_string(), None, None, None)
+            .await
+            .unwrap();
+        delete_set(&db, set_id).await.unwrap();
+        assert!(search_problems(&db, "product", SearchScope::default(), None)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
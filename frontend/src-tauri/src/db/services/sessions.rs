@@ -0,0 +1,306 @@
+use sea_orm::*;
+use uuid::Uuid;
+
+use crate::db::entities::{
+    app_state, app_state::Entity as AppState, courses::Entity as Course, folders::Entity as Folder,
+    sessions, sessions::Entity as Session, sets::Entity as SetEntity,
+};
+
+/// Result of importing sessions saved outside the database
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SessionImport {
+    pub imported: usize,
+    /// Sessions whose folder, course or set no longer exists, or that repeat
+    /// one already in the database
+    pub skipped: usize,
+}
+
+/// All saved sessions, oldest first
+pub async fn get_all_sessions(db: &DatabaseConnection) -> Result<Vec<sessions::Model>, DbErr> {
+    Session::find()
+        .order_by_asc(sessions::Column::CreatedAt)
+        .order_by_asc(sessions::Column::Id)
+        .all(db)
+        .await
+}
+
+pub async fn get_app_state<C: ConnectionTrait>(db: &C) -> Result<app_state::Model, DbErr> {
+    AppState::find_by_id(app_state::ID)
+        .one(db)
+        .await?
+        .ok_or(DbErr::RecordNotFound("App state not found".to_string()))
+}
+
+async fn set_active_session_id<C: ConnectionTrait>(
+    db: &C,
+    session_id: Option<Uuid>,
+) -> Result<(), DbErr> {
+    app_state::ActiveModel {
+        id: Set(app_state::ID),
+        active_session_id: Set(session_id),
+        ..Default::default()
+    }
+    .update(db)
+    .await?;
+    Ok(())
+}
+
+/// Saves a new session, making it the active one if `start` is set
+pub async fn create_session(
+    db: &DatabaseConnection,
+    session: sessions::Model,
+    start: bool,
+) -> Result<sessions::Model, DbErr> {
+    let txn = db.begin().await?;
+    let session = sessions::ActiveModel::from(session).insert(&txn).await?;
+    if start {
+        set_active_session_id(&txn, Some(session.id)).await?;
+    }
+    txn.commit().await?;
+    Ok(session)
+}
+
+/// Makes a saved session the active one and marks it as used now
+pub async fn start_session(
+    db: &DatabaseConnection,
+    session_id: Uuid,
+) -> Result<sessions::Model, DbErr> {
+    let txn = db.begin().await?;
+    let mut session: sessions::ActiveModel = Session::find_by_id(session_id)
+        .one(&txn)
+        .await?
+        .ok_or(DbErr::RecordNotFound("Session not found".to_string()))?
+        .into();
+    session.last_used = Set(chrono::Utc::now().naive_utc());
+    let session = session.update(&txn).await?;
+    set_active_session_id(&txn, Some(session_id)).await?;
+    txn.commit().await?;
+    Ok(session)
+}
+
+/// Clears the active session. The session itself stays saved.
+pub async fn end_session(db: &DatabaseConnection) -> Result<(), DbErr> {
+    set_active_session_id(db, None).await
+}
+
+/// Deletes a saved session. If it was active, the foreign key clears
+/// `app_state.active_session_id` with it.
+pub async fn delete_session(db: &DatabaseConnection, session_id: Uuid) -> Result<(), DbErr> {
+    let result = Session::delete_by_id(session_id).exec(db).await?;
+    if result.rows_affected == 0 {
+        return Err(DbErr::RecordNotFound("Session not found".to_string()));
+    }
+    Ok(())
+}
+
+/// Whether the session's folder, course and set all exist
+async fn context_exists<C: ConnectionTrait>(
+    db: &C,
+    session: &sessions::Model,
+) -> Result<bool, DbErr> {
+    Ok(Folder::find_by_id(session.folder_id)
+        .one(db)
+        .await?
+        .is_some()
+        && Course::find_by_id(session.course_id)
+            .one(db)
+            .await?
+            .is_some()
+        && SetEntity::find_by_id(session.set_id)
+            .one(db)
+            .await?
+            .is_some())
+}
+
+/// Adds sessions saved before they lived in the database, and makes
+/// `active_session_id` active if it was among them. Runs once: afterwards
+/// `app_state.sessions_imported_at` is set and this returns `None`.
+pub async fn import_sessions(
+    db: &DatabaseConnection,
+    sessions: Vec<sessions::Model>,
+    active_session_id: Option<Uuid>,
+) -> Result<Option<SessionImport>, DbErr> {
+    let txn = db.begin().await?;
+    let state = get_app_state(&txn).await?;
+    if state.sessions_imported_at.is_some() {
+        return Ok(None);
+    }
+
+    let mut result = SessionImport::default();
+    let mut active = None;
+    for session in sessions {
+        let context_exists = context_exists(&txn, &session).await?;
+        let duplicate = Session::find()
+            .filter(
+                Condition::any()
+                    .add(sessions::Column::Id.eq(session.id))
+                    .add(
+                        Condition::all()
+                            .add(sessions::Column::FolderId.eq(session.folder_id))
+                            .add(sessions::Column::CourseId.eq(session.course_id))
+                            .add(sessions::Column::SetId.eq(session.set_id)),
+                    ),
+            )
+            .one(&txn)
+            .await?
+            .is_some();
+        if !context_exists || duplicate {
+            result.skipped += 1;
+            continue;
+        }
+
+        if active_session_id == Some(session.id) {
+            active = Some(session.id);
+        }
+        sessions::ActiveModel::from(session).insert(&txn).await?;
+        result.imported += 1;
+    }
+
+    app_state::ActiveModel {
+        id: Set(app_state::ID),
+        // A session started before a late import stays active
+        active_session_id: Set(state.active_session_id.or(active)),
+        sessions_imported_at: Set(Some(chrono::Utc::now().naive_utc())),
+    }
+    .update(&txn)
+    .await?;
+    txn.commit().await?;
+
+    Ok(Some(result))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::services::delete_set;
+    use crate::db::services::test_fixtures::{create_set, setup_test_db};
+
+    async fn session_for(db: &DatabaseConnection, set_name: &str) -> sessions::Model {
+        let set = create_set(db, "Calculus", set_name).await;
+        let now = chrono::Utc::now().naive_utc();
+        sessions::Model {
+            id: Uuid::new_v4(),
+            name: format!("Math / Calculus / {}", set_name),
+            folder_id: set.folder_id,
+            course_id: set.course_id,
+            set_id: set.set_id,
+            created_at: now,
+            last_used: now,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_and_start_sessions() {
+        let db = setup_test_db().await;
+        let week1 = session_for(&db, "Week 1").await;
+        let week2 = session_for(&db, "Week 2").await;
+
+        create_session(&db, week1.clone(), false).await.unwrap();
+        assert_eq!(get_app_state(&db).await.unwrap().active_session_id, None);
+        create_session(&db, week2.clone(), true).await.unwrap();
+        assert_eq!(
+            get_all_sessions(&db).await.unwrap(),
+            vec![week1.clone(), week2.clone()]
+        );
+        assert_eq!(
+            get_app_state(&db).await.unwrap().active_session_id,
+            Some(week2.id)
+        );
+
+        // Switching marks the session used and leaves the others alone
+        let started = start_session(&db, week1.id).await.unwrap();
+        assert!(started.last_used > week1.last_used);
+        assert_eq!(
+            get_all_sessions(&db).await.unwrap(),
+            vec![started, week2.clone()]
+        );
+        assert_eq!(
+            get_app_state(&db).await.unwrap().active_session_id,
+            Some(week1.id)
+        );
+
+        end_session(&db).await.unwrap();
+        assert_eq!(get_app_state(&db).await.unwrap().active_session_id, None);
+        assert_eq!(get_all_sessions(&db).await.unwrap().len(), 2);
+
+        assert!(matches!(
+            start_session(&db, Uuid::new_v4()).await,
+            Err(DbErr::RecordNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_delete_session() {
+        let db = setup_test_db().await;
+        let week1 = session_for(&db, "Week 1").await;
+        let week2 = session_for(&db, "Week 2").await;
+        create_session(&db, week1.clone(), false).await.unwrap();
+        create_session(&db, week2.clone(), true).await.unwrap();
+
+        delete_session(&db, week1.id).await.unwrap();
+        assert_eq!(get_all_sessions(&db).await.unwrap(), vec![week2.clone()]);
+        assert_eq!(
+            get_app_state(&db).await.unwrap().active_session_id,
+            Some(week2.id)
+        );
+
+        // Deleting the active session ends it
+        delete_session(&db, week2.id).await.unwrap();
+        assert!(get_all_sessions(&db).await.unwrap().is_empty());
+        assert_eq!(get_app_state(&db).await.unwrap().active_session_id, None);
+
+        assert!(matches!(
+            delete_session(&db, week2.id).await,
+            Err(DbErr::RecordNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_deleting_a_set_removes_its_sessions() {
+        let db = setup_test_db().await;
+        let week1 = session_for(&db, "Week 1").await;
+        let week2 = session_for(&db, "Week 2").await;
+        create_session(&db, week1.clone(), true).await.unwrap();
+        create_session(&db, week2.clone(), false).await.unwrap();
+
+        delete_set(&db, week1.set_id).await.unwrap();
+
+        assert_eq!(get_all_sessions(&db).await.unwrap(), vec![week2]);
+        assert_eq!(get_app_state(&db).await.unwrap().active_session_id, None);
+    }
+
+    #[tokio::test]
+    async fn test_import_sessions_runs_once() {
+        let db = setup_test_db().await;
+        let week1 = session_for(&db, "Week 1").await;
+        let dangling = sessions::Model {
+            id: Uuid::new_v4(),
+            set_id: Uuid::new_v4(),
+            ..session_for(&db, "Week 2").await
+        };
+        let repeat = sessions::Model {
+            id: Uuid::new_v4(),
+            ..week1.clone()
+        };
+
+        let result = import_sessions(&db, vec![week1.clone(), dangling, repeat], Some(week1.id))
+            .await
+            .unwrap();
+        assert_eq!(
+            result,
+            Some(SessionImport {
+                imported: 1,
+                skipped: 2,
+            })
+        );
+        assert_eq!(get_all_sessions(&db).await.unwrap(), vec![week1.clone()]);
+        let state = get_app_state(&db).await.unwrap();
+        assert_eq!(state.active_session_id, Some(week1.id));
+        assert!(state.sessions_imported_at.is_some());
+
+        // A second import is a no-op
+        let week2 = session_for(&db, "Week 2").await;
+        assert_eq!(import_sessions(&db, vec![week2], None).await.unwrap(), None);
+        assert_eq!(get_all_sessions(&db).await.unwrap(), vec![week1]);
+    }
+}
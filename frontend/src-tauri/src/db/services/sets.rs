@@ -73,8 +73,8 @@ pub async fn update_set(
     set.update(db).await
 }
 
-pub async fn delete_set(
-    db: &DatabaseConnection,
+pub async fn delete_set<C: ConnectionTrait>(
+    db: &C,
     id: Uuid,
 ) -> Result<DeleteResult, DbErr> {
     SetEntity::delete_by_id(id).exec(db).await
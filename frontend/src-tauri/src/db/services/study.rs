@@ -0,0 +1,440 @@
+use chrono::{NaiveDate, NaiveDateTime, TimeZone};
+use sea_orm::sea_query::Expr;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::db::entities::{
+    courses::Entity as Course, folders::Entity as Folder, sets::Entity as SetEntity,
+    study_intervals, study_intervals::Entity as StudyInterval,
+};
+
+/// What a running interval is recorded against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StudyContext {
+    pub session_id: Uuid,
+    pub folder_id: Uuid,
+    pub course_id: Uuid,
+    pub set_id: Uuid,
+}
+
+impl StudyContext {
+    fn is_recorded_by(&self, interval: &study_intervals::Model) -> bool {
+        interval.session_id == Some(self.session_id)
+            && interval.folder_id == self.folder_id
+            && interval.course_id == self.course_id
+            && interval.set_id == self.set_id
+    }
+}
+
+/// How study time is totalled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StudyGrouping {
+    Day,
+    Folder,
+    Course,
+    Set,
+}
+
+impl StudyGrouping {
+    /// Name of the folder, course or set `id`; `None` for days
+    pub(crate) async fn name_of<C: ConnectionTrait>(
+        self,
+        db: &C,
+        id: Uuid,
+    ) -> Result<Option<String>, DbErr> {
+        Ok(match self {
+            Self::Folder => Folder::find_by_id(id).one(db).await?.map(|f| f.name),
+            Self::Course => Course::find_by_id(id).one(db).await?.map(|c| c.name),
+            Self::Set => SetEntity::find_by_id(id).one(db).await?.map(|s| s.name),
+            Self::Day => None,
+        })
+    }
+
+    /// The folder, course or set a record is grouped under; `None` for days
+    pub(crate) fn id_of(self, folder_id: Uuid, course_id: Uuid, set_id: Uuid) -> Option<Uuid> {
+        match self {
+            Self::Folder => Some(folder_id),
+            Self::Course => Some(course_id),
+            Self::Set => Some(set_id),
+            Self::Day => None,
+        }
+    }
+}
+
+/// Amounts added up per day, or per folder, course or set
+#[derive(Debug, Default)]
+pub(crate) struct Tally {
+    by_day: HashMap<NaiveDate, i64>,
+    by_id: HashMap<Uuid, i64>,
+}
+
+impl Tally {
+    pub(crate) fn add_day(&mut self, day: NaiveDate, amount: i64) {
+        *self.by_day.entry(day).or_default() += amount;
+    }
+
+    pub(crate) fn add_id(&mut self, id: Uuid, amount: i64) {
+        *self.by_id.entry(id).or_default() += amount;
+    }
+
+    /// Key, name and amount of each group: days in date order, the rest
+    /// largest first
+    pub(crate) async fn into_groups<C: ConnectionTrait>(
+        self,
+        db: &C,
+        grouping: StudyGrouping,
+    ) -> Result<Vec<(String, Option<String>, i64)>, DbErr> {
+        if grouping == StudyGrouping::Day {
+            let mut groups: Vec<_> = self
+                .by_day
+                .into_iter()
+                .map(|(day, amount)| (day.to_string(), None, amount))
+                .collect();
+            groups.sort_by(|a, b| a.0.cmp(&b.0));
+            return Ok(groups);
+        }
+
+        let mut groups = Vec::new();
+        for (id, amount) in self.by_id {
+            groups.push((id.to_string(), grouping.name_of(db, id).await?, amount));
+        }
+        groups.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| a.0.cmp(&b.0)));
+        Ok(groups)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StudyTotal {
+    /// `YYYY-MM-DD` for days, otherwise the folder, course or set id
+    pub key: String,
+    /// Folder, course or set name; `None` for days
+    pub name: Option<String>,
+    pub seconds: i64,
+}
+
+/// Intervals that are still running
+pub async fn get_open_intervals<C: ConnectionTrait>(
+    db: &C,
+) -> Result<Vec<study_intervals::Model>, DbErr> {
+    StudyInterval::find()
+        .filter(study_intervals::Column::EndedAt.is_null())
+        .order_by_asc(study_intervals::Column::StartedAt)
+        .all(db)
+        .await
+}
+
+/// Makes the running interval match `context`. An interval already running
+/// for it is kept; any other is closed at `now`, and one for `context` opened.
+/// `None` closes everything, e.g. when the session ends.
+pub async fn record_study_context(
+    db: &DatabaseConnection,
+    context: Option<StudyContext>,
+    now: NaiveDateTime,
+) -> Result<(), DbErr> {
+    // Read in the transaction, so two callers can't both see nothing running
+    // and each open an interval
+    let txn = db.begin().await?;
+    let open = get_open_intervals(&txn).await?;
+    let mut running = false;
+    for interval in open {
+        if !running && context.is_some_and(|c| c.is_recorded_by(&interval)) {
+            running = true;
+            continue;
+        }
+        // `now` may be in the past, e.g. the last input before going idle
+        let ended_at = now.max(interval.started_at);
+        study_intervals::ActiveModel {
+            id: Set(interval.id),
+            ended_at: Set(Some(ended_at)),
+            last_seen_at: Set(ended_at),
+            ..Default::default()
+        }
+        .update(&txn)
+        .await?;
+    }
+
+    if let (Some(context), false) = (context, running) {
+        study_intervals::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            session_id: Set(Some(context.session_id)),
+            folder_id: Set(context.folder_id),
+            course_id: Set(context.course_id),
+            set_id: Set(context.set_id),
+            started_at: Set(now),
+            ended_at: Set(None),
+            last_seen_at: Set(now),
+        }
+        .insert(&txn)
+        .await?;
+    }
+    txn.commit().await
+}
+
+/// Records that the app is still running with the open intervals
+pub async fn touch_open_intervals(
+    db: &DatabaseConnection,
+    now: NaiveDateTime,
+) -> Result<u64, DbErr> {
+    let result = StudyInterval::update_many()
+        .col_expr(study_intervals::Column::LastSeenAt, Expr::value(now))
+        .filter(study_intervals::Column::EndedAt.is_null())
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected)
+}
+
+/// Closes intervals left open when the app last stopped, at the last time it
+/// was seen running with them
+pub async fn close_stale_intervals(db: &DatabaseConnection) -> Result<u64, DbErr> {
+    let result = StudyInterval::update_many()
+        .col_expr(
+            study_intervals::Column::EndedAt,
+            Expr::col(study_intervals::Column::LastSeenAt).into(),
+        )
+        .filter(study_intervals::Column::EndedAt.is_null())
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected)
+}
+
+/// Splits `[start, end)` at midnights in `tz`, giving the seconds on each day
+fn seconds_per_day<Tz: TimeZone>(
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+    tz: &Tz,
+) -> Vec<(NaiveDate, i64)> {
+    let mut days = Vec::new();
+    let mut at = start;
+    while at < end {
+        let day = tz.from_utc_datetime(&at).date_naive();
+        let next_midnight = day
+            .succ_opt()
+            .and_then(|next| {
+                tz.from_local_datetime(&next.and_hms_opt(0, 0, 0)?)
+                    .earliest()
+            })
+            .map(|midnight| midnight.naive_utc())
+            .filter(|midnight| *midnight > at)
+            .map_or(end, |midnight| midnight.min(end));
+        days.push((day, (next_midnight - at).num_seconds()));
+        at = next_midnight;
+    }
+    days
+}
+
+/// Time studied between `from` and `to` (either open-ended), totalled per
+/// `grouping`. Days are calendar days in `tz`; running intervals count up to
+/// `now`. Days come in date order, the rest longest first.
+pub async fn get_study_totals<Tz: TimeZone>(
+    db: &DatabaseConnection,
+    grouping: StudyGrouping,
+    from: Option<NaiveDateTime>,
+    to: Option<NaiveDateTime>,
+    now: NaiveDateTime,
+    tz: &Tz,
+) -> Result<Vec<StudyTotal>, DbErr> {
+    let mut query = StudyInterval::find();
+    if let Some(to) = to {
+        query = query.filter(study_intervals::Column::StartedAt.lt(to));
+    }
+    if let Some(from) = from {
+        query = query.filter(
+            Condition::any()
+                .add(study_intervals::Column::EndedAt.is_null())
+                .add(study_intervals::Column::EndedAt.gt(from)),
+        );
+    }
+
+    let mut tally = Tally::default();
+    for interval in query.all(db).await? {
+        let start = from.map_or(interval.started_at, |from| interval.started_at.max(from));
+        let end = interval.ended_at.unwrap_or(now);
+        let end = to.map_or(end, |to| end.min(to));
+        if end <= start {
+            continue;
+        }
+
+        match grouping.id_of(interval.folder_id, interval.course_id, interval.set_id) {
+            Some(id) => tally.add_id(id, (end - start).num_seconds()),
+            None => {
+                for (day, seconds) in seconds_per_day(start, end, tz) {
+                    tally.add_day(day, seconds);
+                }
+            }
+        }
+    }
+
+    Ok(tally
+        .into_groups(db, grouping)
+        .await?
+        .into_iter()
+        .map(|(key, name, seconds)| StudyTotal { key, name, seconds })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::services::test_fixtures::{at, setup_test_db, study_context};
+    use chrono::{FixedOffset, Utc};
+
+    #[tokio::test]
+    async fn test_session_changes_open_and_close_intervals() {
+        let db = setup_test_db().await;
+        let week1 = study_context(&db, "Calculus", "Week 1").await;
+        let week2 = study_context(&db, "Calculus", "Week 2").await;
+
+        record_study_context(&db, Some(week1), at("2026-03-02 09:00"))
+            .await
+            .unwrap();
+        // Starting the running session again changes nothing
+        record_study_context(&db, Some(week1), at("2026-03-02 09:30"))
+            .await
+            .unwrap();
+        // Switching closes the first interval
+        record_study_context(&db, Some(week2), at("2026-03-02 10:00"))
+            .await
+            .unwrap();
+        record_study_context(&db, None, at("2026-03-02 10:15"))
+            .await
+            .unwrap();
+
+        let mut intervals = StudyInterval::find()
+            .order_by_asc(study_intervals::Column::StartedAt)
+            .all(&db)
+            .await
+            .unwrap();
+        assert_eq!(intervals.len(), 2);
+        let second = intervals.pop().unwrap();
+        let first = intervals.pop().unwrap();
+        assert_eq!(
+            (first.set_id, first.started_at, first.ended_at),
+            (
+                week1.set_id,
+                at("2026-03-02 09:00"),
+                Some(at("2026-03-02 10:00"))
+            )
+        );
+        assert_eq!(
+            (second.set_id, second.started_at, second.ended_at),
+            (
+                week2.set_id,
+                at("2026-03-02 10:00"),
+                Some(at("2026-03-02 10:15"))
+            )
+        );
+        assert!(get_open_intervals(&db).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_stale_intervals_close_when_last_seen() {
+        let db = setup_test_db().await;
+        let week1 = study_context(&db, "Calculus", "Week 1").await;
+
+        record_study_context(&db, Some(week1), at("2026-03-02 09:00"))
+            .await
+            .unwrap();
+        touch_open_intervals(&db, at("2026-03-02 09:41"))
+            .await
+            .unwrap();
+
+        // The app stopped without ending the session
+        assert_eq!(close_stale_intervals(&db).await.unwrap(), 1);
+        let interval = StudyInterval::find().one(&db).await.unwrap().unwrap();
+        assert_eq!(interval.ended_at, Some(at("2026-03-02 09:41")));
+    }
+
+    #[tokio::test]
+    async fn test_closing_in_the_past_never_ends_before_the_start() {
+        let db = setup_test_db().await;
+        let week1 = study_context(&db, "Calculus", "Week 1").await;
+
+        record_study_context(&db, Some(week1), at("2026-03-02 09:00"))
+            .await
+            .unwrap();
+        // Idle since before the interval was opened
+        record_study_context(&db, None, at("2026-03-02 08:50"))
+            .await
+            .unwrap();
+
+        let interval = StudyInterval::find().one(&db).await.unwrap().unwrap();
+        assert_eq!(interval.ended_at, Some(at("2026-03-02 09:00")));
+    }
+
+    #[tokio::test]
+    async fn test_study_totals() {
+        let db = setup_test_db().await;
+        let calculus = study_context(&db, "Calculus", "Week 1").await;
+        let algebra = study_context(&db, "Algebra", "Week 1").await;
+
+        // 23:00 to 01:00 across midnight, then half an hour of algebra
+        record_study_context(&db, Some(calculus), at("2026-03-02 23:00"))
+            .await
+            .unwrap();
+        record_study_context(&db, Some(algebra), at("2026-03-03 01:00"))
+            .await
+            .unwrap();
+        record_study_context(&db, None, at("2026-03-03 01:30"))
+            .await
+            .unwrap();
+        // Still running at `now`
+        record_study_context(&db, Some(calculus), at("2026-03-03 12:00"))
+            .await
+            .unwrap();
+        let now = at("2026-03-03 12:10");
+
+        let by_day = get_study_totals(&db, StudyGrouping::Day, None, None, now, &Utc)
+            .await
+            .unwrap();
+        let days: Vec<(&str, i64)> = by_day.iter().map(|t| (t.key.as_str(), t.seconds)).collect();
+        assert_eq!(
+            days,
+            vec![("2026-03-02", 3600), ("2026-03-03", 3600 + 1800 + 600)]
+        );
+
+        // The same time two hours east of UTC, where midnight falls at 22:00 UTC
+        let east = FixedOffset::east_opt(2 * 3600).unwrap();
+        let by_day = get_study_totals(&db, StudyGrouping::Day, None, None, now, &east)
+            .await
+            .unwrap();
+        let days: Vec<(&str, i64)> = by_day.iter().map(|t| (t.key.as_str(), t.seconds)).collect();
+        assert_eq!(days, vec![("2026-03-03", 2 * 3600 + 1800 + 600)]);
+
+        let by_course = get_study_totals(&db, StudyGrouping::Course, None, None, now, &Utc)
+            .await
+            .unwrap();
+        assert_eq!(
+            by_course,
+            vec![
+                StudyTotal {
+                    key: calculus.course_id.to_string(),
+                    name: Some("Calculus".to_string()),
+                    seconds: 2 * 3600 + 600,
+                },
+                StudyTotal {
+                    key: algebra.course_id.to_string(),
+                    name: Some("Algebra".to_string()),
+                    seconds: 1800,
+                },
+            ]
+        );
+
+        // Only what falls inside the range counts
+        let by_folder = get_study_totals(
+            &db,
+            StudyGrouping::Folder,
+            Some(at("2026-03-03 00:00")),
+            Some(at("2026-03-03 01:15")),
+            now,
+            &Utc,
+        )
+        .await
+        .unwrap();
+        assert_eq!(by_folder.len(), 1);
+        assert_eq!(by_folder[0].name.as_deref(), Some("Math"));
+        assert_eq!(by_folder[0].seconds, 3600 + 900);
+    }
+}
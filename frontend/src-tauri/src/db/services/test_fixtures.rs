@@ -0,0 +1,83 @@
+//! Databases and hierarchy shared by the tests of the services and the code built on them
+
+use chrono::NaiveDateTime;
+use migration::{Migrator, MigratorTrait};
+use sea_orm::{Database, DatabaseConnection};
+use uuid::Uuid;
+
+use crate::db::entities::sessions;
+use crate::db::services::{
+    create_session, find_or_create_course, find_or_create_folder, find_or_create_set,
+    get_or_create_default_user, StudyContext,
+};
+
+/// A set and the folder and course it is in
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct TestSet {
+    pub folder_id: Uuid,
+    pub course_id: Uuid,
+    pub set_id: Uuid,
+}
+
+/// A migrated in-memory database
+pub(crate) async fn setup_test_db() -> DatabaseConnection {
+    let db = Database::connect("sqlite::memory:")
+        .await
+        .expect("Failed to create test database");
+
+    Migrator::up(&db, None)
+        .await
+        .expect("Failed to run migrations");
+
+    db
+}
+
+/// `YYYY-MM-DD HH:MM`
+pub(crate) fn at(text: &str) -> NaiveDateTime {
+    NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M").unwrap()
+}
+
+/// Finds or creates Math/{course}/{set}
+pub(crate) async fn create_set(db: &DatabaseConnection, course: &str, set: &str) -> TestSet {
+    let user_id = get_or_create_default_user(db).await.unwrap();
+    let folder = find_or_create_folder(db, user_id, "Math".to_string())
+        .await
+        .unwrap();
+    let course = find_or_create_course(db, folder.id, course.to_string())
+        .await
+        .unwrap();
+    let set = find_or_create_set(db, course.id, set.to_string())
+        .await
+        .unwrap();
+    TestSet {
+        folder_id: folder.id,
+        course_id: course.id,
+        set_id: set.id,
+    }
+}
+
+/// A saved session for Math/{course}/{set}
+pub(crate) async fn study_context(
+    db: &DatabaseConnection,
+    course: &str,
+    set: &str,
+) -> StudyContext {
+    let test_set = create_set(db, course, set).await;
+    let session = sessions::Model {
+        id: Uuid::new_v4(),
+        name: set.to_string(),
+        folder_id: test_set.folder_id,
+        course_id: test_set.course_id,
+        set_id: test_set.set_id,
+        created_at: at("2026-03-01 00:00"),
+        last_used: at("2026-03-01 00:00"),
+    };
+    create_session(db, session.clone(), false).await.unwrap();
+
+    StudyContext {
+        session_id: session.id,
+        folder_id: test_set.folder_id,
+        course_id: test_set.course_id,
+        set_id: test_set.set_id,
+    }
+}
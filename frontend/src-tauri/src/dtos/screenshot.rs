@@ -1,8 +1,15 @@
+use uuid::Uuid;
+
 #[derive(Clone)]
 pub struct ScreenshotDto {
+    pub problem_id: Uuid, // chosen before saving so the image file can carry it
     pub folder_name: String,
     pub course_name: String,
     pub set_name: String,
     pub problem_name: String, // problem_name is equivalent to the screenshot name
     pub base64_data: String,
+    pub ocr_text: Option<String>, // text recognised while naming, if any
+    pub source_window_title: Option<String>, // set for window captures
+    pub source_app_name: Option<String>,
+    pub image_hash: Option<i64>, // perceptual hash, see imaging::phash
 }
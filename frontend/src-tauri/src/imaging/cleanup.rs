@@ -0,0 +1,329 @@
+use image::{imageops, DynamicImage, GrayImage, Rgba, RgbaImage};
+
+use crate::settings::CleanupSteps;
+
+/// Captures darker than this on average are treated as light-on-dark
+const DARK_MEAN_LUMA: f64 = 110.0;
+/// Largest per-channel difference from the corner colour still counted as border
+const BORDER_TOLERANCE: u8 = 24;
+/// Border left around the content after trimming, in pixels
+const TRIM_MARGIN: u32 = 8;
+/// Skew is searched for within ± this many degrees
+const MAX_SKEW_DEGREES: f64 = 5.0;
+const SKEW_STEP_DEGREES: f64 = 0.25;
+/// Longest side of the copy the skew is measured on
+const SKEW_SAMPLE_SIZE: u32 = 600;
+/// Levels below the 1st and above the 99th percentile are clipped by the stretch
+const CONTRAST_CLIP_PERCENT: f64 = 1.0;
+
+fn luma(pixel: &Rgba<u8>) -> u8 {
+    let [r, g, b, _] = pixel.0;
+    ((299 * u32::from(r) + 587 * u32::from(g) + 114 * u32::from(b)) / 1000) as u8
+}
+
+/// Runs the enabled steps, in the order they are declared in [`CleanupSteps`]
+pub(crate) fn apply(image: DynamicImage, steps: &CleanupSteps) -> DynamicImage {
+    if steps.is_empty() {
+        return image;
+    }
+
+    let mut rgba = image.to_rgba8();
+    if steps.invert_dark && is_dark(&rgba) {
+        imageops::invert(&mut rgba);
+    }
+    if steps.trim_borders {
+        rgba = trim_borders(rgba);
+    }
+    if steps.deskew {
+        if let Some(angle) = estimate_skew(&rgba) {
+            rgba = rotate(&rgba, angle);
+        }
+    }
+    if steps.boost_contrast {
+        stretch_contrast(&mut rgba);
+    }
+
+    if steps.greyscale {
+        DynamicImage::ImageLuma8(imageops::grayscale(&rgba))
+    } else {
+        DynamicImage::ImageRgba8(rgba)
+    }
+}
+
+fn is_dark(image: &RgbaImage) -> bool {
+    let pixels = u64::from(image.width()) * u64::from(image.height());
+    if pixels == 0 {
+        return false;
+    }
+    let total: u64 = image.pixels().map(|p| u64::from(luma(p))).sum();
+    (total as f64 / pixels as f64) < DARK_MEAN_LUMA
+}
+
+/// Crops to the content, taking the top-left pixel's colour as the border
+fn trim_borders(image: RgbaImage) -> RgbaImage {
+    let (width, height) = image.dimensions();
+    if width == 0 || height == 0 {
+        return image;
+    }
+
+    let border = *image.get_pixel(0, 0);
+    let is_content = |p: &Rgba<u8>| {
+        p.0[..3]
+            .iter()
+            .zip(&border.0[..3])
+            .any(|(a, b)| a.abs_diff(*b) > BORDER_TOLERANCE)
+    };
+
+    let mut bounds: Option<(u32, u32, u32, u32)> = None;
+    for (x, y, pixel) in image.enumerate_pixels() {
+        if is_content(pixel) {
+            bounds = Some(match bounds {
+                None => (x, y, x, y),
+                Some((left, top, right, bottom)) => {
+                    (left.min(x), top.min(y), right.max(x), bottom.max(y))
+                }
+            });
+        }
+    }
+    let Some((left, top, right, bottom)) = bounds else {
+        return image;
+    };
+
+    let left = left.saturating_sub(TRIM_MARGIN);
+    let top = top.saturating_sub(TRIM_MARGIN);
+    let right = (right + TRIM_MARGIN).min(width - 1);
+    let bottom = (bottom + TRIM_MARGIN).min(height - 1);
+    imageops::crop_imm(&image, left, top, right - left + 1, bottom - top + 1).to_image()
+}
+
+/// Angle in degrees of the text lines, measured clockwise from horizontal in
+/// image coordinates. `None` if the image is straight or has too little ink
+/// to tell.
+fn estimate_skew(image: &RgbaImage) -> Option<f64> {
+    let grey: GrayImage = imageops::grayscale(image);
+    let (width, height) = grey.dimensions();
+    let scale = (f64::from(SKEW_SAMPLE_SIZE) / f64::from(width.max(height).max(1))).min(1.0);
+    let grey = if scale < 1.0 {
+        imageops::resize(
+            &grey,
+            ((f64::from(width) * scale) as u32).max(1),
+            ((f64::from(height) * scale) as u32).max(1),
+            imageops::FilterType::Triangle,
+        )
+    } else {
+        grey
+    };
+
+    // Ink: pixels clearly darker than the page
+    let mean = grey.pixels().map(|p| f64::from(p.0[0])).sum::<f64>()
+        / f64::from((grey.width() * grey.height()).max(1));
+    let (cx, cy) = (
+        f64::from(grey.width()) / 2.0,
+        f64::from(grey.height()) / 2.0,
+    );
+    let ink: Vec<(f64, f64)> = grey
+        .enumerate_pixels()
+        .filter(|(_, _, p)| f64::from(p.0[0]) < mean * 0.7)
+        .map(|(x, y, _)| (f64::from(x) - cx, f64::from(y) - cy))
+        .collect();
+    if ink.len() < 50 {
+        return None;
+    }
+
+    // Text lines are sharpest, i.e. the row profile is most uneven, when
+    // projected along their own angle. Smaller angles win ties.
+    let offset = f64::from(grey.width() + grey.height());
+    let steps = (MAX_SKEW_DEGREES / SKEW_STEP_DEGREES) as i32;
+    let mut best = (0.0, f64::MIN);
+    for step in (0..=steps).flat_map(|s| [s, -s]) {
+        let angle = f64::from(step) * SKEW_STEP_DEGREES;
+        let (sin, cos) = angle.to_radians().sin_cos();
+        let mut rows = vec![0u32; (offset * 2.0) as usize + 1];
+        for (x, y) in &ink {
+            let row = (y * cos - x * sin + offset).round() as usize;
+            rows[row] += 1;
+        }
+        let score: f64 = rows.iter().map(|&n| f64::from(n) * f64::from(n)).sum();
+        if score > best.1 {
+            best = (angle, score);
+        }
+    }
+
+    (best.0 != 0.0).then_some(best.0)
+}
+
+/// Rotates by `degrees` about the centre, so that lines at that angle (see
+/// [`estimate_skew`]) come out horizontal. Keeps the size; uncovered corners
+/// take the top-left pixel's colour.
+fn rotate(image: &RgbaImage, degrees: f64) -> RgbaImage {
+    let (width, height) = image.dimensions();
+    let fill = image
+        .get_pixel_checked(0, 0)
+        .copied()
+        .unwrap_or(Rgba([255; 4]));
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    let (cx, cy) = (f64::from(width) / 2.0, f64::from(height) / 2.0);
+
+    RgbaImage::from_fn(width, height, |x, y| {
+        let (dx, dy) = (f64::from(x) + 0.5 - cx, f64::from(y) + 0.5 - cy);
+        let sx = dx * cos - dy * sin + cx - 0.5;
+        let sy = dx * sin + dy * cos + cy - 0.5;
+        sample_bilinear(image, sx, sy).unwrap_or(fill)
+    })
+}
+
+fn sample_bilinear(image: &RgbaImage, x: f64, y: f64) -> Option<Rgba<u8>> {
+    let (width, height) = image.dimensions();
+    if x < 0.0 || y < 0.0 || x > f64::from(width - 1) || y > f64::from(height - 1) {
+        return None;
+    }
+
+    let (x0, y0) = (x.floor() as u32, y.floor() as u32);
+    let (x1, y1) = ((x0 + 1).min(width - 1), (y0 + 1).min(height - 1));
+    let (fx, fy) = (x - f64::from(x0), y - f64::from(y0));
+
+    let mut out = [0u8; 4];
+    for (channel, value) in out.iter_mut().enumerate() {
+        let at = |px: u32, py: u32| f64::from(image.get_pixel(px, py).0[channel]);
+        let top = at(x0, y0) * (1.0 - fx) + at(x1, y0) * fx;
+        let bottom = at(x0, y1) * (1.0 - fx) + at(x1, y1) * fx;
+        *value = (top * (1.0 - fy) + bottom * fy).round() as u8;
+    }
+    Some(Rgba(out))
+}
+
+/// Stretches levels so the darkest and lightest percent of pixels become
+/// black and white. Images with almost no range are left alone.
+fn stretch_contrast(image: &mut RgbaImage) {
+    let mut histogram = [0u64; 256];
+    for pixel in image.pixels() {
+        histogram[usize::from(luma(pixel))] += 1;
+    }
+    let total: u64 = histogram.iter().sum();
+    let clip = (total as f64 * CONTRAST_CLIP_PERCENT / 100.0) as u64;
+
+    let percentile = |levels: &mut dyn Iterator<Item = usize>| {
+        let mut seen = 0;
+        for level in levels {
+            seen += histogram[level];
+            if seen > clip {
+                return level as f64;
+            }
+        }
+        0.0
+    };
+    let low = percentile(&mut (0..256));
+    let high = percentile(&mut (0..256).rev());
+    if high - low < 32.0 {
+        return;
+    }
+
+    for pixel in image.pixels_mut() {
+        for value in &mut pixel.0[..3] {
+            *value = ((f64::from(*value) - low) * 255.0 / (high - low))
+                .round()
+                .clamp(0.0, 255.0) as u8;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+    const BLACK: Rgba<u8> = Rgba([0, 0, 0, 255]);
+
+    /// White page with black text-like lines
+    fn page() -> RgbaImage {
+        RgbaImage::from_fn(300, 200, |x, y| {
+            if (40..260).contains(&x) && (30..170).contains(&y) && y % 20 < 4 {
+                BLACK
+            } else {
+                WHITE
+            }
+        })
+    }
+
+    #[test]
+    fn test_no_steps_is_a_no_op() {
+        let image = DynamicImage::ImageRgba8(page());
+        assert_eq!(apply(image.clone(), &CleanupSteps::default()), image);
+    }
+
+    #[test]
+    fn test_invert_dark() {
+        let steps = CleanupSteps {
+            invert_dark: true,
+            ..Default::default()
+        };
+
+        let mut dark = page();
+        imageops::invert(&mut dark);
+        assert_eq!(
+            apply(DynamicImage::ImageRgba8(dark), &steps).to_rgba8(),
+            page()
+        );
+
+        // Light captures are left alone
+        assert_eq!(
+            apply(DynamicImage::ImageRgba8(page()), &steps).to_rgba8(),
+            page()
+        );
+    }
+
+    #[test]
+    fn test_trim_borders() {
+        let trimmed = trim_borders(page());
+        // The lines span x 40..260 and y 40..164, plus the margin
+        assert_eq!(
+            trimmed.dimensions(),
+            (220 + 2 * TRIM_MARGIN, 124 + 2 * TRIM_MARGIN)
+        );
+
+        // Nothing but border
+        let blank = RgbaImage::from_pixel(10, 10, WHITE);
+        assert_eq!(trim_borders(blank.clone()), blank);
+    }
+
+    #[test]
+    fn test_deskew() {
+        assert_eq!(estimate_skew(&page()), None);
+
+        let tilted = rotate(&page(), -3.0);
+        let angle = estimate_skew(&tilted).unwrap();
+        assert!((angle - 3.0).abs() <= 0.5, "estimated {}", angle);
+
+        let straightened = rotate(&tilted, angle);
+        assert!(estimate_skew(&straightened).map_or(true, |a| a.abs() <= 0.5));
+
+        // Not enough ink to go on
+        assert_eq!(estimate_skew(&RgbaImage::from_pixel(50, 50, WHITE)), None);
+    }
+
+    #[test]
+    fn test_stretch_contrast() {
+        let mut washed_out = RgbaImage::from_fn(100, 1, |x, _| {
+            let level = 100 + (x / 2) as u8;
+            Rgba([level, level, level, 255])
+        });
+        stretch_contrast(&mut washed_out);
+        assert_eq!(washed_out.get_pixel(0, 0).0[0], 0);
+        assert_eq!(washed_out.get_pixel(99, 0).0[0], 255);
+
+        // Flat images have nothing to stretch
+        let mut flat = RgbaImage::from_pixel(10, 10, Rgba([120, 120, 120, 255]));
+        stretch_contrast(&mut flat);
+        assert_eq!(flat.get_pixel(0, 0).0[0], 120);
+    }
+
+    #[test]
+    fn test_greyscale() {
+        let steps = CleanupSteps {
+            greyscale: true,
+            ..Default::default()
+        };
+        let result = apply(DynamicImage::ImageRgba8(page()), &steps);
+        assert!(matches!(result, DynamicImage::ImageLuma8(_)));
+    }
+}
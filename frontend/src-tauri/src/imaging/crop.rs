@@ -0,0 +1,144 @@
+use image::{imageops, RgbaImage};
+use serde::{Deserialize, Serialize};
+
+/// A selection in logical (CSS) pixels, relative to the top-left corner of
+/// the captured monitor. This is what the overlay reports, since it covers
+/// the monitor in a fullscreen window.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SelectionRect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Converts a logical selection into a physical pixel rectangle
+/// `(x, y, width, height)` inside an image of `image_width` × `image_height`.
+///
+/// Negative sizes (dragging up or left) are normalised, edges are rounded
+/// outwards so the selected pixels are always included, and the result is
+/// clamped to the image. Returns `None` if nothing of the selection is left.
+pub(crate) fn to_pixel_rect(
+    selection: &SelectionRect,
+    scale_factor: f64,
+    image_width: u32,
+    image_height: u32,
+) -> Option<(u32, u32, u32, u32)> {
+    let values = [selection.x, selection.y, selection.width, selection.height];
+    if values.iter().any(|v| !v.is_finite()) || !scale_factor.is_finite() || scale_factor <= 0.0 {
+        return None;
+    }
+
+    let (left, right) = ordered(selection.x, selection.x + selection.width);
+    let (top, bottom) = ordered(selection.y, selection.y + selection.height);
+
+    let left = (left * scale_factor).floor().clamp(0.0, image_width as f64) as u32;
+    let right = (right * scale_factor).ceil().clamp(0.0, image_width as f64) as u32;
+    let top = (top * scale_factor).floor().clamp(0.0, image_height as f64) as u32;
+    let bottom = (bottom * scale_factor).ceil().clamp(0.0, image_height as f64) as u32;
+
+    if right <= left || bottom <= top {
+        return None;
+    }
+
+    Some((left, top, right - left, bottom - top))
+}
+
+fn ordered(a: f64, b: f64) -> (f64, f64) {
+    if a <= b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+/// Crops `frame` to a logical selection on a monitor with the given scale factor
+pub(crate) fn crop_selection(
+    frame: &RgbaImage,
+    selection: &SelectionRect,
+    scale_factor: f64,
+) -> Result<RgbaImage, String> {
+    let (x, y, width, height) = to_pixel_rect(selection, scale_factor, frame.width(), frame.height())
+        .ok_or_else(|| "Selection is empty or outside the captured screen".to_string())?;
+
+    Ok(imageops::crop_imm(frame, x, y, width, height).to_image())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    fn rect(x: f64, y: f64, width: f64, height: f64) -> SelectionRect {
+        SelectionRect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn test_to_pixel_rect_applies_scale_factor() {
+        assert_eq!(
+            to_pixel_rect(&rect(10.0, 20.0, 100.0, 50.0), 1.0, 1920, 1080),
+            Some((10, 20, 100, 50))
+        );
+        assert_eq!(
+            to_pixel_rect(&rect(10.0, 20.0, 100.0, 50.0), 2.0, 3840, 2160),
+            Some((20, 40, 200, 100))
+        );
+        // Fractional scale rounds outwards
+        assert_eq!(
+            to_pixel_rect(&rect(10.5, 0.0, 10.0, 10.0), 1.25, 2400, 1350),
+            Some((13, 0, 13, 13))
+        );
+    }
+
+    #[test]
+    fn test_to_pixel_rect_normalises_reverse_drag() {
+        assert_eq!(
+            to_pixel_rect(&rect(110.0, 70.0, -100.0, -50.0), 1.0, 1920, 1080),
+            Some((10, 20, 100, 50))
+        );
+    }
+
+    #[test]
+    fn test_to_pixel_rect_clamps_to_image() {
+        assert_eq!(
+            to_pixel_rect(&rect(-10.0, -10.0, 30.0, 30.0), 1.0, 100, 100),
+            Some((0, 0, 20, 20))
+        );
+        assert_eq!(
+            to_pixel_rect(&rect(90.0, 90.0, 50.0, 50.0), 1.0, 100, 100),
+            Some((90, 90, 10, 10))
+        );
+    }
+
+    #[test]
+    fn test_to_pixel_rect_rejects_empty_selections() {
+        assert_eq!(to_pixel_rect(&rect(10.0, 10.0, 0.0, 20.0), 1.0, 100, 100), None);
+        assert_eq!(to_pixel_rect(&rect(200.0, 200.0, 10.0, 10.0), 1.0, 100, 100), None);
+        assert_eq!(to_pixel_rect(&rect(0.0, 0.0, f64::NAN, 10.0), 1.0, 100, 100), None);
+        assert_eq!(to_pixel_rect(&rect(0.0, 0.0, 10.0, 10.0), 0.0, 100, 100), None);
+    }
+
+    #[test]
+    fn test_crop_selection_keeps_only_selected_pixels() {
+        // Left half red, right half blue
+        let frame = RgbaImage::from_fn(200, 100, |x, _| {
+            if x < 100 {
+                Rgba([255, 0, 0, 255])
+            } else {
+                Rgba([0, 0, 255, 255])
+            }
+        });
+
+        // Logical 50x25 at 2x covers the blue half only
+        let cropped = crop_selection(&frame, &rect(50.0, 0.0, 50.0, 25.0), 2.0).unwrap();
+        assert_eq!(cropped.dimensions(), (100, 50));
+        assert!(cropped.pixels().all(|p| *p == Rgba([0, 0, 255, 255])));
+
+        assert!(crop_selection(&frame, &rect(0.0, 0.0, 0.0, 0.0), 1.0).is_err());
+    }
+}
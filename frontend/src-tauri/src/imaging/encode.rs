@@ -0,0 +1,154 @@
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::codecs::webp::WebPEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageError};
+
+use crate::settings::{EncodingSettings, ImageFormat};
+
+/// An image ready to be written to disk
+pub(crate) struct EncodedImage {
+    pub bytes: Vec<u8>,
+    /// File extension matching `bytes`, without the dot
+    pub extension: &'static str,
+}
+
+pub(crate) fn extension_for(format: ImageFormat) -> &'static str {
+    match format {
+        ImageFormat::Png => "png",
+        ImageFormat::Webp => "webp",
+        ImageFormat::Jpeg => "jpg",
+    }
+}
+
+/// True when `options` would re-encode a PNG into an equivalent PNG,
+/// so PNG input can be written as-is
+pub(crate) fn is_passthrough(options: &EncodingSettings) -> bool {
+    options.format == ImageFormat::Png && options.max_long_edge.is_none() && !options.greyscale
+}
+
+/// Applies downscaling and greyscale, then encodes in the configured format
+pub(crate) fn encode(image: DynamicImage, options: &EncodingSettings) -> Result<EncodedImage, ImageError> {
+    let mut image = image;
+
+    if let Some(max) = options.max_long_edge.filter(|max| *max > 0) {
+        if image.width().max(image.height()) > max {
+            // `resize` keeps the aspect ratio and fits within max × max
+            image = image.resize(max, max, FilterType::Lanczos3);
+        }
+    }
+
+    if options.greyscale {
+        image = image.grayscale();
+    }
+
+    let mut bytes = Vec::new();
+    match options.format {
+        ImageFormat::Png => image.write_with_encoder(PngEncoder::new(&mut bytes))?,
+        ImageFormat::Webp => image.write_with_encoder(WebPEncoder::new_lossless(&mut bytes))?,
+        ImageFormat::Jpeg => {
+            // JPEG has no alpha channel
+            let image = if options.greyscale {
+                DynamicImage::ImageLuma8(image.to_luma8())
+            } else {
+                DynamicImage::ImageRgb8(image.to_rgb8())
+            };
+            let quality = options.quality.clamp(1, 100);
+            image.write_with_encoder(JpegEncoder::new_with_quality(&mut bytes, quality))?
+        }
+    }
+
+    Ok(EncodedImage {
+        bytes,
+        extension: extension_for(options.format),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageFormat as FileFormat, Rgba, RgbaImage};
+
+    fn sample(width: u32, height: u32) -> DynamicImage {
+        DynamicImage::ImageRgba8(RgbaImage::from_fn(width, height, |x, y| {
+            Rgba([(x % 256) as u8, (y % 256) as u8, 200, 255])
+        }))
+    }
+
+    fn options(format: ImageFormat) -> EncodingSettings {
+        EncodingSettings {
+            format,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_encode_formats() {
+        for (format, file_format, extension) in [
+            (ImageFormat::Png, FileFormat::Png, "png"),
+            (ImageFormat::Webp, FileFormat::WebP, "webp"),
+            (ImageFormat::Jpeg, FileFormat::Jpeg, "jpg"),
+        ] {
+            let encoded = encode(sample(64, 32), &options(format)).unwrap();
+            assert_eq!(encoded.extension, extension);
+            assert_eq!(image::guess_format(&encoded.bytes).unwrap(), file_format);
+
+            let decoded = image::load_from_memory(&encoded.bytes).unwrap();
+            assert_eq!((decoded.width(), decoded.height()), (64, 32));
+        }
+    }
+
+    #[test]
+    fn test_encode_downscales_long_edge() {
+        let encoded = encode(
+            sample(400, 100),
+            &EncodingSettings {
+                max_long_edge: Some(200),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let decoded = image::load_from_memory(&encoded.bytes).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (200, 50));
+
+        // Smaller images are never upscaled
+        let encoded = encode(
+            sample(100, 40),
+            &EncodingSettings {
+                max_long_edge: Some(200),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let decoded = image::load_from_memory(&encoded.bytes).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (100, 40));
+    }
+
+    #[test]
+    fn test_encode_greyscale() {
+        for format in [ImageFormat::Png, ImageFormat::Jpeg] {
+            let encoded = encode(
+                sample(16, 16),
+                &EncodingSettings {
+                    format,
+                    greyscale: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+            let decoded = image::load_from_memory(&encoded.bytes).unwrap().to_rgb8();
+            assert!(decoded.pixels().all(|p| p[0] == p[1] && p[1] == p[2]));
+        }
+    }
+
+    #[test]
+    fn test_is_passthrough() {
+        assert!(is_passthrough(&EncodingSettings::default()));
+        assert!(is_passthrough(&EncodingSettings::lossless()));
+        assert!(!is_passthrough(&options(ImageFormat::Jpeg)));
+        assert!(!is_passthrough(&EncodingSettings {
+            greyscale: true,
+            ..Default::default()
+        }));
+    }
+}
@@ -0,0 +1,10 @@
+//! Image processing applied to captured frames before they are saved.
+
+pub(crate) mod cleanup;
+pub(crate) mod crop;
+pub(crate) mod encode;
+pub(crate) mod phash;
+pub(crate) mod png_meta;
+pub(crate) mod redact;
+pub(crate) mod stitch;
+pub(crate) mod thumbnail;
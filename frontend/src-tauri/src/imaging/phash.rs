@@ -0,0 +1,83 @@
+use image::imageops::FilterType;
+use image::DynamicImage;
+
+/// Largest Hamming distance between two hashes still treated as the same capture
+pub const DUPLICATE_THRESHOLD: u32 = 10;
+
+/// 64-bit difference hash (dHash): the image is shrunk to 9×8 greyscale and
+/// each bit records whether a pixel is brighter than its right-hand neighbour.
+/// Robust to rescaling, re-encoding and small brightness changes.
+pub(crate) fn dhash(image: &DynamicImage) -> u64 {
+    let small = image
+        .resize_exact(9, 8, FilterType::Triangle)
+        .to_luma8();
+
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            let left = small.get_pixel(x, y)[0];
+            let right = small.get_pixel(x + 1, y)[0];
+            hash = (hash << 1) | u64::from(left > right);
+        }
+    }
+    hash
+}
+
+/// Number of differing bits
+pub(crate) fn hamming(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// Hashes are stored in SQLite's signed 64-bit integers
+pub(crate) fn to_db(hash: u64) -> i64 {
+    hash as i64
+}
+
+pub(crate) fn from_db(value: i64) -> u64 {
+    value as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Luma, Rgba, RgbaImage};
+
+    /// Horizontal gradient with vertical bands, drawn relative to the image size
+    fn gradient(width: u32, height: u32, flip: bool) -> DynamicImage {
+        DynamicImage::ImageRgba8(RgbaImage::from_fn(width, height, |x, y| {
+            let fx = x as f32 / width as f32;
+            let fy = y as f32 / height as f32;
+            let band = if (fx * 5.0) as u32 % 2 == 0 { 0.0 } else { 30.0 };
+            let v = (fx * 180.0 + fy * 40.0 + band) as u8;
+            let v = if flip { 255 - v } else { v };
+            Rgba([v, v, v, 255])
+        }))
+    }
+
+    #[test]
+    fn test_dhash_is_stable_under_rescale() {
+        let original = gradient(400, 300, false);
+        let smaller = original.resize_exact(200, 150, FilterType::Lanczos3);
+        assert!(hamming(dhash(&original), dhash(&smaller)) <= DUPLICATE_THRESHOLD);
+    }
+
+    #[test]
+    fn test_dhash_differs_for_different_images() {
+        let a = gradient(400, 300, false);
+        let b = gradient(400, 300, true);
+        assert!(hamming(dhash(&a), dhash(&b)) > DUPLICATE_THRESHOLD);
+    }
+
+    #[test]
+    fn test_dhash_of_flat_image_is_zero() {
+        let flat = DynamicImage::ImageLuma8(image::GrayImage::from_pixel(50, 50, Luma([128])));
+        assert_eq!(dhash(&flat), 0);
+    }
+
+    #[test]
+    fn test_db_round_trip() {
+        for hash in [0, 1, u64::MAX, 0x8000_0000_0000_0000] {
+            assert_eq!(from_db(to_db(hash)), hash);
+        }
+    }
+}
@@ -0,0 +1,119 @@
+//! Problem metadata carried in saved PNGs as `iTXt` chunks, so an image
+//! copied out of the store still says what it is. Names are as they were
+//! when the file was written; [`crate::storage::sidecar`]s follow renames.
+
+use chrono::{DateTime, Utc};
+use std::io::Cursor;
+use uuid::Uuid;
+
+use crate::storage::sidecar::ImageMetadata;
+
+const PROBLEM_ID: &str = "plutodesk:problem_id";
+const TITLE: &str = "plutodesk:title";
+const ROLE: &str = "plutodesk:role";
+const FOLDER: &str = "plutodesk:folder";
+const COURSE: &str = "plutodesk:course";
+const SET: &str = "plutodesk:set";
+const CAPTURED_AT: &str = "plutodesk:captured_at";
+
+/// Re-encodes `png` with `metadata` in front of the image data. The pixels
+/// are unchanged; palette and low bit depth images come back expanded.
+pub(crate) fn embed(png: &[u8], metadata: &ImageMetadata) -> Result<Vec<u8>, String> {
+    let mut decoder = png::Decoder::new(Cursor::new(png));
+    decoder.set_transformations(png::Transformations::EXPAND);
+    let mut reader = decoder.read_info().map_err(|e| e.to_string())?;
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let frame = reader.next_frame(&mut pixels).map_err(|e| e.to_string())?;
+    pixels.truncate(frame.buffer_size());
+
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, frame.width, frame.height);
+    encoder.set_color(frame.color_type);
+    encoder.set_depth(frame.bit_depth);
+    let fields = [
+        (PROBLEM_ID, metadata.problem_id.to_string()),
+        (TITLE, metadata.title.clone()),
+        (ROLE, metadata.role.clone()),
+        (FOLDER, metadata.folder.clone()),
+        (COURSE, metadata.course.clone()),
+        (SET, metadata.set.clone()),
+        (CAPTURED_AT, metadata.captured_at.to_rfc3339()),
+    ];
+    for (keyword, text) in fields {
+        encoder
+            .add_itxt_chunk(keyword.to_string(), text)
+            .map_err(|e| e.to_string())?;
+    }
+
+    let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+    writer
+        .write_image_data(&pixels)
+        .map_err(|e| e.to_string())?;
+    writer.finish().map_err(|e| e.to_string())?;
+    Ok(bytes)
+}
+
+/// The metadata [`embed`] wrote. `None` for other files, or PNGs without all
+/// of it.
+pub(crate) fn read(png: &[u8]) -> Option<ImageMetadata> {
+    // Metadata sits before the image data, which is all the header read covers
+    let reader = png::Decoder::new(Cursor::new(png)).read_info().ok()?;
+    let field = |keyword: &str| {
+        reader
+            .info()
+            .utf8_text
+            .iter()
+            .find(|chunk| chunk.keyword == keyword)
+            .and_then(|chunk| chunk.get_text().ok())
+    };
+
+    Some(ImageMetadata {
+        problem_id: Uuid::parse_str(&field(PROBLEM_ID)?).ok()?,
+        title: field(TITLE)?,
+        role: field(ROLE)?,
+        folder: field(FOLDER)?,
+        course: field(COURSE)?,
+        set: field(SET)?,
+        captured_at: DateTime::parse_from_rfc3339(&field(CAPTURED_AT)?)
+            .ok()?
+            .with_timezone(&Utc),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageFormat, Rgba, RgbaImage};
+
+    fn metadata() -> ImageMetadata {
+        ImageMetadata {
+            problem_id: Uuid::new_v4(),
+            title: "Intégrale — Q1".to_string(),
+            role: "question".to_string(),
+            folder: "Mathématiques".to_string(),
+            course: "Analyse".to_string(),
+            set: "Semaine 1".to_string(),
+            captured_at: DateTime::parse_from_rfc3339("2026-03-01T09:30:00Z")
+                .unwrap()
+                .with_timezone(&Utc),
+        }
+    }
+
+    #[test]
+    fn test_embed_and_read() {
+        let image = RgbaImage::from_fn(3, 2, |x, y| Rgba([x as u8 * 80, y as u8 * 90, 30, 200]));
+        let mut plain = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut plain), ImageFormat::Png)
+            .unwrap();
+        assert_eq!(read(&plain), None);
+
+        let metadata = metadata();
+        let tagged = embed(&plain, &metadata).unwrap();
+        assert_eq!(read(&tagged), Some(metadata));
+        assert_eq!(image::load_from_memory(&tagged).unwrap().to_rgba8(), image);
+
+        assert_eq!(read(b"not a png"), None);
+        assert!(embed(b"not a png", &self::metadata()).is_err());
+    }
+}
@@ -0,0 +1,146 @@
+//! Hiding parts of a capture before it is saved. Redactions are burned into
+//! the pixels, so nothing under them reaches OCR, hashing or the disk.
+
+use image::{imageops, Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+
+use super::crop::{to_pixel_rect, SelectionRect};
+
+/// Side of the blocks a blurred region is averaged over, in pixels. Coarse
+/// enough that text under it can't be read back.
+const BLUR_BLOCK: u32 = 16;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RedactionStyle {
+    #[default]
+    Black,
+    Blur,
+}
+
+/// A region to hide, in the same logical pixels as [`SelectionRect`]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Redaction {
+    pub rect: SelectionRect,
+    #[serde(default)]
+    pub style: RedactionStyle,
+}
+
+/// Redacts each region of `image` in place. Regions outside the image are
+/// ignored.
+pub(crate) fn apply(image: &mut RgbaImage, redactions: &[Redaction], scale_factor: f64) {
+    for redaction in redactions {
+        let Some((x, y, width, height)) =
+            to_pixel_rect(&redaction.rect, scale_factor, image.width(), image.height())
+        else {
+            continue;
+        };
+
+        let covered = match redaction.style {
+            RedactionStyle::Black => RgbaImage::from_pixel(width, height, Rgba([0, 0, 0, 255])),
+            RedactionStyle::Blur => {
+                blur(&imageops::crop_imm(image, x, y, width, height).to_image())
+            }
+        };
+        imageops::replace(image, &covered, i64::from(x), i64::from(y));
+    }
+}
+
+/// Averages the region down to one pixel per [`BLUR_BLOCK`] and scales it back
+/// up smoothly, so only block averages survive
+fn blur(region: &RgbaImage) -> RgbaImage {
+    let (width, height) = region.dimensions();
+    let small = imageops::resize(
+        region,
+        width.div_ceil(BLUR_BLOCK),
+        height.div_ceil(BLUR_BLOCK),
+        imageops::FilterType::Triangle,
+    );
+    imageops::resize(&small, width, height, imageops::FilterType::Triangle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+    const BLACK: Rgba<u8> = Rgba([0, 0, 0, 255]);
+
+    fn redaction(x: f64, y: f64, width: f64, height: f64, style: RedactionStyle) -> Redaction {
+        Redaction {
+            rect: SelectionRect {
+                x,
+                y,
+                width,
+                height,
+            },
+            style,
+        }
+    }
+
+    /// One-pixel black and white stripes
+    fn stripes() -> RgbaImage {
+        RgbaImage::from_fn(64, 64, |x, _| if x % 2 == 0 { BLACK } else { WHITE })
+    }
+
+    #[test]
+    fn test_black_covers_only_the_region() {
+        let mut image = RgbaImage::from_pixel(100, 100, WHITE);
+        apply(
+            &mut image,
+            &[redaction(10.0, 10.0, 20.0, 5.0, RedactionStyle::Black)],
+            2.0,
+        );
+
+        for (x, y, pixel) in image.enumerate_pixels() {
+            let inside = (20..60).contains(&x) && (20..30).contains(&y);
+            assert_eq!(*pixel, if inside { BLACK } else { WHITE }, "at {x},{y}");
+        }
+    }
+
+    #[test]
+    fn test_blur_removes_detail() {
+        let mut image = stripes();
+        apply(
+            &mut image,
+            &[redaction(0.0, 0.0, 32.0, 64.0, RedactionStyle::Blur)],
+            1.0,
+        );
+
+        // The stripes average out to grey inside the region
+        for x in 0..32 {
+            let level = image.get_pixel(x, 32).0[0];
+            assert!((96..=160).contains(&level), "level {} at x {}", level, x);
+        }
+        // and are untouched outside it
+        for x in 32..64 {
+            assert_eq!(image.get_pixel(x, 32), stripes().get_pixel(x, 32));
+        }
+    }
+
+    #[test]
+    fn test_regions_outside_the_image_are_ignored() {
+        let mut image = stripes();
+        apply(
+            &mut image,
+            &[
+                redaction(100.0, 100.0, 10.0, 10.0, RedactionStyle::Black),
+                redaction(0.0, 0.0, 0.0, 10.0, RedactionStyle::Blur),
+            ],
+            1.0,
+        );
+        assert_eq!(image, stripes());
+    }
+
+    #[test]
+    fn test_style_defaults_to_black() {
+        let redaction: Redaction =
+            serde_json::from_str(r#"{"rect":{"x":1,"y":2,"width":3,"height":4}}"#).unwrap();
+        assert_eq!(redaction.style, RedactionStyle::Black);
+
+        let redaction: Redaction =
+            serde_json::from_str(r#"{"rect":{"x":1,"y":2,"width":3,"height":4},"style":"blur"}"#)
+                .unwrap();
+        assert_eq!(redaction.style, RedactionStyle::Blur);
+    }
+}
@@ -0,0 +1,119 @@
+use image::{imageops, Rgba, RgbaImage};
+
+/// One captured monitor and where it sits on the virtual desktop, in logical pixels
+pub(crate) struct ScreenTile {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub image: RgbaImage,
+}
+
+/// Several monitors combined into one image
+pub(crate) struct Stitched {
+    pub image: RgbaImage,
+    /// Image pixels per logical pixel across the whole canvas
+    pub scale_factor: f64,
+}
+
+/// Places every tile at its offset from the top-left of the union of all tiles.
+///
+/// The canvas uses the highest pixel density among the tiles, taken from each
+/// capture's size relative to its logical size, so the sharpest monitor is not
+/// downscaled; lower-density captures are resized up to match. Gaps between
+/// monitors are filled with opaque black. Returns `None` for no tiles.
+pub(crate) fn stitch(tiles: &[ScreenTile]) -> Option<Stitched> {
+    let min_x = tiles.iter().map(|t| t.x).min()?;
+    let min_y = tiles.iter().map(|t| t.y).min()?;
+    let max_x = tiles.iter().map(|t| t.x + t.width as i32).max()?;
+    let max_y = tiles.iter().map(|t| t.y + t.height as i32).max()?;
+
+    let scale_factor = tiles
+        .iter()
+        .filter(|t| t.width > 0)
+        .map(|t| t.image.width() as f64 / t.width as f64)
+        .fold(1.0_f64, f64::max);
+    let scaled = |v: f64| (v * scale_factor).round();
+
+    let mut canvas = RgbaImage::from_pixel(
+        scaled((max_x - min_x) as f64) as u32,
+        scaled((max_y - min_y) as f64) as u32,
+        Rgba([0, 0, 0, 255]),
+    );
+
+    for tile in tiles {
+        let target_width = scaled(tile.width as f64) as u32;
+        let target_height = scaled(tile.height as f64) as u32;
+        let x = scaled((tile.x - min_x) as f64) as i64;
+        let y = scaled((tile.y - min_y) as f64) as i64;
+
+        if tile.image.dimensions() == (target_width, target_height) {
+            imageops::replace(&mut canvas, &tile.image, x, y);
+        } else {
+            let resized = imageops::resize(
+                &tile.image,
+                target_width,
+                target_height,
+                imageops::FilterType::Triangle,
+            );
+            imageops::replace(&mut canvas, &resized, x, y);
+        }
+    }
+
+    Some(Stitched {
+        image: canvas,
+        scale_factor,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+    const BLUE: Rgba<u8> = Rgba([0, 0, 255, 255]);
+
+    fn tile(x: i32, y: i32, width: u32, height: u32, scale: u32, color: Rgba<u8>) -> ScreenTile {
+        ScreenTile {
+            x,
+            y,
+            width,
+            height,
+            image: RgbaImage::from_pixel(width * scale, height * scale, color),
+        }
+    }
+
+    #[test]
+    fn test_stitch_side_by_side() {
+        let stitched = stitch(&[tile(0, 0, 100, 50, 1, RED), tile(100, 0, 100, 50, 1, BLUE)]).unwrap();
+        assert_eq!(stitched.scale_factor, 1.0);
+        assert_eq!(stitched.image.dimensions(), (200, 50));
+        assert_eq!(*stitched.image.get_pixel(99, 10), RED);
+        assert_eq!(*stitched.image.get_pixel(100, 10), BLUE);
+    }
+
+    #[test]
+    fn test_stitch_negative_offsets_and_gaps() {
+        // Second monitor to the left and lower; the area above it stays black
+        let stitched = stitch(&[tile(0, 0, 100, 100, 1, RED), tile(-50, 50, 50, 50, 1, BLUE)]).unwrap();
+        assert_eq!(stitched.image.dimensions(), (150, 100));
+        assert_eq!(*stitched.image.get_pixel(10, 10), Rgba([0, 0, 0, 255]));
+        assert_eq!(*stitched.image.get_pixel(10, 60), BLUE);
+        assert_eq!(*stitched.image.get_pixel(60, 10), RED);
+    }
+
+    #[test]
+    fn test_stitch_mixed_scale_uses_highest_density() {
+        let stitched = stitch(&[tile(0, 0, 100, 50, 2, RED), tile(100, 0, 100, 50, 1, BLUE)]).unwrap();
+        assert_eq!(stitched.scale_factor, 2.0);
+        assert_eq!(stitched.image.dimensions(), (400, 100));
+        assert_eq!(*stitched.image.get_pixel(199, 99), RED);
+        assert_eq!(*stitched.image.get_pixel(200, 0), BLUE);
+        assert_eq!(*stitched.image.get_pixel(399, 99), BLUE);
+    }
+
+    #[test]
+    fn test_stitch_no_tiles() {
+        assert!(stitch(&[]).is_none());
+    }
+}
@@ -0,0 +1,141 @@
+use image::codecs::jpeg::JpegEncoder;
+use image::{DynamicImage, ImageError};
+use std::fs;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// Longest side of each generated thumbnail, in pixels
+pub const THUMBNAIL_SIZES: [u32; 3] = [128, 256, 512];
+
+const THUMBNAIL_QUALITY: u8 = 80;
+
+/// `<app data>/cache/thumbnails`
+pub(crate) fn cache_dir(app_data_dir: &Path) -> PathBuf {
+    app_data_dir.join("cache").join("thumbnails")
+}
+
+/// `<cache>/<size>/<problem_id>.jpg`
+pub(crate) fn thumbnail_path(cache_dir: &Path, problem_id: Uuid, size: u32) -> PathBuf {
+    cache_dir
+        .join(size.to_string())
+        .join(format!("{}.jpg", problem_id))
+}
+
+/// The smallest fixed size at least as large as `requested`, or the largest one
+pub(crate) fn snap_size(requested: u32) -> u32 {
+    THUMBNAIL_SIZES
+        .iter()
+        .copied()
+        .find(|size| *size >= requested)
+        .unwrap_or(THUMBNAIL_SIZES[THUMBNAIL_SIZES.len() - 1])
+}
+
+/// True if any of the fixed sizes is missing for a problem
+pub(crate) fn is_missing(cache_dir: &Path, problem_id: Uuid) -> bool {
+    THUMBNAIL_SIZES
+        .iter()
+        .any(|size| !thumbnail_path(cache_dir, problem_id, *size).is_file())
+}
+
+/// Writes every fixed-size thumbnail of `source` for a problem, replacing old ones.
+/// Images smaller than a size are stored at their own size rather than upscaled.
+pub(crate) fn generate(source: &Path, cache_dir: &Path, problem_id: Uuid) -> Result<(), ImageError> {
+    let image = image::open(source)?;
+
+    for size in THUMBNAIL_SIZES {
+        let thumbnail = if image.width().max(image.height()) > size {
+            image.thumbnail(size, size)
+        } else {
+            image.clone()
+        };
+        // JPEG has no alpha channel
+        let thumbnail = DynamicImage::ImageRgb8(thumbnail.to_rgb8());
+
+        let path = thumbnail_path(cache_dir, problem_id, size);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // Write next to the target and rename, so readers never see a partial file
+        let tmp_path = path.with_extension("jpg.tmp");
+        let mut bytes = Vec::new();
+        thumbnail.write_with_encoder(JpegEncoder::new_with_quality(&mut bytes, THUMBNAIL_QUALITY))?;
+        fs::write(&tmp_path, &bytes)?;
+        fs::rename(&tmp_path, &path)?;
+    }
+
+    Ok(())
+}
+
+/// Removes all thumbnails of a problem
+pub(crate) fn remove(cache_dir: &Path, problem_id: Uuid) {
+    for size in THUMBNAIL_SIZES {
+        let _ = fs::remove_file(thumbnail_path(cache_dir, problem_id, size));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgba, RgbaImage};
+    use tempfile::tempdir;
+
+    fn write_source(dir: &Path, width: u32, height: u32) -> PathBuf {
+        let path = dir.join("source.png");
+        RgbaImage::from_pixel(width, height, Rgba([10, 20, 30, 255]))
+            .save(&path)
+            .unwrap();
+        path
+    }
+
+    #[test]
+    fn test_snap_size() {
+        assert_eq!(snap_size(1), 128);
+        assert_eq!(snap_size(128), 128);
+        assert_eq!(snap_size(200), 256);
+        assert_eq!(snap_size(4000), 512);
+    }
+
+    #[test]
+    fn test_generate_all_sizes() {
+        let dir = tempdir().unwrap();
+        let source = write_source(dir.path(), 1000, 500);
+        let cache = cache_dir(dir.path());
+        let problem_id = Uuid::new_v4();
+
+        assert!(is_missing(&cache, problem_id));
+        generate(&source, &cache, problem_id).unwrap();
+        assert!(!is_missing(&cache, problem_id));
+
+        for size in THUMBNAIL_SIZES {
+            let thumbnail = image::open(thumbnail_path(&cache, problem_id, size)).unwrap();
+            assert_eq!(thumbnail.width(), size);
+            assert_eq!(thumbnail.height(), size / 2);
+        }
+
+        remove(&cache, problem_id);
+        assert!(is_missing(&cache, problem_id));
+    }
+
+    #[test]
+    fn test_generate_does_not_upscale() {
+        let dir = tempdir().unwrap();
+        let source = write_source(dir.path(), 200, 100);
+        let cache = cache_dir(dir.path());
+        let problem_id = Uuid::new_v4();
+
+        generate(&source, &cache, problem_id).unwrap();
+
+        let small = image::open(thumbnail_path(&cache, problem_id, 128)).unwrap();
+        assert_eq!((small.width(), small.height()), (128, 64));
+        let large = image::open(thumbnail_path(&cache, problem_id, 512)).unwrap();
+        assert_eq!((large.width(), large.height()), (200, 100));
+    }
+
+    #[test]
+    fn test_generate_missing_source_fails() {
+        let dir = tempdir().unwrap();
+        let cache = cache_dir(dir.path());
+        assert!(generate(&dir.path().join("nope.png"), &cache, Uuid::new_v4()).is_err());
+    }
+}
@@ -1,9 +1,14 @@
 mod commands;
 mod db;
 mod dtos;
+mod imaging;
+mod notify;
 mod problem_naming;
+mod protocol;
 mod screenshot;
 mod session;
+mod settings;
+mod storage;
 mod tray;
 
 #[cfg(test)]
@@ -12,9 +17,10 @@ mod screenshot_session_test;
 use commands::*;
 use db::init_sqlite;
 use screenshot::{
-    close_screenshot_overlay, get_screenshot_data, receive_screenshot_data, take_screenshot,
-    check_session_and_notify,
+    capture_window, close_screenshot_overlay, get_capture_layout, list_monitors, list_windows,
+    receive_screenshot_data, save_screenshot_selection, take_screenshot, check_session_and_notify,
 };
+use screenshot::import::{capture_clipboard, import_image_directory, import_image_files};
 use tauri::Manager;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -22,10 +28,30 @@ pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_http::init())
         .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .register_asynchronous_uri_scheme_protocol(protocol::CAPTURE_SCHEME, |ctx, request, responder| {
+            let app = ctx.app_handle().clone();
+            tauri::async_runtime::spawn(async move {
+                responder.respond(protocol::handle_capture(app, request).await);
+            });
+        })
+        .register_asynchronous_uri_scheme_protocol(protocol::IMAGE_SCHEME, |ctx, request, responder| {
+            let app = ctx.app_handle().clone();
+            tauri::async_runtime::spawn(async move {
+                responder.respond(protocol::handle_image(app, request).await);
+            });
+        })
         .invoke_handler(tauri::generate_handler![
             close_screenshot_overlay,
-            get_screenshot_data,
+            get_capture_layout,
+            list_monitors,
+            list_windows,
+            capture_window,
             receive_screenshot_data,
+            save_screenshot_selection,
+            capture_clipboard,
+            import_image_files,
+            import_image_directory,
             // Folder commands
             create_folder,
             get_folder,
@@ -51,12 +77,25 @@ pub fn run() {
             update_problem,
             update_problem_stats,
             delete_problem,
+            search_problems,
+            get_problem_thumbnail,
+            list_duplicate_clusters,
+            find_problem_duplicates,
             // Problem attempt commands
             create_problem_attempt,
             get_problem_attempt,
             get_attempts_by_problem,
             update_problem_attempt,
             delete_problem_attempt,
+            // Problem image commands
+            get_problem_images,
+            add_problem_image,
+            remove_problem_image,
+            reorder_problem_images,
+            apply_image_cleanup,
+            revert_image_cleanup,
+            collect_image_garbage,
+            recover_from_images,
             // Session commands
             get_all_sessions,
             get_active_session,
@@ -64,6 +103,20 @@ pub fn run() {
             create_and_start_session,
             end_session,
             delete_session,
+            // Settings commands
+            get_settings,
+            update_settings,
+            set_naming_strategy_for_set,
+            set_cleanup_steps_for_course,
+            // Study log commands
+            get_study_time,
+            // Focus timer commands
+            get_focus_timer,
+            start_focus_timer,
+            pause_focus_timer,
+            skip_focus_phase,
+            stop_focus_timer,
+            get_pomodoro_counts,
         ])
         .setup(|app| {
             // Enable logging in both debug and release builds
@@ -78,23 +131,21 @@ pub fn run() {
             use screenshot::ScreenshotData;
             use std::sync::{Arc, Mutex};
             let app_handle = app.handle().clone();
-            app_handle.manage::<ScreenshotData>(Arc::new(Mutex::new(None::<String>)));
+            app_handle.manage::<ScreenshotData>(Arc::new(Mutex::new(None)));
 
-            // Initialize session manager
+            // Initialize session manager; it is filled from the database once that is open
             use session::{SessionManager, SessionManagerState};
-            let sessions_path = app_handle
-                .path()
-                .app_data_dir()
-                .map_err(|e| format!("Failed to get app data directory: {}", e))?
-                .join("sessions.json");
-            
-            let session_manager = SessionManager::load_from_file(&sessions_path)
-                .unwrap_or_else(|e| {
-                    log::warn!("Failed to load sessions from file: {}, creating new SessionManager", e);
-                    SessionManager::new()
-                });
-            
-            app_handle.manage::<SessionManagerState>(Arc::new(Mutex::new(session_manager)));
+            app_handle.manage::<SessionManagerState>(Arc::new(Mutex::new(SessionManager::new())));
+            app_handle.manage::<session::FocusTimerState>(Arc::new(Mutex::new(session::FocusTimer::new())));
+
+            // Initialize settings
+            use settings::{AppSettings, SettingsState};
+            let settings_path = commands::settings::get_settings_file_path(&app_handle)?;
+            let app_settings = AppSettings::load_from_file(&settings_path).unwrap_or_else(|e| {
+                log::warn!("Failed to load settings from file: {}, using defaults", e);
+                AppSettings::default()
+            });
+            app_handle.manage::<SettingsState>(Arc::new(Mutex::new(app_settings)));
 
             // Initialize system tray
             #[cfg(desktop)]
@@ -114,6 +165,52 @@ pub fn run() {
                 tauri::async_runtime::spawn(async move {
                     if let Err(e) = init_sqlite(&app_handle).await {
                         log::error!("Failed to initialize database: {}", e);
+                        return;
+                    }
+
+                    // Sessions are kept in the database, so they can only be loaded now
+                    match commands::sessions::reload_sessions(&app_handle).await {
+                        Ok(()) => {
+                            use tauri::Emitter;
+                            app_handle.emit("session-state-changed", ()).ok();
+                        }
+                        Err(e) => log::error!("{}", e),
+                    }
+
+                    match commands::study::start_study_log(&app_handle).await {
+                        Ok(()) => {
+                            session::start_idle_monitor(app_handle.clone());
+                            session::start_focus_timer_loop(app_handle.clone());
+                        }
+                        Err(e) => log::error!("{}", e),
+                    }
+
+                    // Thumbnails for problems saved before they existed, or whose cache was cleared
+                    match commands::thumbnails::backfill_thumbnails(&app_handle).await {
+                        Ok(0) => {}
+                        Ok(count) => log::info!("Generated thumbnails for {} problems", count),
+                        Err(e) => log::warn!("Thumbnail backfill failed: {}", e),
+                    }
+
+                    // Perceptual hashes for problems saved before duplicate detection
+                    match commands::duplicates::backfill_image_hashes(&app_handle).await {
+                        Ok(0) => {}
+                        Ok(count) => log::info!("Hashed images of {} problems", count),
+                        Err(e) => log::warn!("Image hash backfill failed: {}", e),
+                    }
+
+                    // Image files a delete couldn't remove last time
+                    match commands::storage::retry_pending_deletions(&app_handle).await {
+                        Ok(0) => {}
+                        Ok(count) => log::warn!("{} image files still could not be deleted", count),
+                        Err(e) => log::warn!("Retrying image deletions failed: {}", e),
+                    }
+
+                    // Sidecars missing from before they existed, or left stale by a failed write
+                    match commands::storage::sync_sidecars(&app_handle).await {
+                        Ok(0) => {}
+                        Ok(count) => log::info!("Updated {} image sidecars", count),
+                        Err(e) => log::warn!("Image sidecar sync failed: {}", e),
                     }
                 });
 
@@ -121,14 +218,25 @@ pub fn run() {
                     Shortcut::new(Some(Modifiers::CONTROL | Modifiers::SHIFT), Code::KeyS);
                 let screenshot_shortcut_cmd =
                     Shortcut::new(Some(Modifiers::META | Modifiers::SHIFT), Code::KeyS);
+                // Always captures every monitor, whatever the configured mode
+                let all_screens_shortcut = screenshot::all_monitors_shortcut();
 
                 app.handle().plugin(
                     tauri_plugin_global_shortcut::Builder::new()
                         .with_handler(move |_app, shortcut, event| {
                             log::info!("{:?}", shortcut);
-                            if shortcut == &screenshot_shortcut_ctrl
+                            // Outer None: not a screenshot shortcut. Inner None: use the mode from settings
+                            let mode = if shortcut == &screenshot_shortcut_ctrl
                                 || shortcut == &screenshot_shortcut_cmd
                             {
+                                Some(None)
+                            } else if shortcut == &all_screens_shortcut {
+                                Some(Some(settings::CaptureMode::AllMonitors))
+                            } else {
+                                None
+                            };
+
+                            if let Some(mode) = mode {
                                 let app_handle = _app.clone();
                                 match event.state() {
                                     ShortcutState::Pressed => {
@@ -137,7 +245,7 @@ pub fn run() {
                                             // Check for active session first
                                             if check_session_and_notify(&app_handle).await {
                                                 // Session is active, proceed with screenshot
-                                                take_screenshot(app_handle).await.ok();
+                                                take_screenshot(app_handle, mode).await.ok();
                                             }
                                             // If no session, notification was shown and user can click it
                                         });
@@ -154,6 +262,17 @@ pub fn run() {
                 )?;
 
                 app.global_shortcut().register(screenshot_shortcut_ctrl)?;
+
+                // Only claimed when asked for; failing to get it shouldn't stop the app
+                let all_monitors = app
+                    .state::<SettingsState>()
+                    .lock()
+                    .unwrap()
+                    .capture
+                    .all_monitors_shortcut;
+                if let Err(e) = screenshot::set_all_monitors_shortcut(app.handle(), all_monitors) {
+                    log::warn!("{}", e);
+                }
             }
 
             log::info!("Tauri application is running");
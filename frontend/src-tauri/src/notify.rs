@@ -0,0 +1,13 @@
+//! Desktop notifications, shown without taking focus from whatever the user
+//! is working in
+
+use tauri::{AppHandle, Runtime};
+use tauri_plugin_notification::NotificationExt;
+
+/// Shows a notification. Failing to show one is logged, not returned, since
+/// nothing the caller was doing depends on it.
+pub(crate) fn notify<R: Runtime>(app: &AppHandle<R>, title: &str, body: impl Into<String>) {
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        log::error!("Failed to show notification: {}", e);
+    }
+}
@@ -0,0 +1,208 @@
+//! LLM phase of the naming pipeline.
+//!
+//! Sends OCR text to a local Ollama-compatible `/api/generate` endpoint and
+//! asks for a short title. The raw reply is returned; the caller is responsible
+//! for turning it into a filesystem-safe name.
+
+use std::fmt;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri_plugin_http::reqwest;
+
+use crate::settings::LlmSettings;
+
+/// OCR text beyond this many characters is dropped from the prompt.
+const MAX_PROMPT_TEXT_CHARS: usize = 2000;
+
+#[derive(Debug)]
+pub(crate) enum LlmError {
+    /// Connection failure, timeout or non-success HTTP status.
+    Http(String),
+    /// The response body was not the expected JSON shape.
+    InvalidResponse(String),
+    /// The model replied with nothing usable.
+    EmptyResponse,
+}
+
+impl fmt::Display for LlmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LlmError::Http(e) => write!(f, "LLM request failed: {}", e),
+            LlmError::InvalidResponse(e) => write!(f, "invalid LLM response: {}", e),
+            LlmError::EmptyResponse => write!(f, "LLM returned an empty response"),
+        }
+    }
+}
+
+impl std::error::Error for LlmError {}
+
+#[derive(Debug, Serialize)]
+struct GenerateRequest<'a> {
+    model: &'a str,
+    prompt: String,
+    stream: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct GenerateResponse {
+    response: String,
+}
+
+fn build_prompt(text: &str) -> String {
+    let text: String = text.chars().take(MAX_PROMPT_TEXT_CHARS).collect();
+    format!(
+        "Summarize the following homework or exam problem as a title of at most \
+         five words, in kebab-case (lowercase words separated by hyphens). \
+         Reply with the title only.\n\nProblem:\n{}",
+        text
+    )
+}
+
+/// Asks the configured model for a title for `text`.
+/// Returns the first non-empty line of the reply, untrimmed of punctuation.
+pub(crate) async fn summarize_title(config: &LlmSettings, text: &str) -> Result<String, LlmError> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_millis(config.timeout_ms))
+        .build()
+        .map_err(|e| LlmError::Http(e.to_string()))?;
+
+    let url = format!("{}/api/generate", config.endpoint.trim_end_matches('/'));
+    let body = serde_json::to_vec(&GenerateRequest {
+        model: &config.model,
+        prompt: build_prompt(text),
+        stream: false,
+    })
+    .map_err(|e| LlmError::InvalidResponse(e.to_string()))?;
+
+    let response = client
+        .post(&url)
+        .header("Content-Type", "application/json")
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| LlmError::Http(e.to_string()))?;
+
+    if !response.status().is_success() {
+        return Err(LlmError::Http(format!("status {}", response.status())));
+    }
+
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|e| LlmError::Http(e.to_string()))?;
+    let parsed: GenerateResponse =
+        serde_json::from_slice(&bytes).map_err(|e| LlmError::InvalidResponse(e.to_string()))?;
+
+    parsed
+        .response
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(str::to_string)
+        .ok_or(LlmError::EmptyResponse)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Starts a one-shot HTTP server that answers every request with `status`
+    /// and `body` after `delay`. Returns the base URL.
+    pub(crate) async fn spawn_mock_server(status: u16, body: &str, delay: Duration) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let body = body.to_string();
+
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let body = body.clone();
+                tokio::spawn(async move {
+                    let mut buf = vec![0u8; 16 * 1024];
+                    let _ = socket.read(&mut buf).await;
+                    tokio::time::sleep(delay).await;
+                    let response = format!(
+                        "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        status,
+                        body.len(),
+                        body
+                    );
+                    let _ = socket.write_all(response.as_bytes()).await;
+                    let _ = socket.shutdown().await;
+                });
+            }
+        });
+
+        format!("http://{}", addr)
+    }
+
+    pub(crate) fn config_for(endpoint: String, timeout_ms: u64) -> LlmSettings {
+        LlmSettings {
+            enabled: true,
+            endpoint,
+            model: "test-model".to_string(),
+            timeout_ms,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_summarize_title_success() {
+        let endpoint = spawn_mock_server(
+            200,
+            r#"{"model":"test-model","response":"\n  chain-rule-derivative\nextra","done":true}"#,
+            Duration::ZERO,
+        )
+        .await;
+
+        let title = summarize_title(&config_for(endpoint, 2000), "d/dx sin(x^2)")
+            .await
+            .expect("Summarize should succeed");
+        assert_eq!(title, "chain-rule-derivative");
+    }
+
+    #[tokio::test]
+    async fn test_summarize_title_http_error() {
+        let endpoint = spawn_mock_server(500, r#"{"error":"boom"}"#, Duration::ZERO).await;
+
+        let result = summarize_title(&config_for(endpoint, 2000), "text").await;
+        assert!(matches!(result, Err(LlmError::Http(_))));
+    }
+
+    #[tokio::test]
+    async fn test_summarize_title_invalid_json() {
+        let endpoint = spawn_mock_server(200, "not json", Duration::ZERO).await;
+
+        let result = summarize_title(&config_for(endpoint, 2000), "text").await;
+        assert!(matches!(result, Err(LlmError::InvalidResponse(_))));
+    }
+
+    #[tokio::test]
+    async fn test_summarize_title_empty_response() {
+        let endpoint = spawn_mock_server(200, r#"{"response":"  \n "}"#, Duration::ZERO).await;
+
+        let result = summarize_title(&config_for(endpoint, 2000), "text").await;
+        assert!(matches!(result, Err(LlmError::EmptyResponse)));
+    }
+
+    #[tokio::test]
+    async fn test_summarize_title_times_out() {
+        let endpoint = spawn_mock_server(
+            200,
+            r#"{"response":"too-late"}"#,
+            Duration::from_millis(500),
+        )
+        .await;
+
+        let result = summarize_title(&config_for(endpoint, 100), "text").await;
+        assert!(matches!(result, Err(LlmError::Http(_))));
+    }
+
+    #[test]
+    fn test_build_prompt_truncates_text() {
+        let long_text = "x".repeat(MAX_PROMPT_TEXT_CHARS + 500);
+        let prompt = build_prompt(&long_text);
+        assert!(prompt.matches('x').count() <= MAX_PROMPT_TEXT_CHARS + 10);
+    }
+}
@@ -0,0 +1,213 @@
+//! Problem naming pipeline for screenshots.
+//!
+//! Entry point: [`namer::namer_for`] — picks a [`namer::ProblemNamer`] for the
+//! configured strategy (AI, timestamp, per-set sequence or template).
+//! The AI strategy is [`suggest_problem_name`] — async, returns a filesystem-safe
+//! name plus any text recognised in the image.
+//! Steps: decode image → OCR (ocrs) → LLM (Ollama-compatible endpoint) with
+//! kebab-case summarization, max 5 words. With the LLM disabled, the name is
+//! built from the first words of the OCR text. On any failure or missing model,
+//! fall back to a timestamp-based name so the app never blocks or crashes.
+
+pub(crate) mod llm;
+pub(crate) mod namer;
+pub(crate) mod ocr;
+
+use crate::settings::LlmSettings;
+use chrono::Utc;
+use sanitize_filename::sanitize;
+use std::path::PathBuf;
+
+/// Maximum number of words taken from OCR text for a name.
+const MAX_NAME_WORDS: usize = 5;
+
+/// Result of the naming pipeline.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ProblemNameSuggestion {
+    pub name: String,
+    /// Text recognised by OCR, if the OCR phase succeeded and found any.
+    pub ocr_text: Option<String>,
+}
+
+/// Fallback name when OCR/LLM are unavailable or fail. Filesystem-safe, no I/O.
+/// Format: `problem-YYYYMMDD-HHMMSS` (no colons for portability).
+pub(crate) fn problem_name_timestamp() -> String {
+    Utc::now().format("problem-%Y%m%d-%H%M%S").to_string()
+}
+
+/// Builds a kebab-case name from the first few words of `text`, sanitised for
+/// use as a file name. Returns `None` when the text has no usable words.
+fn name_from_text(text: &str) -> Option<String> {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .take(MAX_NAME_WORDS)
+        .map(|w| w.to_lowercase())
+        .collect();
+
+    let name = sanitize(words.join("-"));
+    if name.is_empty() {
+        None
+    } else {
+        Some(name)
+    }
+}
+
+/// Turns OCR text into a name: via the LLM when enabled, otherwise from the
+/// leading words. `None` means the caller should use the timestamp fallback.
+async fn name_from_ocr_text(text: &str, llm: &LlmSettings) -> Option<String> {
+    if !llm.enabled {
+        return name_from_text(text);
+    }
+
+    match llm::summarize_title(llm, text).await {
+        Ok(title) => name_from_text(&title),
+        Err(e) => {
+            log::warn!("LLM naming failed, using timestamp name: {}", e);
+            None
+        }
+    }
+}
+
+/// Suggests a problem name from screenshot image data.
+/// Runs OCR with the models in `ocr_models_dir`, then asks the LLM described by
+/// `llm` for a title when it is enabled. Falls back to a timestamp when the image
+/// cannot be decoded, the models are missing, no text was found, or the LLM
+/// request fails.
+pub(crate) async fn suggest_problem_name(
+    image_base64: String,
+    ocr_models_dir: PathBuf,
+    llm: &LlmSettings,
+) -> ProblemNameSuggestion {
+    // OCR is CPU-bound; keep it off the async runtime.
+    let ocr_result = tauri::async_runtime::spawn_blocking(move || {
+        let image = ocr::decode_image(&image_base64)?;
+        ocr::extract_text(&image, &ocr_models_dir)
+    })
+    .await;
+
+    let ocr_text = match ocr_result {
+        Ok(Ok(text)) if !text.is_empty() => Some(text),
+        Ok(Ok(_)) => None,
+        Ok(Err(e)) => {
+            log::warn!("OCR unavailable, using timestamp name: {}", e);
+            None
+        }
+        Err(e) => {
+            log::error!("OCR task failed: {}", e);
+            None
+        }
+    };
+
+    let name = match ocr_text.as_deref() {
+        Some(text) => name_from_ocr_text(text, llm).await,
+        None => None,
+    };
+
+    ProblemNameSuggestion {
+        name: name.unwrap_or_else(problem_name_timestamp),
+        ocr_text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_suggest_problem_name_returns_timestamp_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let suggestion = tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(suggest_problem_name(
+                String::new(),
+                dir.path().to_path_buf(),
+                &LlmSettings::default(),
+            ));
+        let name = suggestion.name;
+        assert!(
+            name.starts_with("problem-"),
+            "expected prefix 'problem-', got '{}'",
+            name
+        );
+        // Format: problem-YYYYMMDD-HHMMSS (no colons for portability)
+        assert!(
+            name.len() >= 22,
+            "expected at least 'problem-YYYYMMDD-HHMMSS' length"
+        );
+        assert!(
+            name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'),
+            "name must be filesystem-safe (alphanumeric and hyphen only), got '{}'",
+            name
+        );
+        assert!(suggestion.ocr_text.is_none());
+    }
+
+    #[test]
+    fn test_suggest_problem_name_falls_back_without_models() {
+        let dir = tempfile::tempdir().unwrap();
+        let bytes = std::fs::read(
+            PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("tests")
+                .join("fixtures")
+                .join("ocr_find_the_derivative.png"),
+        )
+        .unwrap();
+        use base64::prelude::*;
+        let image_base64 = BASE64_STANDARD.encode(bytes);
+
+        let suggestion = tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(suggest_problem_name(
+                image_base64,
+                dir.path().to_path_buf(),
+                &LlmSettings::default(),
+            ));
+
+        assert!(suggestion.name.starts_with("problem-"));
+        assert!(suggestion.ocr_text.is_none());
+    }
+
+    #[test]
+    fn test_name_from_text() {
+        assert_eq!(
+            name_from_text("Find the derivative of x^2 + 3x with respect to x"),
+            Some("find-the-derivative-of-x".to_string())
+        );
+        assert_eq!(
+            name_from_text("  Q1.\n(a) Solve: "),
+            Some("q1-a-solve".to_string())
+        );
+        assert_eq!(name_from_text(" ... !! "), None);
+        assert_eq!(name_from_text(""), None);
+    }
+
+    #[tokio::test]
+    async fn test_name_from_ocr_text_sanitises_llm_output() {
+        let endpoint = llm::tests::spawn_mock_server(
+            200,
+            r#"{"response":"\"Chain Rule: d/dx Sin(x^2)!\"  and more words"}"#,
+            std::time::Duration::ZERO,
+        )
+        .await;
+        let config = llm::tests::config_for(endpoint, 2000);
+
+        let name = name_from_ocr_text("d/dx sin(x^2)", &config).await;
+        assert_eq!(name, Some("chain-rule-d-dx-sin".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_name_from_ocr_text_llm_failure_falls_back() {
+        let endpoint =
+            llm::tests::spawn_mock_server(503, "{}", std::time::Duration::ZERO).await;
+        let config = llm::tests::config_for(endpoint, 2000);
+
+        assert_eq!(name_from_ocr_text("Find the area", &config).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_name_from_ocr_text_llm_disabled_uses_text() {
+        let name = name_from_ocr_text("Find the area", &LlmSettings::default()).await;
+        assert_eq!(name, Some("find-the-area".to_string()));
+    }
+}
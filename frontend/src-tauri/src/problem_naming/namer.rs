@@ -0,0 +1,258 @@
+//! Naming strategies.
+//!
+//! Each [`NamingStrategy`] from the settings maps to a [`ProblemNamer`]. The
+//! strategy is picked per set, falling back to the global default, and the
+//! resulting namer is what `receive_screenshot_data` calls.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sanitize_filename::sanitize;
+use std::path::PathBuf;
+
+use super::{problem_name_timestamp, suggest_problem_name, ProblemNameSuggestion};
+use crate::settings::{LlmSettings, NamingStrategy};
+
+/// Everything a namer may use to name a new problem.
+pub(crate) struct NamingContext<'a> {
+    pub folder_name: &'a str,
+    pub course_name: &'a str,
+    pub set_name: &'a str,
+    pub image_base64: &'a str,
+    /// Number of problems already in the set; the new problem is `problem_count + 1`.
+    pub problem_count: u64,
+    pub now: DateTime<Utc>,
+}
+
+#[async_trait]
+pub(crate) trait ProblemNamer: Send + Sync {
+    async fn name(&self, ctx: &NamingContext<'_>) -> ProblemNameSuggestion;
+}
+
+/// OCR → LLM pipeline, see [`suggest_problem_name`].
+pub(crate) struct AiNamer {
+    pub ocr_models_dir: PathBuf,
+    pub llm: LlmSettings,
+}
+
+#[async_trait]
+impl ProblemNamer for AiNamer {
+    async fn name(&self, ctx: &NamingContext<'_>) -> ProblemNameSuggestion {
+        suggest_problem_name(
+            ctx.image_base64.to_string(),
+            self.ocr_models_dir.clone(),
+            &self.llm,
+        )
+        .await
+    }
+}
+
+/// `problem-YYYYMMDD-HHMMSS`
+pub(crate) struct TimestampNamer;
+
+#[async_trait]
+impl ProblemNamer for TimestampNamer {
+    async fn name(&self, _ctx: &NamingContext<'_>) -> ProblemNameSuggestion {
+        ProblemNameSuggestion {
+            name: problem_name_timestamp(),
+            ocr_text: None,
+        }
+    }
+}
+
+/// `Q1`, `Q2`, … numbered within the set.
+pub(crate) struct SequenceNamer {
+    pub prefix: String,
+}
+
+#[async_trait]
+impl ProblemNamer for SequenceNamer {
+    async fn name(&self, ctx: &NamingContext<'_>) -> ProblemNameSuggestion {
+        let name = sanitize(format!("{}{}", self.prefix, ctx.problem_count + 1));
+        ProblemNameSuggestion {
+            name: if name.is_empty() {
+                problem_name_timestamp()
+            } else {
+                name
+            },
+            ocr_text: None,
+        }
+    }
+}
+
+/// User-defined template such as `{set}-{n}-{date}`, see [`render_template`].
+pub(crate) struct TemplateNamer {
+    pub template: String,
+}
+
+#[async_trait]
+impl ProblemNamer for TemplateNamer {
+    async fn name(&self, ctx: &NamingContext<'_>) -> ProblemNameSuggestion {
+        let name = match render_template(&self.template, ctx) {
+            Ok(rendered) => sanitize(rendered.trim()),
+            Err(e) => {
+                log::warn!("Invalid naming template '{}': {}", self.template, e);
+                String::new()
+            }
+        };
+
+        ProblemNameSuggestion {
+            name: if name.is_empty() {
+                problem_name_timestamp()
+            } else {
+                name
+            },
+            ocr_text: None,
+        }
+    }
+}
+
+/// Expands `{folder}`, `{course}`, `{set}`, `{n}`, `{date}` (`YYYYMMDD`) and
+/// `{time}` (`HHMMSS`). Errors on unknown placeholders or unbalanced braces.
+pub(crate) fn render_template(template: &str, ctx: &NamingContext<'_>) -> Result<String, String> {
+    let mut out = String::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find(['{', '}']) {
+        if rest[start..].starts_with('}') {
+            return Err("unexpected '}'".to_string());
+        }
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let end = after
+            .find('}')
+            .ok_or_else(|| "unclosed '{'".to_string())?;
+        let value = match &after[..end] {
+            "folder" => ctx.folder_name.to_string(),
+            "course" => ctx.course_name.to_string(),
+            "set" => ctx.set_name.to_string(),
+            "n" => (ctx.problem_count + 1).to_string(),
+            "date" => ctx.now.format("%Y%m%d").to_string(),
+            "time" => ctx.now.format("%H%M%S").to_string(),
+            other => return Err(format!("unknown placeholder '{{{}}}'", other)),
+        };
+        out.push_str(&value);
+        rest = &after[end + 1..];
+    }
+    out.push_str(rest);
+
+    Ok(out)
+}
+
+/// Checks a strategy before it is saved to settings.
+pub(crate) fn validate_strategy(strategy: &NamingStrategy) -> Result<(), String> {
+    if let NamingStrategy::Template { template } = strategy {
+        let ctx = NamingContext {
+            folder_name: "",
+            course_name: "",
+            set_name: "",
+            image_base64: "",
+            problem_count: 0,
+            now: Utc::now(),
+        };
+        render_template(template, &ctx)?;
+        if template.trim().is_empty() {
+            return Err("template must not be empty".to_string());
+        }
+    }
+    Ok(())
+}
+
+/// Builds the namer for a configured strategy.
+pub(crate) fn namer_for(
+    strategy: &NamingStrategy,
+    ocr_models_dir: PathBuf,
+    llm: LlmSettings,
+) -> Box<dyn ProblemNamer> {
+    match strategy {
+        NamingStrategy::Ai => Box::new(AiNamer {
+            ocr_models_dir,
+            llm,
+        }),
+        NamingStrategy::Timestamp => Box::new(TimestampNamer),
+        NamingStrategy::Sequence { prefix } => Box::new(SequenceNamer {
+            prefix: prefix.clone(),
+        }),
+        NamingStrategy::Template { template } => Box::new(TemplateNamer {
+            template: template.clone(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn ctx(problem_count: u64) -> NamingContext<'static> {
+        NamingContext {
+            folder_name: "Math",
+            course_name: "Calculus I",
+            set_name: "Week 3",
+            image_base64: "",
+            problem_count,
+            now: Utc.with_ymd_and_hms(2026, 3, 14, 15, 9, 26).unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_timestamp_namer() {
+        let suggestion = TimestampNamer.name(&ctx(0)).await;
+        assert!(suggestion.name.starts_with("problem-"));
+    }
+
+    #[tokio::test]
+    async fn test_sequence_namer() {
+        let namer = SequenceNamer {
+            prefix: "Q".to_string(),
+        };
+        assert_eq!(namer.name(&ctx(0)).await.name, "Q1");
+        assert_eq!(namer.name(&ctx(11)).await.name, "Q12");
+    }
+
+    #[tokio::test]
+    async fn test_template_namer() {
+        let namer = TemplateNamer {
+            template: "{set}-{n}-{date}".to_string(),
+        };
+        assert_eq!(namer.name(&ctx(2)).await.name, "Week 3-3-20260314");
+
+        let namer = TemplateNamer {
+            template: "{folder}/{course} {time}".to_string(),
+        };
+        // '/' is stripped by sanitize
+        assert_eq!(namer.name(&ctx(0)).await.name, "MathCalculus I 150926");
+    }
+
+    #[tokio::test]
+    async fn test_template_namer_invalid_template_falls_back() {
+        let namer = TemplateNamer {
+            template: "{unknown}".to_string(),
+        };
+        assert!(namer.name(&ctx(0)).await.name.starts_with("problem-"));
+    }
+
+    #[test]
+    fn test_render_template_errors() {
+        assert!(render_template("{set", &ctx(0)).is_err());
+        assert!(render_template("set}", &ctx(0)).is_err());
+        assert!(render_template("{nope}", &ctx(0)).is_err());
+        assert_eq!(render_template("plain", &ctx(0)).unwrap(), "plain");
+    }
+
+    #[test]
+    fn test_validate_strategy() {
+        assert!(validate_strategy(&NamingStrategy::Ai).is_ok());
+        assert!(validate_strategy(&NamingStrategy::Template {
+            template: "{course}-{n}".to_string()
+        })
+        .is_ok());
+        assert!(validate_strategy(&NamingStrategy::Template {
+            template: "{bad}".to_string()
+        })
+        .is_err());
+        assert!(validate_strategy(&NamingStrategy::Template {
+            template: "  ".to_string()
+        })
+        .is_err());
+    }
+}
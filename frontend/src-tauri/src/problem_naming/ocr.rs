@@ -0,0 +1,142 @@
+//! OCR phase of the naming pipeline.
+//!
+//! Uses `ocrs` (pure Rust, no native deps) with `.rten` model files that ship
+//! under `<app_data>/models/ocr/`. Models are never downloaded at runtime; if
+//! they are missing the caller falls back to a timestamp name.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use base64::prelude::*;
+use image::DynamicImage;
+use ocrs::{ImageSource, OcrEngine, OcrEngineParams};
+use rten::Model;
+
+pub(crate) const DETECTION_MODEL_FILE: &str = "text-detection.rten";
+pub(crate) const RECOGNITION_MODEL_FILE: &str = "text-recognition.rten";
+
+#[derive(Debug)]
+pub(crate) enum OcrError {
+    /// The payload was not valid base64 or not a decodable image.
+    Decode(String),
+    /// A model file was not found in the models directory.
+    ModelMissing(PathBuf),
+    /// The engine failed to load a model or run recognition.
+    Engine(String),
+}
+
+impl fmt::Display for OcrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OcrError::Decode(e) => write!(f, "failed to decode image: {}", e),
+            OcrError::ModelMissing(path) => write!(f, "OCR model not found at {}", path.display()),
+            OcrError::Engine(e) => write!(f, "OCR engine error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for OcrError {}
+
+/// Directory holding the OCR model files, relative to the app data directory.
+pub(crate) fn models_dir(app_data_dir: &Path) -> PathBuf {
+    app_data_dir.join("models").join("ocr")
+}
+
+/// Decodes a base64 image, with or without a `data:image/...;base64,` header.
+pub(crate) fn decode_image(image_base64: &str) -> Result<DynamicImage, OcrError> {
+    let b64_string = image_base64.split(',').next_back().unwrap_or(image_base64);
+    let bytes = BASE64_STANDARD
+        .decode(b64_string.trim())
+        .map_err(|e| OcrError::Decode(e.to_string()))?;
+    image::load_from_memory(&bytes).map_err(|e| OcrError::Decode(e.to_string()))
+}
+
+fn load_model(models_dir: &Path, file_name: &str) -> Result<Model, OcrError> {
+    let path = models_dir.join(file_name);
+    if !path.exists() {
+        return Err(OcrError::ModelMissing(path));
+    }
+    Model::load_file(&path).map_err(|e| OcrError::Engine(e.to_string()))
+}
+
+/// Runs detection + recognition on `image` and returns the recognised lines
+/// joined with newlines. Blocking and CPU-heavy; call from `spawn_blocking`.
+pub(crate) fn extract_text(image: &DynamicImage, models_dir: &Path) -> Result<String, OcrError> {
+    let detection_model = load_model(models_dir, DETECTION_MODEL_FILE)?;
+    let recognition_model = load_model(models_dir, RECOGNITION_MODEL_FILE)?;
+
+    let engine = OcrEngine::new(OcrEngineParams {
+        detection_model: Some(detection_model),
+        recognition_model: Some(recognition_model),
+        ..Default::default()
+    })
+    .map_err(|e| OcrError::Engine(e.to_string()))?;
+
+    let rgb = image.to_rgb8();
+    let source = ImageSource::from_bytes(rgb.as_raw(), rgb.dimensions())
+        .map_err(|e| OcrError::Engine(e.to_string()))?;
+    let input = engine
+        .prepare_input(source)
+        .map_err(|e| OcrError::Engine(e.to_string()))?;
+    let text = engine
+        .get_text(&input)
+        .map_err(|e| OcrError::Engine(e.to_string()))?;
+
+    Ok(text.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("fixtures")
+            .join(name)
+    }
+
+    fn fixture_base64(name: &str) -> String {
+        let bytes = std::fs::read(fixture(name)).expect("Failed to read fixture");
+        format!("data:image/png;base64,{}", BASE64_STANDARD.encode(bytes))
+    }
+
+    #[test]
+    fn test_decode_image_fixture() {
+        let image = decode_image(&fixture_base64("ocr_find_the_derivative.png"))
+            .expect("Fixture should decode");
+        assert!(image.width() > image.height());
+    }
+
+    #[test]
+    fn test_decode_image_rejects_garbage() {
+        assert!(matches!(decode_image("not base64!"), Err(OcrError::Decode(_))));
+        let not_an_image = BASE64_STANDARD.encode(b"plain text");
+        assert!(matches!(decode_image(&not_an_image), Err(OcrError::Decode(_))));
+    }
+
+    #[test]
+    fn test_extract_text_missing_models() {
+        let dir = tempfile::tempdir().unwrap();
+        let image = decode_image(&fixture_base64("ocr_blank.png")).unwrap();
+
+        let result = extract_text(&image, dir.path());
+        assert!(matches!(result, Err(OcrError::ModelMissing(_))));
+    }
+
+    // Runs only when PLUTODESK_OCR_MODELS points at a directory with both model files.
+    #[test]
+    fn test_extract_text_with_models() {
+        let Some(dir) = std::env::var_os("PLUTODESK_OCR_MODELS") else {
+            return;
+        };
+        let image = decode_image(&fixture_base64("ocr_find_the_derivative.png")).unwrap();
+
+        let text = extract_text(&image, Path::new(&dir)).expect("OCR should succeed");
+        assert!(
+            text.to_uppercase().contains("DERIVATIVE"),
+            "unexpected OCR output: '{}'",
+            text
+        );
+    }
+}
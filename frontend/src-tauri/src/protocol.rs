@@ -0,0 +1,316 @@
+//! Custom URI schemes that serve image bytes to the webviews.
+//!
+//! - `pluto-capture://current` — the capture held in [`ScreenshotData`]
+//! - `pluto-image://<problem_id>` — a problem's primary image from the app data dir
+//! - `pluto-image://<problem_id>/images/<image_id>` — any one of its images
+//! - `pluto-image://<problem_id>/thumbnail/<size>` — one of its cached thumbnails
+//!
+//! Webviews on Windows and Android reach these as
+//! `http://<scheme>.localhost/<path>`; use [`url`] to build a URL that works
+//! everywhere.
+
+use image::codecs::png::{CompressionType, FilterType, PngEncoder};
+use image::{ExtendedColorType, ImageEncoder};
+use std::path::Path;
+use tauri::http::{header, Request, Response, StatusCode, Uri};
+use tauri::{AppHandle, Manager, Url};
+use uuid::Uuid;
+
+use crate::commands::thumbnails::ensure_thumbnails;
+use crate::db::{services, Db};
+use crate::imaging::thumbnail;
+use crate::screenshot::ScreenshotData;
+use crate::storage::resolve_image_file;
+
+pub const CAPTURE_SCHEME: &str = "pluto-capture";
+pub const IMAGE_SCHEME: &str = "pluto-image";
+
+/// URL for `path` on one of our schemes, in the form the current platform's webview expects
+pub fn url(scheme: &str, path: &str) -> String {
+    if cfg!(any(windows, target_os = "android")) {
+        format!("http://{}.localhost/{}", scheme, path)
+    } else {
+        format!("{}://localhost/{}", scheme, path)
+    }
+}
+
+/// Path segments of a request. `pluto-image://<id>/…`, `pluto-image://localhost/<id>/…`
+/// and `http://pluto-image.localhost/<id>/…` all give `[<id>, …]`.
+fn segments(uri: &Uri) -> Vec<String> {
+    let mut segments = Vec::new();
+    if let Some(host) = uri.host() {
+        if host != "localhost" && !host.ends_with(".localhost") {
+            segments.push(host.to_string());
+        }
+    }
+    segments.extend(
+        uri.path()
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| segment.to_string()),
+    );
+    segments
+}
+
+fn content_type_for(path: &Path) -> &'static str {
+    match path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .as_deref()
+    {
+        Some("webp") => "image/webp",
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        _ => "image/png",
+    }
+}
+
+fn respond(status: StatusCode, content_type: &str, body: Vec<u8>) -> Response<Vec<u8>> {
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, content_type)
+        .header(header::CACHE_CONTROL, "no-store")
+        .body(body)
+        .unwrap()
+}
+
+fn not_found(message: &str) -> Response<Vec<u8>> {
+    respond(StatusCode::NOT_FOUND, "text/plain", message.as_bytes().to_vec())
+}
+
+fn server_error(message: String) -> Response<Vec<u8>> {
+    log::error!("{}", message);
+    respond(StatusCode::INTERNAL_SERVER_ERROR, "text/plain", message.into_bytes())
+}
+
+/// Whether `origin` is one of our own pages: the bundled frontend, or the dev
+/// server in debug builds
+fn is_app_origin(origin: &str, dev_url: Option<&Url>) -> bool {
+    matches!(
+        origin,
+        "tauri://localhost" | "http://tauri.localhost" | "https://tauri.localhost"
+    ) || (cfg!(debug_assertions)
+        && dev_url.is_some_and(|url| url.origin().ascii_serialization() == origin))
+}
+
+/// Serves the capture held in memory, PNG-encoded on request. The overlay
+/// `fetch`es it from another origin, so only our own pages are let read it.
+pub async fn handle_capture(app: AppHandle, request: Request<Vec<u8>>) -> Response<Vec<u8>> {
+    let origin = request
+        .headers()
+        .get(header::ORIGIN)
+        .filter(|origin| {
+            origin
+                .to_str()
+                .is_ok_and(|origin| is_app_origin(origin, app.config().build.dev_url.as_ref()))
+        })
+        .cloned();
+
+    let mut response = capture_response(app, request).await;
+    if let Some(origin) = origin {
+        let headers = response.headers_mut();
+        headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+        headers.insert(header::VARY, header::HeaderValue::from_static("Origin"));
+    }
+    response
+}
+
+async fn capture_response(app: AppHandle, request: Request<Vec<u8>>) -> Response<Vec<u8>> {
+    if segments(request.uri()) != ["current"] {
+        return not_found("Unknown capture");
+    }
+
+    let encoded = tauri::async_runtime::spawn_blocking(move || {
+        // Copied out so saving the selection isn't kept waiting on the encode
+        let image = {
+            let state = app.try_state::<ScreenshotData>()?;
+            let data = state.lock().unwrap();
+            data.as_ref()?.image.clone()
+        };
+
+        // Fast compression: this is only for display and is thrown away afterwards
+        let mut png_bytes = Vec::new();
+        let result = PngEncoder::new_with_quality(
+            &mut png_bytes,
+            CompressionType::Fast,
+            FilterType::Adaptive,
+        )
+        .write_image(
+            &image,
+            image.width(),
+            image.height(),
+            ExtendedColorType::Rgba8,
+        );
+        Some(result.map(|_| png_bytes))
+    })
+    .await;
+
+    match encoded {
+        Ok(Some(Ok(png_bytes))) => respond(StatusCode::OK, "image/png", png_bytes),
+        Ok(Some(Err(e))) => server_error(format!("Failed to encode capture: {}", e)),
+        Ok(None) => not_found("No capture"),
+        Err(e) => server_error(format!("Capture encoding task failed: {}", e)),
+    }
+}
+
+/// Which file of a problem a `pluto-image` request is for
+#[derive(Debug, PartialEq)]
+enum ImageRequest {
+    Primary,
+    Image(Uuid),
+    Thumbnail(u32),
+}
+
+fn parse_image_request(segments: &[String]) -> Result<(Uuid, ImageRequest), &'static str> {
+    let Some(problem_id) = segments.first().and_then(|t| Uuid::parse_str(t).ok()) else {
+        return Err("Invalid problem id");
+    };
+    let request = match &segments[1..] {
+        [] => ImageRequest::Primary,
+        [kind, image_id] if kind == "images" => match Uuid::parse_str(image_id) {
+            Ok(image_id) => ImageRequest::Image(image_id),
+            Err(_) => return Err("Invalid image id"),
+        },
+        [kind, size] if kind == "thumbnail" => match size.parse::<u32>() {
+            Ok(size) => ImageRequest::Thumbnail(thumbnail::snap_size(size)),
+            Err(_) => return Err("Invalid thumbnail size"),
+        },
+        _ => return Err("Unknown image"),
+    };
+    Ok((problem_id, request))
+}
+
+/// Serves a problem's image files from the app data dir, or one of its
+/// thumbnails for `<problem_id>/thumbnail/<size>`
+pub async fn handle_image(app: AppHandle, request: Request<Vec<u8>>) -> Response<Vec<u8>> {
+    let (problem_id, image_request) = match parse_image_request(&segments(request.uri())) {
+        Ok(parsed) => parsed,
+        Err(message) => return not_found(message),
+    };
+
+    let Some(db) = app.try_state::<Db>() else {
+        return server_error("Database not initialized".to_string());
+    };
+    let image = match &image_request {
+        ImageRequest::Image(image_id) => {
+            services::get_problem_image_by_id(db.connection(), *image_id)
+                .await
+                .map(|image| image.filter(|image| image.problem_id == problem_id))
+        }
+        _ => services::get_primary_image(db.connection(), problem_id).await,
+    };
+    let image_path = match image {
+        Ok(Some(image)) => image.path,
+        Ok(None) => return not_found("Image not found"),
+        Err(e) => return server_error(format!("Database error: {}", e)),
+    };
+
+    let app_dir = match app.path().app_data_dir() {
+        Ok(dir) => dir,
+        Err(e) => return server_error(format!("Failed to get app data directory: {}", e)),
+    };
+
+    let file = match image_request {
+        ImageRequest::Thumbnail(size) => {
+            // Thumbnails are generated on first request if the backfill hasn't reached them
+            if let Err(e) = ensure_thumbnails(app_dir.clone(), problem_id, image_path, false).await {
+                return not_found(&e);
+            }
+            thumbnail::thumbnail_path(&thumbnail::cache_dir(&app_dir), problem_id, size)
+        }
+        ImageRequest::Primary | ImageRequest::Image(_) => {
+            match resolve_image_file(&app_dir, &image_path) {
+                Some(file) => file,
+                None => return not_found("Image file not found"),
+            }
+        }
+    };
+
+    match read_file(&file).await {
+        Ok(bytes) => respond(StatusCode::OK, content_type_for(&file), bytes),
+        Err(e) => server_error(format!("Failed to read {}: {}", file.display(), e)),
+    }
+}
+
+async fn read_file(path: &Path) -> std::io::Result<Vec<u8>> {
+    let path = path.to_path_buf();
+    tauri::async_runtime::spawn_blocking(move || std::fs::read(path))
+        .await
+        .map_err(std::io::Error::other)?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segments_accept_all_url_forms() {
+        let id = "6f1c2a54-3f0e-4a8e-9a43-1f2b7e0c9d11";
+        for uri in [
+            format!("pluto-image://{}", id),
+            format!("pluto-image://localhost/{}", id),
+            format!("http://pluto-image.localhost/{}", id),
+        ] {
+            let uri: Uri = uri.parse().unwrap();
+            assert_eq!(segments(&uri), vec![id.to_string()]);
+        }
+
+        for uri in [
+            format!("pluto-image://{}/thumbnail/256", id),
+            format!("http://pluto-image.localhost/{}/thumbnail/256", id),
+        ] {
+            let uri: Uri = uri.parse().unwrap();
+            assert_eq!(segments(&uri), vec![id, "thumbnail", "256"]);
+        }
+
+        let uri: Uri = "pluto-capture://current".parse().unwrap();
+        assert_eq!(segments(&uri), vec!["current"]);
+    }
+
+    #[test]
+    fn test_is_app_origin() {
+        assert!(is_app_origin("tauri://localhost", None));
+        assert!(is_app_origin("http://tauri.localhost", None));
+        assert!(!is_app_origin("https://example.com", None));
+        assert!(!is_app_origin("null", None));
+
+        let dev_url: Url = "http://localhost:3000/".parse().unwrap();
+        assert_eq!(
+            is_app_origin("http://localhost:3000", Some(&dev_url)),
+            cfg!(debug_assertions)
+        );
+        assert!(!is_app_origin("http://localhost:3001", Some(&dev_url)));
+    }
+
+    #[test]
+    fn test_parse_image_request() {
+        let id = Uuid::new_v4();
+        let image_id = Uuid::new_v4();
+        let parse = |path: &str| {
+            let segments: Vec<String> = path.split('/').map(|s| s.to_string()).collect();
+            parse_image_request(&segments)
+        };
+
+        assert_eq!(parse(&id.to_string()), Ok((id, ImageRequest::Primary)));
+        assert_eq!(
+            parse(&format!("{}/images/{}", id, image_id)),
+            Ok((id, ImageRequest::Image(image_id)))
+        );
+        assert_eq!(
+            parse(&format!("{}/thumbnail/200", id)),
+            Ok((id, ImageRequest::Thumbnail(256)))
+        );
+        assert!(parse("nope").is_err());
+        assert!(parse(&format!("{}/images/nope", id)).is_err());
+        assert!(parse(&format!("{}/thumbnail/big", id)).is_err());
+        assert!(parse(&format!("{}/other", id)).is_err());
+    }
+
+    #[test]
+    fn test_content_type_for() {
+        assert_eq!(content_type_for(Path::new("a/b.png")), "image/png");
+        assert_eq!(content_type_for(Path::new("a/b.WEBP")), "image/webp");
+        assert_eq!(content_type_for(Path::new("a/b.jpg")), "image/jpeg");
+        assert_eq!(content_type_for(Path::new("a/b.jpeg")), "image/jpeg");
+    }
+}
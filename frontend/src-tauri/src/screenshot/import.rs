@@ -0,0 +1,316 @@
+//! Problems from sources other than the screen: the clipboard and image files.
+//! Everything goes through [`save_problem_image`], like a screen capture.
+
+use base64::{engine::general_purpose, Engine};
+use image::RgbaImage;
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+use super::{encode_png_base64, save_problem_image, SavedProblem};
+
+/// File extensions picked up by a directory import
+const IMAGE_EXTENSIONS: [&str; 7] = ["png", "jpg", "jpeg", "webp", "gif", "bmp", "tiff"];
+
+/// What happened to one file of an import
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ImportOutcome {
+    Imported { problem: SavedProblem },
+    Skipped { reason: String },
+    Failed { error: String },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportResult {
+    pub path: String,
+    #[serde(flatten)]
+    pub outcome: ImportOutcome,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ImportReport {
+    pub imported: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    pub results: Vec<ImportResult>,
+}
+
+impl ImportReport {
+    fn push(&mut self, path: &Path, outcome: ImportOutcome) {
+        match outcome {
+            ImportOutcome::Imported { .. } => self.imported += 1,
+            ImportOutcome::Skipped { .. } => self.skipped += 1,
+            ImportOutcome::Failed { .. } => self.failed += 1,
+        }
+        self.results.push(ImportResult {
+            path: path.to_string_lossy().to_string(),
+            outcome,
+        });
+    }
+}
+
+fn is_image_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| IMAGE_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// Files in `dir` sorted by path, split into images and everything else.
+/// Hidden files are ignored; subdirectories are walked when `recursive` is set.
+/// Symlinks to directories are never followed, so a link loop can't trap the walk.
+fn list_directory(dir: &Path, recursive: bool) -> io::Result<(Vec<PathBuf>, Vec<PathBuf>)> {
+    let mut images = Vec::new();
+    let mut others = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let hidden = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .map(|n| n.starts_with('.'))
+                .unwrap_or(false);
+            if hidden {
+                continue;
+            }
+
+            if entry.file_type()?.is_dir() {
+                if recursive {
+                    pending.push(path);
+                }
+            } else if path.is_dir() {
+                continue;
+            } else if is_image_file(&path) {
+                images.push(path);
+            } else {
+                others.push(path);
+            }
+        }
+    }
+
+    images.sort();
+    others.sort();
+    Ok((images, others))
+}
+
+/// Reads an image file as base64, after checking that it decodes
+fn read_image_file(path: &Path) -> Result<String, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read file: {}", e))?;
+    image::load_from_memory(&bytes).map_err(|e| format!("Not a readable image: {}", e))?;
+    Ok(general_purpose::STANDARD.encode(&bytes))
+}
+
+async fn import_file(
+    app: &AppHandle,
+    path: &Path,
+    folder_id: &Option<String>,
+    course_id: &Option<String>,
+    set_id: &Option<String>,
+) -> ImportOutcome {
+    let path_buf = path.to_path_buf();
+    let read = tauri::async_runtime::spawn_blocking(move || read_image_file(&path_buf)).await;
+    let image_base64 = match read.map_err(|e| e.to_string()).and_then(|r| r) {
+        Ok(image_base64) => image_base64,
+        Err(error) => return ImportOutcome::Failed { error },
+    };
+
+    match save_problem_image(
+        app,
+        image_base64,
+        None,
+        folder_id.clone(),
+        course_id.clone(),
+        set_id.clone(),
+    )
+    .await
+    {
+        Ok(problem) => ImportOutcome::Imported { problem },
+        Err(e) => ImportOutcome::Failed {
+            error: e.to_string(),
+        },
+    }
+}
+
+/// Save the image on the clipboard as a problem in the given set, or in the
+/// active session's set when no ids are passed.
+#[tauri::command]
+pub async fn capture_clipboard(
+    app: AppHandle,
+    folder_id: Option<String>,
+    course_id: Option<String>,
+    set_id: Option<String>,
+) -> Result<SavedProblem, tauri::Error> {
+    let clipboard_image = app.clipboard().read_image().map_err(|e| {
+        tauri::Error::from(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("No image on the clipboard: {}", e),
+        ))
+    })?;
+
+    let image = RgbaImage::from_raw(
+        clipboard_image.width(),
+        clipboard_image.height(),
+        clipboard_image.rgba().to_vec(),
+    )
+    .ok_or_else(|| {
+        tauri::Error::from(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "Clipboard image has an unexpected size",
+        ))
+    })?;
+
+    let image_base64 = encode_png_base64(&image).map_err(|e| {
+        tauri::Error::from(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Failed to encode clipboard image: {}", e),
+        ))
+    })?;
+
+    save_problem_image(&app, image_base64, None, folder_id, course_id, set_id).await
+}
+
+/// Create one problem per image file, in the order given. Each file is
+/// reported separately; one failure doesn't stop the rest.
+#[tauri::command]
+pub async fn import_image_files(
+    app: AppHandle,
+    paths: Vec<String>,
+    folder_id: Option<String>,
+    course_id: Option<String>,
+    set_id: Option<String>,
+) -> Result<ImportReport, String> {
+    let mut report = ImportReport::default();
+    for path in paths.iter().map(PathBuf::from) {
+        let outcome = import_file(&app, &path, &folder_id, &course_id, &set_id).await;
+        report.push(&path, outcome);
+    }
+
+    Ok(report)
+}
+
+/// Create one problem per image in a directory, in file name order.
+/// Other files are reported as skipped.
+#[tauri::command]
+pub async fn import_image_directory(
+    app: AppHandle,
+    dir: String,
+    recursive: Option<bool>,
+    folder_id: Option<String>,
+    course_id: Option<String>,
+    set_id: Option<String>,
+) -> Result<ImportReport, String> {
+    let (images, others) = list_directory(Path::new(&dir), recursive.unwrap_or(false))
+        .map_err(|e| format!("Failed to read directory {}: {}", dir, e))?;
+
+    let mut report = ImportReport::default();
+    for path in others {
+        report.push(
+            &path,
+            ImportOutcome::Skipped {
+                reason: "Not an image file".to_string(),
+            },
+        );
+    }
+    for path in images {
+        let outcome = import_file(&app, &path, &folder_id, &course_id, &set_id).await;
+        report.push(&path, outcome);
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+    use tempfile::tempdir;
+
+    fn write_png(path: &Path) {
+        RgbaImage::from_pixel(4, 4, Rgba([0, 0, 0, 255]))
+            .save(path)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_list_directory() {
+        let dir = tempdir().unwrap();
+        write_png(&dir.path().join("b.png"));
+        write_png(&dir.path().join("a.PNG"));
+        fs::write(dir.path().join("notes.txt"), b"notes").unwrap();
+        fs::write(dir.path().join(".DS_Store"), b"").unwrap();
+        fs::create_dir(dir.path().join("week2")).unwrap();
+        write_png(&dir.path().join("week2").join("c.png"));
+
+        let (images, others) = list_directory(dir.path(), false).unwrap();
+        assert_eq!(
+            images,
+            vec![dir.path().join("a.PNG"), dir.path().join("b.png")]
+        );
+        assert_eq!(others, vec![dir.path().join("notes.txt")]);
+
+        let (images, _) = list_directory(dir.path(), true).unwrap();
+        assert_eq!(images.len(), 3);
+        assert!(images.contains(&dir.path().join("week2").join("c.png")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_list_directory_with_symlink_loop() {
+        let dir = tempdir().unwrap();
+        let week1 = dir.path().join("week1");
+        fs::create_dir(&week1).unwrap();
+        write_png(&week1.join("a.png"));
+        std::os::unix::fs::symlink(dir.path(), week1.join("loop")).unwrap();
+
+        let (images, others) = list_directory(dir.path(), true).unwrap();
+        assert_eq!(images, vec![week1.join("a.png")]);
+        assert!(others.is_empty());
+    }
+
+    #[test]
+    fn test_read_image_file() {
+        let dir = tempdir().unwrap();
+        let png = dir.path().join("q1.png");
+        write_png(&png);
+        let decoded = general_purpose::STANDARD
+            .decode(read_image_file(&png).unwrap())
+            .unwrap();
+        assert_eq!(decoded, fs::read(&png).unwrap());
+
+        // An image extension doesn't make a file an image
+        let fake = dir.path().join("fake.png");
+        fs::write(&fake, b"not an image").unwrap();
+        assert!(read_image_file(&fake).is_err());
+        assert!(read_image_file(&dir.path().join("missing.png")).is_err());
+    }
+
+    #[test]
+    fn test_report_counts_outcomes() {
+        let mut report = ImportReport::default();
+        report.push(
+            Path::new("a.txt"),
+            ImportOutcome::Skipped {
+                reason: "Not an image file".to_string(),
+            },
+        );
+        report.push(
+            Path::new("b.png"),
+            ImportOutcome::Failed {
+                error: "No active session".to_string(),
+            },
+        );
+
+        assert_eq!((report.imported, report.skipped, report.failed), (0, 1, 1));
+        let json = serde_json::to_value(&report.results[1]).unwrap();
+        assert_eq!(json["path"], "b.png");
+        assert_eq!(json["status"], "failed");
+        assert_eq!(json["error"], "No active session");
+    }
+}
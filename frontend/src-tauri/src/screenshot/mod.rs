@@ -0,0 +1,1066 @@
+use crate::problem_naming::namer::{namer_for, NamingContext};
+use crate::problem_naming::ocr;
+use crate::protocol;
+use std::borrow::Cow;
+use std::sync::{Arc, Mutex};
+
+use crate::commands::storage::refresh_sidecars_in_scope;
+use crate::commands::thumbnails::ensure_thumbnails;
+use crate::db::entities::problem_images::ImageRole;
+use crate::db::services::{DuplicateMatch, ImageScope};
+use crate::db::{services, Db};
+use crate::dtos::screenshot::ScreenshotDto;
+use crate::imaging::cleanup;
+use crate::imaging::crop::{crop_selection, SelectionRect};
+use crate::imaging::encode::{self, EncodedImage};
+use crate::imaging::{phash, png_meta};
+use crate::notify::notify;
+use crate::imaging::redact::{self, Redaction};
+use crate::imaging::stitch::{stitch, ScreenTile};
+use crate::session::SessionManagerState;
+use crate::settings::{CaptureMode, CleanupSteps, EncodingSettings, SettingsState};
+use crate::storage::sidecar::ImageMetadata;
+use crate::storage::{self, StoredBlob};
+use source::{MonitorInfo, ScreenSource, WindowInfo, XcapSource};
+use base64::{engine::general_purpose, prelude::*};
+use image::{ExtendedColorType, ImageEncoder, RgbaImage};
+use tauri::{AppHandle, Emitter, Listener, Manager, State};
+#[cfg(desktop)]
+use tauri_plugin_global_shortcut::{Code, GlobalShortcutExt, Modifiers, Shortcut};
+use sea_orm::ActiveEnum;
+use serde::Serialize;
+
+pub(crate) mod import;
+pub(crate) mod source;
+
+/// Desktop area covered by a capture, and the monitors inside it.
+/// The overlay window is placed over exactly this area.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CaptureLayout {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub monitors: Vec<MonitorInfo>,
+}
+
+/// The last captured frame, kept so a selection can be cropped from it
+/// without sending image data back from the overlay.
+pub struct CapturedFrame {
+    /// Served to the overlay as `pluto-capture://current`
+    pub image: RgbaImage,
+    /// Image pixels per logical pixel of the layout
+    pub scale_factor: f64,
+    pub layout: CaptureLayout,
+}
+
+/// A capture saved as a problem, with any existing problems it looks like.
+/// The overlay offers to log an attempt on a duplicate instead.
+#[derive(Debug, Clone, Serialize)]
+pub struct SavedProblem {
+    pub problem_id: uuid::Uuid,
+    pub title: String,
+    pub duplicates: Vec<DuplicateMatch>,
+}
+
+// Store screenshot data temporarily to avoid large event payloads
+pub type ScreenshotData = Arc<Mutex<Option<CapturedFrame>>>;
+
+fn encode_png_base64(image: &RgbaImage) -> Result<String, image::ImageError> {
+    let mut png_bytes: Vec<u8> = Vec::new();
+    image::codecs::png::PngEncoder::new(&mut png_bytes).write_image(
+        image,
+        image.width(),
+        image.height(),
+        ExtendedColorType::Rgba8,
+    )?;
+
+    Ok(general_purpose::STANDARD.encode(&png_bytes))
+}
+
+/// Burns `redactions` (image pixels) into an image (base64 or data URL) on a
+/// blocking thread. Returns the result as PNG base64.
+async fn redact_image(image_url: String, redactions: Vec<Redaction>) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut image = ocr::decode_image(&image_url)
+            .map_err(|e| e.to_string())?
+            .to_rgba8();
+        redact::apply(&mut image, &redactions, 1.0);
+        encode_png_base64(&image).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Runs the cleanup pipeline on an image (base64 or data URL) on a blocking
+/// thread. Returns the result as PNG base64.
+pub(crate) async fn clean_up_image(image_url: String, steps: CleanupSteps) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let image = ocr::decode_image(&image_url).map_err(|e| e.to_string())?;
+        let cleaned = cleanup::apply(image, &steps);
+        encode_png_base64(&cleaned.to_rgba8()).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Monitors covered by a capture mode
+fn monitors_for_mode(
+    source: &dyn ScreenSource,
+    mode: &CaptureMode,
+) -> Result<Vec<MonitorInfo>, Box<dyn std::error::Error>> {
+    match mode {
+        CaptureMode::MonitorUnderMouse => Ok(vec![source.monitor_under_cursor()?]),
+        CaptureMode::Monitor { id } => source
+            .monitors()?
+            .into_iter()
+            .find(|m| m.id == *id)
+            .map(|m| vec![m])
+            .ok_or_else(|| format!("Monitor {} not found", id).into()),
+        CaptureMode::AllMonitors => Ok(source.monitors()?),
+    }
+}
+
+// Capture the monitors selected by `mode`
+fn capture_frame(
+    source: &dyn ScreenSource,
+    mode: &CaptureMode,
+) -> Result<CapturedFrame, Box<dyn std::error::Error>> {
+    let mut captures = Vec::new();
+    for info in monitors_for_mode(source, mode)? {
+        let image = source.capture_monitor(info.id)?;
+        captures.push((info, image));
+    }
+
+    let (image, scale_factor, monitors) = if captures.len() == 1 {
+        let (info, image) = captures.remove(0);
+        let scale_factor = info.scale_factor;
+        (image, scale_factor, vec![info])
+    } else {
+        let mut monitors = Vec::new();
+        let mut tiles = Vec::new();
+        for (info, image) in captures {
+            tiles.push(ScreenTile {
+                x: info.x,
+                y: info.y,
+                width: info.width,
+                height: info.height,
+                image,
+            });
+            monitors.push(info);
+        }
+        let stitched = stitch(&tiles).ok_or("No monitors to capture")?;
+        (stitched.image, stitched.scale_factor, monitors)
+    };
+
+    let layout = layout_for(monitors);
+
+    Ok(CapturedFrame {
+        image,
+        scale_factor,
+        layout,
+    })
+}
+
+/// Windows that can be captured, in the order the source reports them
+fn capturable_windows(source: &dyn ScreenSource) -> Result<Vec<WindowInfo>, Box<dyn std::error::Error>> {
+    Ok(source
+        .windows()?
+        .into_iter()
+        .filter(WindowInfo::is_capturable)
+        .collect())
+}
+
+/// Capture a single window by id
+fn capture_window_image(
+    source: &dyn ScreenSource,
+    window_id: u32,
+) -> Result<(WindowInfo, RgbaImage), Box<dyn std::error::Error>> {
+    let window = capturable_windows(source)?
+        .into_iter()
+        .find(|w| w.id == window_id)
+        .ok_or_else(|| format!("Window {} not found or not capturable", window_id))?;
+    let image = source.capture_window(window_id)?;
+    Ok((window, image))
+}
+
+/// Bounding box of the monitors
+fn layout_for(monitors: Vec<MonitorInfo>) -> CaptureLayout {
+    let min_x = monitors.iter().map(|m| m.x).min().unwrap_or(0);
+    let min_y = monitors.iter().map(|m| m.y).min().unwrap_or(0);
+    let max_x = monitors.iter().map(|m| m.x + m.width as i32).max().unwrap_or(0);
+    let max_y = monitors.iter().map(|m| m.y + m.height as i32).max().unwrap_or(0);
+
+    CaptureLayout {
+        x: min_x,
+        y: min_y,
+        width: (max_x - min_x) as u32,
+        height: (max_y - min_y) as u32,
+        monitors,
+    }
+}
+
+/// List the monitors that can be captured
+#[tauri::command]
+pub fn list_monitors() -> Result<Vec<MonitorInfo>, String> {
+    XcapSource.monitors().map_err(|e| e.to_string())
+}
+
+/// List the windows that can be captured, with their title and app name
+#[tauri::command]
+pub fn list_windows() -> Result<Vec<WindowInfo>, String> {
+    capturable_windows(&XcapSource).map_err(|e| e.to_string())
+}
+
+/// Geometry of the last capture, so the overlay can span its monitors
+#[tauri::command]
+pub async fn get_capture_layout(
+    screenshot_data: State<'_, ScreenshotData>,
+) -> Result<Option<CaptureLayout>, tauri::Error> {
+    let data = screenshot_data.lock().unwrap();
+    Ok(data.as_ref().map(|frame| frame.layout.clone()))
+}
+
+/// Check if there's an active session. If not, show OS notification.
+/// Returns true if session is active, false if not.
+pub async fn check_session_and_notify(app: &AppHandle) -> bool {
+    // Check for active session
+    if let Some(session_manager) = app.try_state::<SessionManagerState>() {
+        let manager = session_manager.lock().unwrap();
+        if manager.get_active_session().is_some() {
+            // Session is active
+            return true;
+        }
+    }
+
+    // No active session - show notification and open window
+    log::info!("No active session detected, showing notification and opening session modal");
+    
+    // Show notification
+    notify(
+        app,
+        "Active Session Required",
+        "Please start a session before taking screenshots",
+    );
+    
+    // Open or focus main window with session modal
+    if let Err(e) = open_or_focus_main_window_with_session_modal(app).await {
+        log::error!("Failed to open main window: {}", e);
+    }
+    
+    false
+}
+
+/// Open or focus the main window and show the session modal
+pub async fn open_or_focus_main_window_with_session_modal(app: &AppHandle) -> Result<(), tauri::Error> {
+    // Check if main window exists
+    if let Some(main_window) = app.get_webview_window("main") {
+        // Window exists - show and focus it
+        log::info!("Main window exists, focusing it");
+        main_window.show()?;
+        main_window.set_focus()?;
+    } else {
+        // Window doesn't exist - create it
+        log::info!("Main window doesn't exist, creating it");
+        use tauri::WebviewWindowBuilder;
+        use tauri::WebviewUrl;
+        
+        let window = WebviewWindowBuilder::new(
+            app,
+            "main",
+            WebviewUrl::default()
+        )
+        .title("plutodesk")
+        .inner_size(800.0, 600.0)
+        .resizable(true)
+        .build()?;
+        
+        window.show()?;
+        window.set_focus()?;
+    }
+    
+    // Emit event to open session modal
+    app.emit("open-session-modal", ())?;
+    
+    Ok(())
+}
+
+/// `Ctrl+Shift+A`, registered while `capture.all_monitors_shortcut` is on
+#[cfg(desktop)]
+pub(crate) fn all_monitors_shortcut() -> Shortcut {
+    Shortcut::new(Some(Modifiers::CONTROL | Modifiers::SHIFT), Code::KeyA)
+}
+
+/// Registers or unregisters [`all_monitors_shortcut`]; the shortcut handler
+/// set up in `run` takes care of presses
+#[cfg(desktop)]
+pub(crate) fn set_all_monitors_shortcut(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    let shortcut = all_monitors_shortcut();
+    let global_shortcut = app.global_shortcut();
+    if enabled == global_shortcut.is_registered(shortcut) {
+        return Ok(());
+    }
+
+    let result = if enabled {
+        global_shortcut.register(shortcut)
+    } else {
+        global_shortcut.unregister(shortcut)
+    };
+    result.map_err(|e| format!("Failed to update the all-monitors shortcut: {}", e))
+}
+
+// Initiate screenshot overlay interface.
+// `mode` overrides the capture mode from settings, e.g. for a dedicated shortcut.
+pub async fn take_screenshot(app: AppHandle, mode: Option<CaptureMode>) -> Result<(), tauri::Error> {
+    // Check if window exists and is actually usable (not just in registry)
+    if let Some(existing_window) = app.get_webview_window("screenshot_overlay") {
+        // Check if window is actually visible/usable
+        match existing_window.is_visible() {
+            Ok(true) => {
+                log::warn!("Screenshot overlay already open and visible, ignoring shortcut.");
+                return Ok(());
+            }
+            Ok(false) => {
+                // Window exists but is hidden - close it to clean up state
+                log::info!("Found hidden screenshot overlay window, closing it before creating new one.");
+                if let Err(e) = existing_window.close() {
+                    log::warn!("Failed to close existing window: {}", e);
+                } else {
+                    // Give it a moment to fully close
+                    let _ = tauri::async_runtime::spawn_blocking(|| {
+                        std::thread::sleep(std::time::Duration::from_millis(100));
+                    })
+                    .await;
+                }
+            }
+            Err(_) => {
+                // Window exists but is in invalid state - try to close it
+                log::warn!("Found screenshot overlay window in invalid state, attempting to close.");
+                if let Err(e) = existing_window.close() {
+                    log::warn!("Failed to close invalid window: {}", e);
+                } else {
+                    let _ = tauri::async_runtime::spawn_blocking(|| {
+                        std::thread::sleep(std::time::Duration::from_millis(100));
+                    })
+                    .await;
+                }
+            }
+        }
+    }
+
+    let mode = mode.unwrap_or_else(|| {
+        app.try_state::<SettingsState>()
+            .map(|settings| settings.lock().unwrap().capture.mode.clone())
+            .unwrap_or_default()
+    });
+
+    log::info!("Taking screenshot ({:?})...", mode);
+    let frame = capture_frame(&XcapSource, &mode).map_err(|e| {
+        log::error!("Failed to capture screenshot: {}", e);
+        tauri::Error::from(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Screenshot capture failed: {}", e),
+        ))
+    })?;
+
+    // The overlay loads the image from this URL instead of receiving it in the event
+    let payload = protocol::url(protocol::CAPTURE_SCHEME, "current");
+    let layout = frame.layout.clone();
+
+    // Store screenshot data in app state
+    if let Some(state) = app.try_state::<ScreenshotData>() {
+        let mut data = state.lock().unwrap();
+        *data = Some(frame);
+    } else {
+        log::error!("ScreenshotData state not found - state not initialized!");
+    }
+
+    let app_clone = app.clone();
+
+    // Store listener ID for cleanup
+    let listener_id = app.listen_any("screenshot_overlay_ready", {
+        let app_for_emit = app_clone.clone();
+        let payload_for_emit = payload.clone();
+        move |event| {
+            // Clone for unlisten call (needed after async spawn)
+            let app_for_unlisten = app_for_emit.clone();
+            let event_id = event.id();
+            
+            // Add a delay to ensure the frontend listener is registered
+            // The ready event is emitted immediately on mount, but the listener might not be ready yet
+            let app_for_emit_inner = app_for_emit.clone();
+            let payload_for_emit_inner = payload_for_emit.clone();
+            tauri::async_runtime::spawn(async move {
+                // Wait a bit for the frontend to set up the listener
+                let _ = tauri::async_runtime::spawn_blocking(|| {
+                    std::thread::sleep(std::time::Duration::from_millis(100));
+                })
+                .await;
+
+                // Check if window still exists before emitting
+                let window_exists = app_for_emit_inner
+                    .get_webview_window("screenshot_overlay")
+                    .is_some();
+
+                if !window_exists {
+                    log::warn!("Window no longer exists when trying to emit screenshot data");
+                    return;
+                }
+
+                match app_for_emit_inner.emit_to(
+                    "screenshot_overlay",
+                    "open_screenshot_overlay",
+                    payload_for_emit_inner.clone(),
+                ) {
+                    Ok(_) => {
+                        // Try to show the window after a short delay to allow frontend to process
+                        // This is a fallback in case the frontend doesn't call window.show()
+                        let app_for_show = app_for_emit_inner.clone();
+                        tauri::async_runtime::spawn(async move {
+                            // Use std::thread::sleep in async context via spawn_blocking
+                            let _ = tauri::async_runtime::spawn_blocking(|| {
+                                std::thread::sleep(std::time::Duration::from_millis(200));
+                            })
+                            .await;
+
+                            // Check if window still exists and is valid
+                            if let Some(window) = app_for_show.get_webview_window("screenshot_overlay") {
+                                // Verify window is still valid before showing
+                                match window.is_visible() {
+                                    Ok(_) => {
+                                        if let Err(e) = window.show() {
+                                            log::error!("Window.show() failed (fallback): {}", e);
+                                        }
+                                    }
+                                    Err(_) => {
+                                        log::warn!("Window is in invalid state, skipping show");
+                                    }
+                                }
+                            } else {
+                                log::warn!(
+                                    "Window 'screenshot_overlay' not found when trying to show (may have been closed)"
+                                );
+                            }
+                        });
+                    }
+                    Err(e) => log::error!("Failed to emit open_screenshot_overlay event: {}", e),
+                }
+            });
+
+            // Unlisten after spawning the async task
+            app_for_unlisten.unlisten(event_id);
+        }
+    });
+
+    // Use trailing slash for Next.js static export compatibility
+    // With trailingSlash: true, Next.js creates routes as directories with index.html
+    let overlay_url = "tauri/overlay/screenshot/";
+
+    let builder = tauri::WebviewWindowBuilder::new(
+        &app,
+        "screenshot_overlay",
+        tauri::WebviewUrl::App(overlay_url.into()),
+    )
+    .transparent(true)
+    .decorations(false)
+    .always_on_top(true)
+    .position(layout.x as f64, layout.y as f64)
+    .skip_taskbar(true)
+    .visible(false); // <-- Give time for frontend to process snapshot payload
+
+    // A single monitor is covered by going fullscreen on it; several monitors
+    // need a borderless window spanning all of them
+    let builder = if layout.monitors.len() == 1 {
+        builder.fullscreen(true)
+    } else {
+        builder.inner_size(layout.width as f64, layout.height as f64)
+    };
+
+    let webview_window = builder
+        .build()
+    .map_err(|e| {
+        log::error!("Failed to create screenshot overlay window: {}", e);
+        e
+    })?;
+
+    // Set up window close event handler to clean up listeners
+    let app_for_close = app.clone();
+    let listener_id_for_close = listener_id.clone();
+    webview_window.on_window_event(move |event| {
+        if let tauri::WindowEvent::CloseRequested { .. } = event {
+            log::info!("Screenshot overlay window close requested, cleaning up listeners");
+            // Clean up the ready event listener
+            app_for_close.unlisten(listener_id_for_close);
+        }
+    });
+
+    // Set up a fallback to show the window if frontend doesn't do it
+    // This handles the case where frontend JavaScript fails to execute
+    let app_for_fallback = app.clone();
+    tauri::async_runtime::spawn(async move {
+        // Wait for frontend to be ready and process the event
+        let _ = tauri::async_runtime::spawn_blocking(|| {
+            std::thread::sleep(std::time::Duration::from_millis(500));
+        })
+        .await;
+
+        // Check if window still exists and is valid before trying to show
+        if let Some(window) = app_for_fallback.get_webview_window("screenshot_overlay") {
+            // Verify window is still in a valid state
+            match window.is_visible() {
+                Ok(false) => {
+                    // Window exists and is hidden - safe to show
+                    log::warn!("Window is still hidden after 500ms, showing it now (fallback)");
+                    if let Err(e) = window.show() {
+                        log::error!("Window.show() failed (fallback): {}", e);
+                    }
+                }
+                Ok(true) => {
+                    // Window is already visible, nothing to do
+                    log::debug!("Window is already visible, skipping fallback show");
+                }
+                Err(e) => {
+                    // Window is in invalid state - don't try to show it
+                    log::warn!("Window is in invalid state (may have been closed), skipping fallback show: {}", e);
+                }
+            }
+        } else {
+            log::warn!("Window 'screenshot_overlay' not found for fallback show (may have been closed)");
+        }
+    });
+
+    Ok(())
+}
+
+/// Encodes an image (base64 or data URL) per `encoding` and puts it in the
+/// content-addressed image store. PNGs carry `metadata`, so the file still
+/// says what it is if it is copied out or the database is lost. Returns its
+/// path for `problem_images.path`.
+pub(crate) fn write_image_data_url_to_local_fs(
+    app: &AppHandle,
+    image_url: &str,
+    encoding: &EncodingSettings,
+    metadata: Option<&ImageMetadata>,
+) -> Result<StoredBlob, tauri::Error> {
+    let app_dir = app.path().app_data_dir()?;
+
+    // Decode image url
+    // Remove header if present (data:image/png;base64, ....)
+    let b64_string = image_url.split(',').next_back().unwrap_or(image_url);
+    let image_bytes = BASE64_STANDARD.decode(b64_string.trim()).map_err(|e| {
+        tauri::Error::from(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("Invalid base64 image data: {}", e),
+        ))
+    })?;
+
+    // Encode per the user's settings; PNG input needing no changes is written as-is
+    let encoded = if encode::is_passthrough(encoding)
+        && image::guess_format(&image_bytes).ok() == Some(image::ImageFormat::Png)
+    {
+        EncodedImage {
+            bytes: image_bytes,
+            extension: "png",
+        }
+    } else {
+        let image = image::load_from_memory(&image_bytes).map_err(|e| {
+            tauri::Error::from(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Failed to decode image: {}", e),
+            ))
+        })?;
+        encode::encode(image, encoding).map_err(|e| {
+            tauri::Error::from(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Failed to encode image: {}", e),
+            ))
+        })?
+    };
+
+    let bytes = match metadata.filter(|_| encoded.extension == "png") {
+        Some(metadata) => png_meta::embed(&encoded.bytes, metadata).map_err(|e| {
+            tauri::Error::from(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Failed to embed image metadata: {}", e),
+            ))
+        })?,
+        None => encoded.bytes,
+    };
+
+    let blob = storage::put_blob(&app_dir, &bytes, encoded.extension)?;
+    log::info!("Stored image as {}", blob.path);
+    Ok(blob)
+}
+
+/// Save an image as a new problem. `redactions`, in pixels of the image, are
+/// blacked out or blurred before anything is read from or written for it.
+#[tauri::command]
+pub async fn receive_screenshot_data(
+    app: AppHandle,
+    image_url: String,
+    folder_id: Option<String>,
+    course_id: Option<String>,
+    set_id: Option<String>,
+    redactions: Option<Vec<Redaction>>,
+) -> Result<SavedProblem, tauri::Error> {
+    let image_url = match redactions.filter(|r| !r.is_empty()) {
+        Some(redactions) => redact_image(image_url, redactions).await.map_err(|e| {
+            tauri::Error::from(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Failed to redact screenshot: {}", e),
+            ))
+        })?,
+        None => image_url,
+    };
+    save_problem_image(&app, image_url, None, folder_id, course_id, set_id).await
+}
+
+/// Crop the held capture to `selection` (logical pixels on the captured
+/// monitor) and save only that region as a new problem. `redactions` use the
+/// same coordinates and are burned in before the crop.
+#[tauri::command]
+pub async fn save_screenshot_selection(
+    app: AppHandle,
+    screenshot_data: State<'_, ScreenshotData>,
+    selection: SelectionRect,
+    folder_id: Option<String>,
+    course_id: Option<String>,
+    set_id: Option<String>,
+    redactions: Option<Vec<Redaction>>,
+) -> Result<SavedProblem, tauri::Error> {
+    let cropped = {
+        let data = screenshot_data.lock().unwrap();
+        let frame = data.as_ref().ok_or_else(|| {
+            tauri::Error::from(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "No screenshot has been captured",
+            ))
+        })?;
+        let image = match redactions.as_deref() {
+            Some(redactions) if !redactions.is_empty() => {
+                let mut image = frame.image.clone();
+                redact::apply(&mut image, redactions, frame.scale_factor);
+                Cow::Owned(image)
+            }
+            _ => Cow::Borrowed(&frame.image),
+        };
+        crop_selection(&image, &selection, frame.scale_factor).map_err(|e| {
+            tauri::Error::from(std::io::Error::new(std::io::ErrorKind::InvalidInput, e))
+        })?
+    };
+
+    let image_base64 = encode_png_base64(&cropped).map_err(|e| {
+        tauri::Error::from(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Failed to encode screenshot: {}", e),
+        ))
+    })?;
+
+    let saved = save_problem_image(&app, image_base64, None, folder_id, course_id, set_id).await?;
+
+    // The frame is no longer needed once the selection is saved
+    *screenshot_data.lock().unwrap() = None;
+
+    Ok(saved)
+}
+
+/// Capture one window, without the overlay, and save it as a problem.
+/// The window title and app name are recorded on the problem.
+#[tauri::command]
+pub async fn capture_window(
+    app: AppHandle,
+    window_id: u32,
+    folder_id: Option<String>,
+    course_id: Option<String>,
+    set_id: Option<String>,
+) -> Result<SavedProblem, tauri::Error> {
+    let (window, image) = capture_window_image(&XcapSource, window_id).map_err(|e| {
+        log::error!("Failed to capture window {}: {}", window_id, e);
+        tauri::Error::from(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Window capture failed: {}", e),
+        ))
+    })?;
+
+    let image_base64 = encode_png_base64(&image).map_err(|e| {
+        tauri::Error::from(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Failed to encode screenshot: {}", e),
+        ))
+    })?;
+
+    save_problem_image(&app, image_base64, Some(&window), folder_id, course_id, set_id).await
+}
+
+/// Names, writes and records a problem image for the given set, or for the
+/// active session when no ids are passed. `source_window` is the window the
+/// image was captured from, if it was a window capture.
+async fn save_problem_image(
+    app: &AppHandle,
+    image_url: String,
+    source_window: Option<&WindowInfo>,
+    folder_id: Option<String>,
+    course_id: Option<String>,
+    set_id: Option<String>,
+) -> Result<SavedProblem, tauri::Error> {
+    use crate::session::SessionManagerState;
+    use uuid::Uuid;
+
+    let db = app.state::<Db>();
+    
+    // Determine folder/course/set IDs
+    let (folder_uuid, course_uuid, set_uuid) = if let (Some(f), Some(c), Some(s)) = (folder_id, course_id, set_id) {
+        // IDs provided directly (inline session selection)
+        let folder_uuid = Uuid::parse_str(&f).map_err(|e| {
+            tauri::Error::from(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Invalid folder_id: {}", e),
+            ))
+        })?;
+        let course_uuid = Uuid::parse_str(&c).map_err(|e| {
+            tauri::Error::from(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Invalid course_id: {}", e),
+            ))
+        })?;
+        let set_uuid = Uuid::parse_str(&s).map_err(|e| {
+            tauri::Error::from(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Invalid set_id: {}", e),
+            ))
+        })?;
+        (folder_uuid, course_uuid, set_uuid)
+    } else {
+        // Use active session
+        let session_manager = app.state::<SessionManagerState>();
+        let manager = session_manager.lock().unwrap();
+        let session = manager.get_active_session().ok_or_else(|| {
+            tauri::Error::from(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "No active session. Please start a session before taking screenshots.",
+            ))
+        })?;
+        (session.folder_id, session.course_id, session.set_id)
+    };
+
+    // Fetch names for filesystem path
+    let folder = services::get_folder_by_id(db.connection(), folder_uuid)
+        .await
+        .map_err(|e| {
+            tauri::Error::from(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Database error: {}", e),
+            ))
+        })?
+        .ok_or_else(|| {
+            tauri::Error::from(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("Folder with id {} not found", folder_uuid),
+            ))
+        })?;
+
+    let course = services::get_course_by_id(db.connection(), course_uuid)
+        .await
+        .map_err(|e| {
+            tauri::Error::from(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Database error: {}", e),
+            ))
+        })?
+        .ok_or_else(|| {
+            tauri::Error::from(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("Course with id {} not found", course_uuid),
+            ))
+        })?;
+
+    let set = services::get_set_by_id(db.connection(), set_uuid)
+        .await
+        .map_err(|e| {
+            tauri::Error::from(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Database error: {}", e),
+            ))
+        })?
+        .ok_or_else(|| {
+            tauri::Error::from(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("Set with id {} not found", set_uuid),
+            ))
+        })?;
+
+    let problem_count = services::count_problems_by_set(db.connection(), set_uuid)
+        .await
+        .map_err(|e| {
+            tauri::Error::from(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Database error: {}", e),
+            ))
+        })?;
+
+    // Pick the naming strategy for this set (or the global default)
+    let namer = {
+        let settings = app.state::<SettingsState>();
+        let settings = settings.lock().unwrap();
+        namer_for(
+            settings.naming.strategy_for_set(set_uuid),
+            ocr::models_dir(&app.path().app_data_dir()?),
+            settings.naming.llm.clone(),
+        )
+    };
+    let suggestion = namer
+        .name(&NamingContext {
+            folder_name: &folder.name,
+            course_name: &course.name,
+            set_name: &set.name,
+            image_base64: &image_url,
+            problem_count,
+            now: chrono::Utc::now(),
+        })
+        .await;
+
+    // Hash the captured pixels before any lossy encoding or resizing
+    let image_hash = {
+        let image_url = image_url.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            ocr::decode_image(&image_url).map(|image| phash::to_db(phash::dhash(&image)))
+        })
+        .await
+        .map_err(|e| tauri::Error::from(std::io::Error::other(e.to_string())))?
+        .map_err(|e| log::warn!("Failed to hash capture: {}", e))
+        .ok()
+    };
+
+    let dto = ScreenshotDto {
+        problem_id: Uuid::new_v4(),
+        folder_name: folder.name,
+        course_name: course.name,
+        set_name: set.name,
+        problem_name: suggestion.name,
+        base64_data: image_url,
+        ocr_text: suggestion.ocr_text,
+        source_window_title: source_window.map(|w| w.title.clone()),
+        source_app_name: source_window.map(|w| w.app_name.clone()),
+        image_hash,
+    };
+
+    let (encoding, steps) = {
+        let settings = app.state::<SettingsState>();
+        let settings = settings.lock().unwrap();
+        (settings.encoding.clone(), settings.cleanup.steps_for_course(course_uuid))
+    };
+
+    let metadata = ImageMetadata {
+        problem_id: dto.problem_id,
+        title: dto.problem_name.clone(),
+        role: ImageRole::Question.to_value(),
+        folder: dto.folder_name.clone(),
+        course: dto.course_name.clone(),
+        set: dto.set_name.clone(),
+        captured_at: chrono::Utc::now(),
+    };
+
+    // Write image to the image store. With cleanup on for the course, the
+    // capture as taken is kept losslessly next to the processed image so it
+    // can be undone; only the processed image is the problem's, so only it
+    // carries the metadata.
+    let (saved, original) = if steps.is_empty() {
+        let saved =
+            write_image_data_url_to_local_fs(app, &dto.base64_data, &encoding, Some(&metadata))?;
+        (saved, None)
+    } else {
+        let cleaned = clean_up_image(dto.base64_data.clone(), steps)
+            .await
+            .map_err(|e| {
+                tauri::Error::from(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("Failed to clean up image: {}", e),
+                ))
+            })?;
+        let original = write_image_data_url_to_local_fs(
+            app,
+            &dto.base64_data,
+            &EncodingSettings::lossless(),
+            None,
+        )?;
+        let saved = write_image_data_url_to_local_fs(app, &cleaned, &encoding, Some(&metadata))?;
+        (saved, Some(original.path))
+    };
+
+    // Save to database
+    let problem = services::save_screenshot_to_db(db.connection(), dto, saved.path.clone())
+        .await
+        .map_err(|e| {
+            tauri::Error::from(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Database error: {}", e),
+            ))
+        })?;
+
+    // Without its original the cleanup just can't be undone, so the save stands
+    if let Some(original) = original {
+        let recorded = match services::get_primary_image(db.connection(), problem.id).await {
+            Ok(Some(image)) => services::set_problem_image_files(
+                db.connection(),
+                image.id,
+                image.path,
+                Some(original),
+            )
+            .await
+            .map(|_| ()),
+            Ok(None) => Ok(()),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = recorded {
+            log::warn!("Failed to record original image of problem {}: {}", problem.id, e);
+        }
+    }
+
+    // The sidecar follows later renames, which the file's own metadata doesn't
+    refresh_sidecars_in_scope(app, &db, ImageScope::Problem(problem.id)).await;
+
+    // Thumbnails are a cache; the save has succeeded either way
+    let app_data_dir = app.path().app_data_dir()?;
+    let problem_id = problem.id;
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = ensure_thumbnails(app_data_dir, problem_id, saved.path, true).await {
+            log::warn!("Failed to generate thumbnails for problem {}: {}", problem_id, e);
+        }
+    });
+
+    // A failed duplicate check shouldn't fail a save that already happened
+    let duplicates = match image_hash {
+        Some(hash) => services::find_near_duplicates(
+            db.connection(),
+            set_uuid,
+            hash,
+            phash::DUPLICATE_THRESHOLD,
+            Some(problem.id),
+        )
+        .await
+        .unwrap_or_else(|e| {
+            log::warn!("Duplicate check failed for problem {}: {}", problem.id, e);
+            Vec::new()
+        }),
+        None => Vec::new(),
+    };
+
+    Ok(SavedProblem {
+        problem_id: problem.id,
+        title: problem.title,
+        duplicates,
+    })
+}
+
+#[tauri::command]
+pub fn close_screenshot_overlay(app: AppHandle) -> Result<(), tauri::Error> {
+    if let Some(window) = app.get_webview_window("screenshot_overlay") {
+        window.close().unwrap();
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+#[path = "screenshot_window_lifecycle_test.rs"]
+mod screenshot_window_lifecycle_test;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::{engine::general_purpose, Engine};
+    use image::Rgba;
+    use source::tests::FakeScreenSource;
+
+    fn monitor(id: u32, x: i32, y: i32, width: u32, height: u32) -> MonitorInfo {
+        MonitorInfo {
+            id,
+            name: format!("Monitor {}", id),
+            x,
+            y,
+            width,
+            height,
+            scale_factor: 1.0,
+            is_primary: id == 1,
+        }
+    }
+
+    #[test]
+    fn test_layout_for_spans_all_monitors() {
+        let layout = layout_for(vec![
+            monitor(1, 0, 0, 1920, 1080),
+            monitor(2, -1280, 200, 1280, 1024),
+        ]);
+        assert_eq!((layout.x, layout.y), (-1280, 0));
+        assert_eq!((layout.width, layout.height), (3200, 1224));
+        assert_eq!(layout.monitors.len(), 2);
+
+        let single = layout_for(vec![monitor(1, 1920, 0, 2560, 1440)]);
+        assert_eq!((single.x, single.y, single.width, single.height), (1920, 0, 2560, 1440));
+    }
+
+    fn two_monitor_source() -> FakeScreenSource {
+        FakeScreenSource {
+            cursor_monitor: 2,
+            ..Default::default()
+        }
+        .with_monitor(1, 0, 0, 100, 50, Rgba([255, 0, 0, 255]))
+        .with_monitor(2, 100, 0, 60, 50, Rgba([0, 0, 255, 255]))
+    }
+
+    #[test]
+    fn test_capture_frame_modes() {
+        let source = two_monitor_source();
+
+        let frame = capture_frame(&source, &CaptureMode::MonitorUnderMouse).unwrap();
+        assert_eq!(frame.image.dimensions(), (60, 50));
+        assert_eq!(frame.layout.monitors[0].id, 2);
+
+        let frame = capture_frame(&source, &CaptureMode::Monitor { id: 1 }).unwrap();
+        assert_eq!(frame.image.dimensions(), (100, 50));
+        assert_eq!((frame.layout.x, frame.layout.width), (0, 100));
+
+        let frame = capture_frame(&source, &CaptureMode::AllMonitors).unwrap();
+        assert_eq!(frame.image.dimensions(), (160, 50));
+        assert_eq!(frame.layout.monitors.len(), 2);
+        assert_eq!(*frame.image.get_pixel(120, 10), Rgba([0, 0, 255, 255]));
+
+        assert!(capture_frame(&source, &CaptureMode::Monitor { id: 9 }).is_err());
+    }
+
+    #[test]
+    fn test_capture_window_image() {
+        let source = FakeScreenSource::default()
+            .with_window(10, "Calculus.pdf", "Preview", false)
+            .with_window(11, "Minimized notes", "Notes", true);
+
+        let windows = capturable_windows(&source).unwrap();
+        assert_eq!(windows.len(), 1);
+        assert_eq!(windows[0].title, "Calculus.pdf");
+
+        let (window, image) = capture_window_image(&source, 10).unwrap();
+        assert_eq!(window.app_name, "Preview");
+        assert_eq!(image.dimensions(), (40, 30));
+
+        // Minimized and unknown windows are refused
+        assert!(capture_window_image(&source, 11).is_err());
+        assert!(capture_window_image(&source, 99).is_err());
+    }
+
+    #[test]
+    fn test_base64_decoding_with_and_without_prefix() {
+        let test_bytes = b"test image data";
+        let base64_str = general_purpose::STANDARD.encode(test_bytes);
+
+        // Test with prefix
+        let with_prefix = format!("data:image/png;base64,{}", base64_str);
+        let b64_part = with_prefix.split(',').next_back().unwrap();
+        let decoded = general_purpose::STANDARD.decode(b64_part).unwrap();
+        assert_eq!(decoded, test_bytes);
+
+        // Test without prefix
+        let b64_part = base64_str.split(',').next_back().unwrap();
+        let decoded = general_purpose::STANDARD.decode(b64_part).unwrap();
+        assert_eq!(decoded, test_bytes);
+    }
+}
@@ -0,0 +1,239 @@
+//! Where captured pixels come from.
+//!
+//! [`XcapSource`] talks to the OS through xcap. Capture logic only sees the
+//! [`ScreenSource`] trait, so tests can run it against a fake.
+
+use device_query::{DeviceQuery, DeviceState, MouseState};
+use image::RgbaImage;
+use serde::Serialize;
+use std::error::Error;
+use xcap::{Monitor, Window, XCapResult};
+
+/// A monitor as reported by xcap; position and size are in desktop coordinates
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MonitorInfo {
+    pub id: u32,
+    pub name: String,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub scale_factor: f64,
+    pub is_primary: bool,
+}
+
+impl MonitorInfo {
+    fn from_monitor(monitor: &Monitor) -> XCapResult<Self> {
+        Ok(Self {
+            id: monitor.id()?,
+            name: monitor.name()?,
+            x: monitor.x()?,
+            y: monitor.y()?,
+            width: monitor.width()?,
+            height: monitor.height()?,
+            scale_factor: monitor.scale_factor()? as f64,
+            is_primary: monitor.is_primary()?,
+        })
+    }
+}
+
+/// A top-level window as reported by xcap
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WindowInfo {
+    pub id: u32,
+    pub title: String,
+    pub app_name: String,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub is_minimized: bool,
+}
+
+impl WindowInfo {
+    fn from_window(window: &Window) -> XCapResult<Self> {
+        Ok(Self {
+            id: window.id()?,
+            title: window.title()?,
+            app_name: window.app_name()?,
+            x: window.x()?,
+            y: window.y()?,
+            width: window.width()?,
+            height: window.height()?,
+            is_minimized: window.is_minimized()?,
+        })
+    }
+
+    /// Minimized and zero-sized windows have nothing to capture
+    pub fn is_capturable(&self) -> bool {
+        !self.is_minimized && self.width > 0 && self.height > 0
+    }
+}
+
+/// Enumerates and captures monitors and windows
+pub(crate) trait ScreenSource {
+    fn monitors(&self) -> Result<Vec<MonitorInfo>, Box<dyn Error>>;
+    fn monitor_under_cursor(&self) -> Result<MonitorInfo, Box<dyn Error>>;
+    fn capture_monitor(&self, id: u32) -> Result<RgbaImage, Box<dyn Error>>;
+    fn windows(&self) -> Result<Vec<WindowInfo>, Box<dyn Error>>;
+    fn capture_window(&self, id: u32) -> Result<RgbaImage, Box<dyn Error>>;
+}
+
+/// The real screen, via xcap
+pub(crate) struct XcapSource;
+
+impl XcapSource {
+    fn find_monitor(id: u32) -> Result<Monitor, Box<dyn Error>> {
+        for monitor in Monitor::all()? {
+            if monitor.id()? == id {
+                return Ok(monitor);
+            }
+        }
+        Err(format!("Monitor {} not found", id).into())
+    }
+
+    fn find_window(id: u32) -> Result<Window, Box<dyn Error>> {
+        for window in Window::all()? {
+            if window.id()? == id {
+                return Ok(window);
+            }
+        }
+        Err(format!("Window {} not found", id).into())
+    }
+}
+
+impl ScreenSource for XcapSource {
+    fn monitors(&self) -> Result<Vec<MonitorInfo>, Box<dyn Error>> {
+        Monitor::all()?
+            .iter()
+            .map(|m| MonitorInfo::from_monitor(m).map_err(Into::into))
+            .collect()
+    }
+
+    fn monitor_under_cursor(&self) -> Result<MonitorInfo, Box<dyn Error>> {
+        let device_state = DeviceState::new();
+        let mouse: MouseState = device_state.get_mouse();
+        let (x, y) = (mouse.coords.0, mouse.coords.1);
+
+        Ok(MonitorInfo::from_monitor(&Monitor::from_point(x, y)?)?)
+    }
+
+    fn capture_monitor(&self, id: u32) -> Result<RgbaImage, Box<dyn Error>> {
+        // Convert xcap image to image::ImageBuffer
+        Ok(Self::find_monitor(id)?.capture_image()?.into())
+    }
+
+    fn windows(&self) -> Result<Vec<WindowInfo>, Box<dyn Error>> {
+        // Some windows refuse to report their properties; skip them rather than fail the list
+        Ok(Window::all()?
+            .iter()
+            .filter_map(|w| match WindowInfo::from_window(w) {
+                Ok(info) => Some(info),
+                Err(e) => {
+                    log::debug!("Skipping window: {}", e);
+                    None
+                }
+            })
+            .collect())
+    }
+
+    fn capture_window(&self, id: u32) -> Result<RgbaImage, Box<dyn Error>> {
+        Ok(Self::find_window(id)?.capture_image()?.into())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use image::Rgba;
+
+    /// In-memory screen: solid-colour monitors and windows
+    #[derive(Default)]
+    pub(crate) struct FakeScreenSource {
+        pub monitors: Vec<(MonitorInfo, RgbaImage)>,
+        pub windows: Vec<(WindowInfo, RgbaImage)>,
+        /// Id of the monitor the cursor is on
+        pub cursor_monitor: u32,
+    }
+
+    impl FakeScreenSource {
+        pub fn with_monitor(mut self, id: u32, x: i32, y: i32, width: u32, height: u32, color: Rgba<u8>) -> Self {
+            let info = MonitorInfo {
+                id,
+                name: format!("Monitor {}", id),
+                x,
+                y,
+                width,
+                height,
+                scale_factor: 1.0,
+                is_primary: self.monitors.is_empty(),
+            };
+            self.monitors.push((info, RgbaImage::from_pixel(width, height, color)));
+            self
+        }
+
+        pub fn with_window(mut self, id: u32, title: &str, app_name: &str, is_minimized: bool) -> Self {
+            let info = WindowInfo {
+                id,
+                title: title.to_string(),
+                app_name: app_name.to_string(),
+                x: 0,
+                y: 0,
+                width: 40,
+                height: 30,
+                is_minimized,
+            };
+            self.windows
+                .push((info, RgbaImage::from_pixel(40, 30, Rgba([0, 255, 0, 255]))));
+            self
+        }
+    }
+
+    impl ScreenSource for FakeScreenSource {
+        fn monitors(&self) -> Result<Vec<MonitorInfo>, Box<dyn Error>> {
+            Ok(self.monitors.iter().map(|(info, _)| info.clone()).collect())
+        }
+
+        fn monitor_under_cursor(&self) -> Result<MonitorInfo, Box<dyn Error>> {
+            self.monitors
+                .iter()
+                .find(|(info, _)| info.id == self.cursor_monitor)
+                .map(|(info, _)| info.clone())
+                .ok_or_else(|| "No monitor under cursor".into())
+        }
+
+        fn capture_monitor(&self, id: u32) -> Result<RgbaImage, Box<dyn Error>> {
+            self.monitors
+                .iter()
+                .find(|(info, _)| info.id == id)
+                .map(|(_, image)| image.clone())
+                .ok_or_else(|| format!("Monitor {} not found", id).into())
+        }
+
+        fn windows(&self) -> Result<Vec<WindowInfo>, Box<dyn Error>> {
+            Ok(self.windows.iter().map(|(info, _)| info.clone()).collect())
+        }
+
+        fn capture_window(&self, id: u32) -> Result<RgbaImage, Box<dyn Error>> {
+            self.windows
+                .iter()
+                .find(|(info, _)| info.id == id)
+                .map(|(_, image)| image.clone())
+                .ok_or_else(|| format!("Window {} not found", id).into())
+        }
+    }
+
+    #[test]
+    fn test_is_capturable() {
+        let source = FakeScreenSource::default()
+            .with_window(1, "Calculus.pdf", "Preview", false)
+            .with_window(2, "Hidden", "Notes", true);
+        let windows = source.windows().unwrap();
+        assert!(windows[0].is_capturable());
+        assert!(!windows[1].is_capturable());
+
+        let mut empty = windows[0].clone();
+        empty.width = 0;
+        assert!(!empty.is_capturable());
+    }
+}
@@ -27,11 +27,16 @@ mod integration_tests {
 
         // Simulate screenshot DTO
         let dto = ScreenshotDto {
+            problem_id: uuid::Uuid::new_v4(),
             folder_name: "Computer Science".to_string(),
             course_name: "Data Structures & Algorithms".to_string(),
             set_name: "Binary Trees".to_string(),
             problem_name: "Lowest Common Ancestor".to_string(),
             base64_data: "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg==".to_string(),
+            ocr_text: None,
+            source_window_title: None,
+            source_app_name: None,
+            image_hash: None,
         };
 
         let image_path = "Computer_Science/Data_Structures_&_Algorithms/Binary_Trees/Lowest_Common_Ancestor.png".to_string();
@@ -43,7 +48,10 @@ mod integration_tests {
 
         // Verify problem was created with correct data
         assert_eq!(problem.title, "Lowest Common Ancestor");
-        assert_eq!(problem.image_path, Some(image_path));
+        let images = crate::db::services::get_problem_images(&db, problem.id)
+            .await
+            .expect("Failed to get images");
+        assert_eq!(images[0].path, image_path);
         assert_eq!(problem.attempt_count, 0);
         assert_eq!(problem.success_rate, 0.0);
     }
@@ -54,11 +62,16 @@ mod integration_tests {
 
         // First screenshot
         let dto1 = ScreenshotDto {
+            problem_id: uuid::Uuid::new_v4(),
             folder_name: "Math".to_string(),
             course_name: "Calculus".to_string(),
             set_name: "Derivatives".to_string(),
             problem_name: "Chain Rule".to_string(),
             base64_data: "test1".to_string(),
+            ocr_text: None,
+            source_window_title: None,
+            source_app_name: None,
+            image_hash: None,
         };
 
         let path1 = "Math/Calculus/Derivatives/Chain_Rule.png".to_string();
@@ -66,11 +79,16 @@ mod integration_tests {
 
         // Second screenshot in same hierarchy
         let dto2 = ScreenshotDto {
+            problem_id: uuid::Uuid::new_v4(),
             folder_name: "Math".to_string(),
             course_name: "Calculus".to_string(),
             set_name: "Derivatives".to_string(),
             problem_name: "Product Rule".to_string(),
             base64_data: "test2".to_string(),
+            ocr_text: None,
+            source_window_title: None,
+            source_app_name: None,
+            image_hash: None,
         };
 
         let path2 = "Math/Calculus/Derivatives/Product_Rule.png".to_string();
@@ -2,7 +2,7 @@
 mod screenshot_session_integration_tests {
     use crate::db::services;
     use crate::dtos::screenshot::ScreenshotDto;
-    use crate::session::SessionManager;
+    use crate::session::{SessionManager, SessionState};
     use migration::{Migrator, MigratorTrait};
     use sea_orm::Database;
 
@@ -18,6 +18,22 @@ mod screenshot_session_integration_tests {
         db
     }
 
+    /// Saves a session the way the session commands do
+    async fn create_session(
+        db: &sea_orm::DatabaseConnection,
+        name: &str,
+        folder_id: uuid::Uuid,
+        course_id: uuid::Uuid,
+        set_id: uuid::Uuid,
+        start: bool,
+    ) -> SessionState {
+        let session = SessionState::new(name.to_string(), folder_id, course_id, set_id);
+        services::create_session(db, session.into(), start)
+            .await
+            .expect("Failed to create session")
+            .into()
+    }
+
     #[tokio::test]
     async fn test_screenshot_save_with_active_session() {
         let db = setup_test_db().await;
@@ -42,26 +58,33 @@ mod screenshot_session_integration_tests {
                 .expect("Failed to create set");
 
         // Create session
-        let mut session_manager = SessionManager::new();
-        let session = session_manager.create_session(
-            "Test Session".to_string(),
+        let session = create_session(
+            &db,
+            "Test Session",
             folder.id,
             course.id,
             set.id,
             true,
-        );
+        )
+        .await;
 
         // Verify session is active
+        let session_manager = SessionManager::load_from_db(&db).await.unwrap();
         assert!(session_manager.get_active_session().is_some());
         assert_eq!(session_manager.get_active_session().unwrap().id, session.id);
 
         // Simulate screenshot save using session context
         let dto = ScreenshotDto {
+            problem_id: uuid::Uuid::new_v4(),
             folder_name: "Computer Science".to_string(),
             course_name: "Algorithms".to_string(),
             set_name: "Dynamic Programming".to_string(),
             problem_name: "Knapsack Problem".to_string(),
             base64_data: "test_base64_data".to_string(),
+            ocr_text: None,
+            source_window_title: None,
+            source_app_name: None,
+            image_hash: None,
         };
 
         let image_path =
@@ -74,7 +97,10 @@ mod screenshot_session_integration_tests {
         // Verify problem is saved to correct set
         assert_eq!(problem.set_id, set.id);
         assert_eq!(problem.title, "Knapsack Problem");
-        assert_eq!(problem.image_path, Some(image_path));
+        let images = services::get_problem_images(&db, problem.id)
+            .await
+            .expect("Failed to get images");
+        assert_eq!(images[0].path, image_path);
     }
 
     #[tokio::test]
@@ -99,23 +125,29 @@ mod screenshot_session_integration_tests {
             .await
             .expect("Failed to create set");
 
-        let mut session_manager = SessionManager::new();
-        session_manager.create_session(
-            "Math Session".to_string(),
+        create_session(
+            &db,
+            "Math Session",
             folder.id,
             course.id,
             set.id,
             true,
-        );
+        )
+        .await;
 
         // Take multiple screenshots in the same session
         for i in 1..=3 {
             let dto = ScreenshotDto {
+                problem_id: uuid::Uuid::new_v4(),
                 folder_name: "Mathematics".to_string(),
                 course_name: "Calculus".to_string(),
                 set_name: "Derivatives".to_string(),
                 problem_name: format!("Problem {}", i),
                 base64_data: format!("test_base64_data_{}", i),
+                ocr_text: None,
+                source_window_title: None,
+                source_app_name: None,
+                image_hash: None,
             };
 
             let image_path = format!("Mathematics/Calculus/Derivatives/Problem_{}.png", i);
@@ -170,30 +202,38 @@ mod screenshot_session_integration_tests {
             .expect("Failed to create set");
 
         // Create two sessions
-        let mut session_manager = SessionManager::new();
-        let _session1 = session_manager.create_session(
-            "CS Session".to_string(),
+        let _session1 = create_session(
+            &db,
+            "CS Session",
             folder1.id,
             course1.id,
             set1.id,
             true,
-        );
+        )
+        .await;
 
-        let session2 = session_manager.create_session(
-            "Math Session".to_string(),
+        let session2 = create_session(
+            &db,
+            "Math Session",
             folder2.id,
             course2.id,
             set2.id,
             false,
-        );
+        )
+        .await;
 
         // Take screenshot in session1
         let dto1 = ScreenshotDto {
+            problem_id: uuid::Uuid::new_v4(),
             folder_name: "Computer Science".to_string(),
             course_name: "Algorithms".to_string(),
             set_name: "Sorting".to_string(),
             problem_name: "QuickSort".to_string(),
             base64_data: "test_data_1".to_string(),
+            ocr_text: None,
+            source_window_title: None,
+            source_app_name: None,
+            image_hash: None,
         };
 
         let problem1 = services::save_screenshot_to_db(&db, dto1, "path1.png".to_string())
@@ -203,15 +243,20 @@ mod screenshot_session_integration_tests {
         assert_eq!(problem1.set_id, set1.id);
 
         // Switch to session2
-        session_manager.start_session(session2.id).unwrap();
+        services::start_session(&db, session2.id).await.unwrap();
 
         // Take screenshot in session2
         let dto2 = ScreenshotDto {
+            problem_id: uuid::Uuid::new_v4(),
             folder_name: "Mathematics".to_string(),
             course_name: "Calculus".to_string(),
             set_name: "Integrals".to_string(),
             problem_name: "Integration by Parts".to_string(),
             base64_data: "test_data_2".to_string(),
+            ocr_text: None,
+            source_window_title: None,
+            source_app_name: None,
+            image_hash: None,
         };
 
         let problem2 = services::save_screenshot_to_db(&db, dto2, "path2.png".to_string())
@@ -279,11 +324,16 @@ mod screenshot_session_integration_tests {
 
         // Simulate screenshot save with inline IDs (not using active session)
         let dto = ScreenshotDto {
+            problem_id: uuid::Uuid::new_v4(),
             folder_name: folder.name.clone(),
             course_name: course.name.clone(),
             set_name: set.name.clone(),
             problem_name: "Newton's Laws".to_string(),
             base64_data: "test_data".to_string(),
+            ocr_text: None,
+            source_window_title: None,
+            source_app_name: None,
+            image_hash: None,
         };
 
         let image_path = "Physics/Mechanics/Dynamics/Newtons_Laws.png".to_string();
@@ -319,24 +369,31 @@ mod screenshot_session_integration_tests {
             .expect("Failed to create set");
 
         // Create and activate session
-        let mut session_manager = SessionManager::new();
-        let _session = session_manager.create_session(
-            "Chemistry Session".to_string(),
+        let _session = create_session(
+            &db,
+            "Chemistry Session",
             folder.id,
             course.id,
             set.id,
             true, // Start immediately
-        );
+        )
+        .await;
 
+        let session_manager = SessionManager::load_from_db(&db).await.unwrap();
         let active_session = session_manager.get_active_session().unwrap();
 
         // Simulate screenshot save using session context
         let dto = ScreenshotDto {
+            problem_id: uuid::Uuid::new_v4(),
             folder_name: folder.name.clone(),
             course_name: course.name.clone(),
             set_name: set.name.clone(),
             problem_name: "Substitution Reaction".to_string(),
             base64_data: "test_data".to_string(),
+            ocr_text: None,
+            source_window_title: None,
+            source_app_name: None,
+            image_hash: None,
         };
 
         let image_path = "Chemistry/Organic_Chemistry/Reactions/Substitution_Reaction.png".to_string();
@@ -414,19 +471,31 @@ mod screenshot_session_integration_tests {
         assert!(!has_active_session, "Should return false when no session");
 
         // Test 2: With session - should return true
-        let mut session_manager = SessionManager::new();
-        let folder_id = uuid::Uuid::new_v4();
-        let course_id = uuid::Uuid::new_v4();
-        let set_id = uuid::Uuid::new_v4();
-
-        session_manager.create_session(
-            "Test Session".to_string(),
-            folder_id,
-            course_id,
-            set_id,
+        let db = setup_test_db().await;
+        let user_id = services::get_or_create_default_user(&db)
+            .await
+            .expect("Failed to create user");
+        let folder = services::find_or_create_folder(&db, user_id, "Test Folder".to_string())
+            .await
+            .expect("Failed to create folder");
+        let course = services::find_or_create_course(&db, folder.id, "Test Course".to_string())
+            .await
+            .expect("Failed to create course");
+        let set = services::find_or_create_set(&db, course.id, "Test Set".to_string())
+            .await
+            .expect("Failed to create set");
+
+        create_session(
+            &db,
+            "Test Session",
+            folder.id,
+            course.id,
+            set.id,
             true, // Start immediately
-        );
+        )
+        .await;
 
+        let session_manager = SessionManager::load_from_db(&db).await.unwrap();
         let has_active_session = session_manager.get_active_session().is_some();
         assert!(has_active_session, "Should return true when session active");
     }
@@ -452,23 +521,29 @@ mod screenshot_session_integration_tests {
             .await
             .expect("Failed to create set");
 
-        let mut session_manager = SessionManager::new();
-        session_manager.create_session(
-            "Biology Session".to_string(),
+        create_session(
+            &db,
+            "Biology Session",
             folder.id,
             course.id,
             set.id,
             true,
-        );
+        )
+        .await;
 
         // Save multiple screenshots in the same session
         for i in 1..=5 {
             let dto = ScreenshotDto {
+                problem_id: uuid::Uuid::new_v4(),
                 folder_name: folder.name.clone(),
                 course_name: course.name.clone(),
                 set_name: set.name.clone(),
                 problem_name: format!("Phase {}", i),
                 base64_data: format!("test_data_{}", i),
+                ocr_text: None,
+                source_window_title: None,
+                source_app_name: None,
+                image_hash: None,
             };
 
             let image_path = format!("Biology/Cell_Biology/Mitosis/Phase_{}.png", i);
@@ -523,30 +598,38 @@ mod screenshot_session_integration_tests {
             .expect("Failed to create set");
 
         // Create two sessions
-        let mut session_manager = SessionManager::new();
-        let _session1 = session_manager.create_session(
-            "Math Session".to_string(),
+        let _session1 = create_session(
+            &db,
+            "Math Session",
             folder1.id,
             course1.id,
             set1.id,
             true, // Start immediately
-        );
+        )
+        .await;
 
-        let session2 = session_manager.create_session(
-            "Physics Session".to_string(),
+        let session2 = create_session(
+            &db,
+            "Physics Session",
             folder2.id,
             course2.id,
             set2.id,
             false,
-        );
+        )
+        .await;
 
         // Take screenshot in session 1
         let dto1 = ScreenshotDto {
+            problem_id: uuid::Uuid::new_v4(),
             folder_name: folder1.name.clone(),
             course_name: course1.name.clone(),
             set_name: set1.name.clone(),
             problem_name: "Matrix Problem".to_string(),
             base64_data: "test_data_1".to_string(),
+            ocr_text: None,
+            source_window_title: None,
+            source_app_name: None,
+            image_hash: None,
         };
 
         let problem1 = services::save_screenshot_to_db(&db, dto1, "path1.png".to_string())
@@ -556,18 +639,23 @@ mod screenshot_session_integration_tests {
         assert_eq!(problem1.set_id, set1.id);
 
         // Switch to session 2
-        session_manager.end_session();
-        session_manager
-            .start_session(session2.id)
+        services::end_session(&db).await.unwrap();
+        services::start_session(&db, session2.id)
+            .await
             .expect("Failed to start session 2");
 
         // Take screenshot in session 2
         let dto2 = ScreenshotDto {
+            problem_id: uuid::Uuid::new_v4(),
             folder_name: folder2.name.clone(),
             course_name: course2.name.clone(),
             set_name: set2.name.clone(),
             problem_name: "Bell State".to_string(),
             base64_data: "test_data_2".to_string(),
+            ocr_text: None,
+            source_window_title: None,
+            source_app_name: None,
+            image_hash: None,
         };
 
         let problem2 = services::save_screenshot_to_db(&db, dto2, "path2.png".to_string())
@@ -0,0 +1,127 @@
+//! The `sessions.json` format. Sessions live in the database now; the file is
+//! only read once to import them, so a damaged file must never cost sessions.
+
+use chrono::Utc;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::SessionManager;
+
+#[derive(Debug)]
+pub enum SessionFileError {
+    Io(io::Error),
+    /// Not JSON, truncated, or not laid out as sessions
+    Invalid(String),
+}
+
+impl fmt::Display for SessionFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{}", e),
+            Self::Invalid(reason) => write!(f, "Invalid sessions file: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for SessionFileError {}
+
+impl From<io::Error> for SessionFileError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// What [`read_or_recover`] found
+#[derive(Debug)]
+pub struct Recovered {
+    pub manager: SessionManager,
+    /// Where a damaged file was moved to
+    pub quarantined: Option<PathBuf>,
+}
+
+/// Reads the file at `path`
+pub(crate) fn read(path: &Path) -> Result<SessionManager, SessionFileError> {
+    let bytes = fs::read(path)?;
+    serde_json::from_slice(&bytes).map_err(|e| SessionFileError::Invalid(e.to_string()))
+}
+
+/// Reads the file at `path`, or no sessions if there isn't one. A damaged file
+/// is moved aside rather than left to be read as empty again; read errors are
+/// returned without touching anything.
+pub(crate) fn read_or_recover(path: &Path) -> Result<Recovered, SessionFileError> {
+    let reason = match SessionManager::load_from_file(path) {
+        Ok(manager) => {
+            return Ok(Recovered {
+                manager,
+                quarantined: None,
+            })
+        }
+        Err(SessionFileError::Invalid(reason)) => reason,
+        Err(e) => return Err(e),
+    };
+
+    let quarantined = path.with_extension(format!(
+        "json.corrupt-{}",
+        Utc::now().format("%Y%m%d-%H%M%S")
+    ));
+    fs::rename(path, &quarantined)?;
+    log::error!(
+        "{} is damaged ({}); moved it to {}",
+        path.display(),
+        reason,
+        quarantined.display()
+    );
+
+    Ok(Recovered {
+        manager: SessionManager::new(),
+        quarantined: Some(quarantined),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::SessionState;
+    use tempfile::tempdir;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_read_or_recover() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("sessions.json");
+
+        let mut manager = SessionManager::new();
+        manager.sessions.push(SessionState::new(
+            "A".to_string(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+        ));
+        fs::write(&path, serde_json::to_string(&manager).unwrap()).unwrap();
+
+        let recovered = read_or_recover(&path).unwrap();
+        assert_eq!(recovered.manager.sessions, manager.sessions);
+        assert_eq!(recovered.quarantined, None);
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn test_damaged_file_is_quarantined() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("sessions.json");
+        fs::write(&path, r#"{"sessions": [{"id": "#).unwrap();
+
+        let recovered = read_or_recover(&path).unwrap();
+        assert!(recovered.manager.sessions.is_empty());
+        let quarantined = recovered.quarantined.unwrap();
+        assert_eq!(fs::read(&quarantined).unwrap(), br#"{"sessions": [{"id": "#);
+        assert!(!path.exists());
+
+        // Reading again, e.g. after the import failed, finds nothing to import
+        let again = read_or_recover(&path).unwrap();
+        assert!(again.manager.sessions.is_empty());
+        assert_eq!(again.quarantined, None);
+    }
+}
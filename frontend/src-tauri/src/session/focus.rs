@@ -0,0 +1,493 @@
+//! A pomodoro timer for the active session: work phases separated by short
+//! breaks, with a long break after every few work phases. Completed work
+//! phases are recorded against the session's set.
+
+use chrono::Utc;
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+use super::SessionManagerState;
+use crate::db::services;
+use crate::db::Db;
+use crate::notify::notify;
+use crate::settings::{FocusSettings, SettingsState};
+
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FocusPhase {
+    Work,
+    ShortBreak,
+    LongBreak,
+}
+
+impl FocusPhase {
+    fn length(self, settings: &FocusSettings) -> Duration {
+        let minutes = match self {
+            Self::Work => settings.work_minutes,
+            Self::ShortBreak => settings.short_break_minutes,
+            Self::LongBreak => settings.long_break_minutes,
+        };
+        Duration::from_secs(u64::from(minutes) * 60)
+    }
+}
+
+/// A move from one phase to the next
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct FocusTransition {
+    pub from: FocusPhase,
+    pub to: FocusPhase,
+    /// Whether `from` ran to the end, rather than being skipped
+    pub completed: bool,
+    /// How long `from` was set to run when it started
+    pub length_seconds: u64,
+}
+
+/// What the UI shows
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FocusStatus {
+    /// `None` while the timer is stopped
+    pub phase: Option<FocusPhase>,
+    pub running: bool,
+    pub remaining_seconds: u64,
+    /// Work phases completed since the timer was started
+    pub completed: u32,
+    /// Work phases, counting the current one, until the next long break
+    pub until_long_break: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Current {
+    phase: FocusPhase,
+    /// As set when the phase started; changing the settings only affects
+    /// later phases
+    length: Duration,
+    /// Time left as of `resumed_at`, or of the pause
+    remaining: Duration,
+    /// `None` while paused
+    resumed_at: Option<Instant>,
+}
+
+impl Current {
+    fn remaining(&self, now: Instant) -> Duration {
+        match self.resumed_at {
+            Some(resumed_at) => self
+                .remaining
+                .saturating_sub(now.saturating_duration_since(resumed_at)),
+            None => self.remaining,
+        }
+    }
+}
+
+/// The timer's state; advanced by [`FocusTimer::tick`]
+#[derive(Debug, Default)]
+pub struct FocusTimer {
+    current: Option<Current>,
+    completed: u32,
+}
+
+impl FocusTimer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn status(&self, now: Instant, settings: &FocusSettings) -> FocusStatus {
+        let every = settings.long_break_every.max(1);
+        FocusStatus {
+            phase: self.current.map(|c| c.phase),
+            running: self.is_running(),
+            remaining_seconds: self
+                .current
+                .map_or(0, |c| c.remaining(now).as_secs_f64().ceil() as u64),
+            completed: self.completed,
+            until_long_break: every - self.completed % every,
+        }
+    }
+
+    /// Whether a phase is counting down
+    pub fn is_running(&self) -> bool {
+        self.current.is_some_and(|c| c.resumed_at.is_some())
+    }
+
+    /// Starts a work phase, or resumes a paused phase. Returns whether
+    /// anything changed.
+    pub fn start(&mut self, now: Instant, settings: &FocusSettings) -> bool {
+        match &mut self.current {
+            None => {
+                let length = FocusPhase::Work.length(settings);
+                self.current = Some(Current {
+                    phase: FocusPhase::Work,
+                    length,
+                    remaining: length,
+                    resumed_at: Some(now),
+                });
+                self.completed = 0;
+                true
+            }
+            Some(current) if current.resumed_at.is_none() => {
+                current.resumed_at = Some(now);
+                true
+            }
+            Some(_) => false,
+        }
+    }
+
+    /// Returns whether anything changed
+    pub fn pause(&mut self, now: Instant) -> bool {
+        match &mut self.current {
+            Some(current) if current.resumed_at.is_some() => {
+                current.remaining = current.remaining(now);
+                current.resumed_at = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns whether anything changed
+    pub fn stop(&mut self) -> bool {
+        self.completed = 0;
+        self.current.take().is_some()
+    }
+
+    /// Ends the current phase early. A skipped work phase isn't counted.
+    pub fn skip(&mut self, now: Instant, settings: &FocusSettings) -> Option<FocusTransition> {
+        self.advance(now, settings, false)
+    }
+
+    /// Moves to the next phase once the current one has run out
+    pub fn tick(&mut self, now: Instant, settings: &FocusSettings) -> Option<FocusTransition> {
+        let current = self.current?;
+        if current.resumed_at.is_none() || !current.remaining(now).is_zero() {
+            return None;
+        }
+        self.advance(now, settings, true)
+    }
+
+    /// The next phase starts at `now`, so a timer that was asleep with the
+    /// computer completes one phase, not every phase it slept through
+    fn advance(
+        &mut self,
+        now: Instant,
+        settings: &FocusSettings,
+        completed: bool,
+    ) -> Option<FocusTransition> {
+        let current = self.current?;
+        let to = match current.phase {
+            FocusPhase::Work if completed => {
+                self.completed += 1;
+                if self.completed % settings.long_break_every.max(1) == 0 {
+                    FocusPhase::LongBreak
+                } else {
+                    FocusPhase::ShortBreak
+                }
+            }
+            FocusPhase::Work => FocusPhase::ShortBreak,
+            FocusPhase::ShortBreak | FocusPhase::LongBreak => FocusPhase::Work,
+        };
+        let length = to.length(settings);
+        self.current = Some(Current {
+            phase: to,
+            length,
+            remaining: length,
+            resumed_at: current.resumed_at.map(|_| now),
+        });
+        Some(FocusTransition {
+            from: current.phase,
+            to,
+            completed,
+            length_seconds: current.length.as_secs(),
+        })
+    }
+}
+
+pub type FocusTimerState = Arc<Mutex<FocusTimer>>;
+
+/// What the UI or tray asked the timer to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusCommand {
+    Start,
+    Pause,
+    Skip,
+    Stop,
+}
+
+fn announce<R: Runtime>(app: &AppHandle<R>, transition: FocusTransition, status: &FocusStatus) {
+    let settings = app.state::<SettingsState>().lock().unwrap().focus.clone();
+    let (title, body) = match transition.to {
+        FocusPhase::Work => (
+            "Back to Work",
+            format!("Focus for {} minutes.", settings.work_minutes),
+        ),
+        FocusPhase::ShortBreak => (
+            "Short Break",
+            format!("Take {} minutes.", settings.short_break_minutes),
+        ),
+        FocusPhase::LongBreak => (
+            "Long Break",
+            format!(
+                "{} pomodoros done. Take {} minutes.",
+                status.completed, settings.long_break_minutes
+            ),
+        ),
+    };
+    notify(app, title, body);
+    app.emit("focus-phase-changed", transition).ok();
+}
+
+/// Records a completed work phase of `length_seconds` against the active
+/// session's set
+async fn record_pomodoro<R: Runtime>(
+    app: &AppHandle<R>,
+    length_seconds: u64,
+) -> Result<(), String> {
+    let db = app
+        .try_state::<Db>()
+        .ok_or_else(|| "Database is not initialized".to_string())?;
+    let Some(context) = app
+        .state::<SessionManagerState>()
+        .lock()
+        .unwrap()
+        .get_active_session()
+        .map(|session| session.study_context())
+    else {
+        return Ok(());
+    };
+    let length_seconds = i32::try_from(length_seconds)
+        .map_err(|_| format!("Work phase of {}s is too long to record", length_seconds))?;
+
+    let pomodoro = services::record_pomodoro(
+        db.connection(),
+        context,
+        length_seconds,
+        Utc::now().naive_utc(),
+    )
+    .await
+    .map_err(|e| format!("Failed to record pomodoro: {}", e))?;
+    app.emit("pomodoro-completed", pomodoro).ok();
+    Ok(())
+}
+
+/// Applies a command from the UI or tray and tells the UI. The timer only
+/// runs while a session is active.
+pub(crate) fn control_focus_timer<R: Runtime>(
+    app: &AppHandle<R>,
+    command: FocusCommand,
+) -> Result<FocusStatus, String> {
+    let session_active = app
+        .state::<SessionManagerState>()
+        .lock()
+        .unwrap()
+        .get_active_session()
+        .is_some();
+    if command == FocusCommand::Start && !session_active {
+        return Err("Start a session to use the focus timer".to_string());
+    }
+
+    let settings = app.state::<SettingsState>().lock().unwrap().focus.clone();
+    let now = Instant::now();
+    let (status, transition) = {
+        let timer = app.state::<FocusTimerState>();
+        let mut timer = timer.lock().unwrap();
+        let transition = match command {
+            FocusCommand::Start => {
+                timer.start(now, &settings);
+                None
+            }
+            FocusCommand::Pause => {
+                timer.pause(now);
+                None
+            }
+            FocusCommand::Skip => timer.skip(now, &settings),
+            FocusCommand::Stop => {
+                timer.stop();
+                None
+            }
+        };
+        (timer.status(now, &settings), transition)
+    };
+
+    if let Some(transition) = transition {
+        announce(app, transition, &status);
+    }
+    app.emit("focus-timer-changed", &status).ok();
+    Ok(status)
+}
+
+/// Runs the timer in the background: moves between phases, records completed
+/// work phases, and stops the timer when the session ends. Call once the
+/// database is open.
+pub(crate) fn start_focus_timer_loop<R: Runtime>(app: AppHandle<R>) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(TICK_INTERVAL);
+        let session_active = app
+            .state::<SessionManagerState>()
+            .lock()
+            .unwrap()
+            .get_active_session()
+            .is_some();
+        let settings = app.state::<SettingsState>().lock().unwrap().focus.clone();
+        let now = Instant::now();
+
+        let (status, transition) = {
+            let timer = app.state::<FocusTimerState>();
+            let mut timer = timer.lock().unwrap();
+            if !session_active {
+                if timer.stop() {
+                    (timer.status(now, &settings), None)
+                } else {
+                    continue;
+                }
+            } else {
+                let Some(transition) = timer.tick(now, &settings) else {
+                    continue;
+                };
+                (timer.status(now, &settings), Some(transition))
+            }
+        };
+
+        if let Some(transition) = transition {
+            if transition.from == FocusPhase::Work && transition.completed {
+                if let Err(e) =
+                    tauri::async_runtime::block_on(record_pomodoro(&app, transition.length_seconds))
+                {
+                    log::error!("{}", e);
+                }
+            }
+            announce(&app, transition, &status);
+        }
+        app.emit("focus-timer-changed", &status).ok();
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn minutes(m: u64) -> Duration {
+        Duration::from_secs(m * 60)
+    }
+
+    fn settings() -> FocusSettings {
+        FocusSettings {
+            work_minutes: 25,
+            short_break_minutes: 5,
+            long_break_minutes: 15,
+            long_break_every: 2,
+        }
+    }
+
+    #[test]
+    fn test_cycles_through_phases() {
+        let settings = settings();
+        let mut timer = FocusTimer::new();
+        let mut now = Instant::now();
+        assert!(timer.start(now, &settings));
+
+        assert_eq!(timer.tick(now + minutes(24), &settings), None);
+        now += minutes(25);
+        assert_eq!(
+            timer.tick(now, &settings),
+            Some(FocusTransition {
+                from: FocusPhase::Work,
+                to: FocusPhase::ShortBreak,
+                completed: true,
+                length_seconds: 25 * 60,
+            })
+        );
+        assert_eq!(timer.status(now, &settings).remaining_seconds, 5 * 60);
+
+        now += minutes(5);
+        assert_eq!(timer.tick(now, &settings).unwrap().to, FocusPhase::Work);
+        now += minutes(25);
+        // Every second work phase is followed by a long break
+        assert_eq!(
+            timer.tick(now, &settings).unwrap().to,
+            FocusPhase::LongBreak
+        );
+
+        let status = timer.status(now, &settings);
+        assert_eq!(status.completed, 2);
+        assert_eq!(status.until_long_break, 2);
+        assert_eq!(status.remaining_seconds, 15 * 60);
+    }
+
+    #[test]
+    fn test_pause_and_resume() {
+        let settings = settings();
+        let mut timer = FocusTimer::new();
+        let start = Instant::now();
+        timer.start(start, &settings);
+
+        assert!(timer.pause(start + minutes(10)));
+        assert!(!timer.pause(start + minutes(11)));
+        // Paused time doesn't count
+        assert_eq!(timer.tick(start + minutes(60), &settings), None);
+        let status = timer.status(start + minutes(60), &settings);
+        assert!(!status.running);
+        assert_eq!(status.remaining_seconds, 15 * 60);
+
+        assert!(timer.start(start + minutes(60), &settings));
+        assert_eq!(timer.tick(start + minutes(74), &settings), None);
+        assert!(timer.tick(start + minutes(75), &settings).is_some());
+    }
+
+    #[test]
+    fn test_settings_changed_mid_phase() {
+        let mut settings = settings();
+        let mut timer = FocusTimer::new();
+        let start = Instant::now();
+        timer.start(start, &settings);
+
+        // The running phase keeps the length it started with
+        settings.work_minutes = 50;
+        assert_eq!(timer.tick(start + minutes(24), &settings), None);
+        let transition = timer.tick(start + minutes(25), &settings).unwrap();
+        assert_eq!(transition.length_seconds, 25 * 60);
+
+        let transition = timer.tick(start + minutes(30), &settings).unwrap();
+        assert_eq!(transition.to, FocusPhase::Work);
+        let status = timer.status(start + minutes(30), &settings);
+        assert_eq!(status.remaining_seconds, 50 * 60);
+    }
+
+    #[test]
+    fn test_skipped_work_is_not_counted() {
+        let settings = settings();
+        let mut timer = FocusTimer::new();
+        let now = Instant::now();
+        assert_eq!(timer.skip(now, &settings), None);
+
+        timer.start(now, &settings);
+        assert_eq!(
+            timer.skip(now, &settings),
+            Some(FocusTransition {
+                from: FocusPhase::Work,
+                to: FocusPhase::ShortBreak,
+                completed: false,
+                length_seconds: 25 * 60,
+            })
+        );
+        assert_eq!(timer.skip(now, &settings).unwrap().to, FocusPhase::Work);
+        assert_eq!(timer.status(now, &settings).completed, 0);
+    }
+
+    #[test]
+    fn test_stop_resets() {
+        let settings = settings();
+        let mut timer = FocusTimer::new();
+        let now = Instant::now();
+        timer.start(now, &settings);
+        timer.tick(now + minutes(25), &settings);
+
+        assert!(timer.stop());
+        assert!(!timer.stop());
+        let status = timer.status(now, &settings);
+        assert_eq!(status.phase, None);
+        assert_eq!(status.completed, 0);
+        assert_eq!(timer.tick(now + minutes(60), &settings), None);
+    }
+}
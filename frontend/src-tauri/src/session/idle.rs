@@ -0,0 +1,365 @@
+//! Pauses and ends the active session when nobody is at the computer.
+//!
+//! [`DeviceQuerySource`] reads the mouse and keyboard through device_query.
+//! [`IdleTracker`] only sees the [`ActivitySource`] trait, so tests can run it
+//! against a fake.
+
+use chrono::Utc;
+use device_query::{DeviceQuery, DeviceState};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+use super::{control_focus_timer, FocusCommand, FocusTimerState, SessionManagerState};
+use crate::commands::sessions::end_active_session;
+use crate::commands::study::record_active_session;
+use crate::db::services;
+use crate::db::Db;
+use crate::notify::notify;
+use crate::settings::{IdleSettings, SettingsState};
+
+/// How often input is sampled. Input is compared between samples, so a
+/// key pressed and released in between goes unseen; moving the mouse or
+/// typing for longer does not.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Mouse and keyboard state at one moment
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InputState {
+    pub mouse: (i32, i32),
+    pub buttons: Vec<bool>,
+    pub keys: Vec<String>,
+}
+
+pub trait ActivitySource {
+    fn sample(&mut self) -> InputState;
+}
+
+pub struct DeviceQuerySource {
+    device_state: DeviceState,
+}
+
+impl DeviceQuerySource {
+    pub fn new() -> Self {
+        Self {
+            device_state: DeviceState::new(),
+        }
+    }
+}
+
+impl ActivitySource for DeviceQuerySource {
+    fn sample(&mut self) -> InputState {
+        let mouse = self.device_state.get_mouse();
+        InputState {
+            mouse: mouse.coords,
+            buttons: mouse.button_pressed,
+            keys: self
+                .device_state
+                .get_keys()
+                .iter()
+                .map(|key| format!("{:?}", key))
+                .collect(),
+        }
+    }
+}
+
+/// What changed since the last [`IdleTracker::poll`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdleEvent {
+    /// Idle for the pause threshold; `idle_since` is the last input
+    Paused { idle_since: Instant },
+    /// Input again after a pause
+    Resumed,
+    /// Idle for the end threshold
+    Ended { idle_since: Instant },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    Active,
+    Paused,
+    Ended,
+}
+
+/// Turns input samples into pause, resume and end events
+pub struct IdleTracker<S> {
+    source: S,
+    last_input: InputState,
+    idle_since: Instant,
+    phase: Phase,
+}
+
+impl<S: ActivitySource> IdleTracker<S> {
+    pub fn new(mut source: S, now: Instant) -> Self {
+        let last_input = source.sample();
+        Self {
+            source,
+            last_input,
+            idle_since: now,
+            phase: Phase::Active,
+        }
+    }
+
+    /// Samples input at `now`. Idle time only counts while a session is
+    /// active, so a session started after a break isn't ended straight away.
+    pub fn poll(
+        &mut self,
+        now: Instant,
+        settings: &IdleSettings,
+        session_active: bool,
+    ) -> Option<IdleEvent> {
+        let input = self.source.sample();
+        if input != self.last_input {
+            self.last_input = input;
+            self.idle_since = now;
+            let was_paused = self.phase == Phase::Paused;
+            self.phase = Phase::Active;
+            return (was_paused && session_active).then_some(IdleEvent::Resumed);
+        }
+
+        if !session_active {
+            self.idle_since = now;
+            self.phase = Phase::Active;
+            return None;
+        }
+
+        let idle = now.saturating_duration_since(self.idle_since);
+        let reached = |minutes: Option<u32>| {
+            minutes.is_some_and(|m| idle >= Duration::from_secs(u64::from(m) * 60))
+        };
+        if self.phase != Phase::Ended && reached(settings.end_after_minutes) {
+            self.phase = Phase::Ended;
+            return Some(IdleEvent::Ended {
+                idle_since: self.idle_since,
+            });
+        }
+        if self.phase == Phase::Active && reached(settings.pause_after_minutes) {
+            self.phase = Phase::Paused;
+            return Some(IdleEvent::Paused {
+                idle_since: self.idle_since,
+            });
+        }
+        None
+    }
+}
+
+/// Stops the running study interval at the last input, not when the
+/// threshold was noticed
+async fn stop_study_time<R: Runtime>(
+    app: &AppHandle<R>,
+    idle_since: Instant,
+) -> Result<(), String> {
+    let db = app
+        .try_state::<Db>()
+        .ok_or_else(|| "Database is not initialized".to_string())?;
+    let idle = chrono::Duration::from_std(idle_since.elapsed()).unwrap_or_default();
+
+    services::record_study_context(db.connection(), None, Utc::now().naive_utc() - idle)
+        .await
+        .map_err(|e| format!("Failed to record study time: {}", e))
+}
+
+/// `focus_paused` is whether the pause stopped a running focus timer, which
+/// then starts again on resume
+async fn handle<R: Runtime>(
+    app: &AppHandle<R>,
+    event: IdleEvent,
+    focus_paused: &mut bool,
+) -> Result<(), String> {
+    let session_manager = app.state::<SessionManagerState>();
+    match event {
+        IdleEvent::Paused { idle_since } => {
+            stop_study_time(app, idle_since).await?;
+            // Nobody is there to finish the pomodoro
+            *focus_paused = app.state::<FocusTimerState>().lock().unwrap().is_running();
+            control_focus_timer(app, FocusCommand::Pause)?;
+            let name = session_manager
+                .lock()
+                .unwrap()
+                .get_active_session()
+                .map(|s| s.name.clone())
+                .unwrap_or_default();
+            notify(
+                app,
+                "Session Paused",
+                format!("No activity, so {name} stopped counting study time."),
+            );
+            app.emit("session-paused", ()).ok();
+        }
+        IdleEvent::Resumed => {
+            record_active_session(app).await?;
+            if std::mem::take(focus_paused) {
+                control_focus_timer(app, FocusCommand::Start)?;
+            }
+            app.emit("session-resumed", ()).ok();
+        }
+        IdleEvent::Ended { idle_since } => {
+            // The focus timer stops with the session
+            *focus_paused = false;
+            stop_study_time(app, idle_since).await?;
+            let ended_name = session_manager
+                .lock()
+                .unwrap()
+                .get_active_session()
+                .map(|s| s.name.clone());
+            end_active_session(app).await?;
+            notify(
+                app,
+                "Session Ended",
+                format!(
+                    "Ended after no activity: {}",
+                    ended_name.unwrap_or_default()
+                ),
+            );
+            app.emit("session-state-changed", ()).ok();
+        }
+    }
+    Ok(())
+}
+
+/// Watches for input in the background and pauses, resumes and ends the
+/// active session as configured in [`IdleSettings`]. Call once the study log
+/// has started.
+pub(crate) fn start_idle_monitor<R: Runtime>(app: AppHandle<R>) {
+    std::thread::spawn(move || {
+        let mut tracker = IdleTracker::new(DeviceQuerySource::new(), Instant::now());
+        let mut focus_paused = false;
+        loop {
+            std::thread::sleep(POLL_INTERVAL);
+            let settings = app.state::<SettingsState>().lock().unwrap().idle.clone();
+            let session_active = app
+                .state::<SessionManagerState>()
+                .lock()
+                .unwrap()
+                .get_active_session()
+                .is_some();
+
+            if let Some(event) = tracker.poll(Instant::now(), &settings, session_active) {
+                if let Err(e) = tauri::async_runtime::block_on(handle(&app, event, &mut focus_paused)) {
+                    log::error!("{}", e);
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    /// Reports whatever the test last set
+    #[derive(Clone, Default)]
+    struct FakeSource(Rc<RefCell<InputState>>);
+
+    impl FakeSource {
+        fn move_mouse(&self, x: i32) {
+            self.0.borrow_mut().mouse = (x, 0);
+        }
+    }
+
+    impl ActivitySource for FakeSource {
+        fn sample(&mut self) -> InputState {
+            self.0.borrow().clone()
+        }
+    }
+
+    fn minutes(m: u64) -> Duration {
+        Duration::from_secs(m * 60)
+    }
+
+    fn settings() -> IdleSettings {
+        IdleSettings {
+            pause_after_minutes: Some(5),
+            end_after_minutes: Some(30),
+        }
+    }
+
+    #[test]
+    fn test_pauses_resumes_and_ends() {
+        let source = FakeSource::default();
+        let start = Instant::now();
+        let settings = settings();
+        let mut tracker = IdleTracker::new(source.clone(), start);
+
+        assert_eq!(tracker.poll(start + minutes(4), &settings, true), None);
+        assert_eq!(
+            tracker.poll(start + minutes(5), &settings, true),
+            Some(IdleEvent::Paused { idle_since: start })
+        );
+        // Paused once, not on every poll
+        assert_eq!(tracker.poll(start + minutes(6), &settings, true), None);
+
+        source.move_mouse(10);
+        assert_eq!(
+            tracker.poll(start + minutes(7), &settings, true),
+            Some(IdleEvent::Resumed)
+        );
+
+        let back = start + minutes(7);
+        assert_eq!(
+            tracker.poll(back + minutes(5), &settings, true),
+            Some(IdleEvent::Paused { idle_since: back })
+        );
+        assert_eq!(
+            tracker.poll(back + minutes(30), &settings, true),
+            Some(IdleEvent::Ended { idle_since: back })
+        );
+        assert_eq!(tracker.poll(back + minutes(31), &settings, true), None);
+    }
+
+    #[test]
+    fn test_keys_count_as_activity() {
+        let source = FakeSource::default();
+        let start = Instant::now();
+        let settings = settings();
+        let mut tracker = IdleTracker::new(source.clone(), start);
+
+        source.0.borrow_mut().keys = vec!["A".to_string()];
+        assert_eq!(tracker.poll(start + minutes(4), &settings, true), None);
+        assert_eq!(tracker.poll(start + minutes(8), &settings, true), None);
+        assert!(tracker.poll(start + minutes(9), &settings, true).is_some());
+    }
+
+    #[test]
+    fn test_nothing_happens_without_a_session() {
+        let source = FakeSource::default();
+        let start = Instant::now();
+        let settings = settings();
+        let mut tracker = IdleTracker::new(source.clone(), start);
+
+        let started = start + minutes(60);
+        assert_eq!(tracker.poll(started, &settings, false), None);
+
+        assert_eq!(tracker.poll(started + minutes(1), &settings, true), None);
+        assert_eq!(
+            tracker.poll(started + minutes(5), &settings, true),
+            Some(IdleEvent::Paused {
+                idle_since: started
+            })
+        );
+    }
+
+    #[test]
+    fn test_disabled_steps() {
+        let source = FakeSource::default();
+        let start = Instant::now();
+        let mut tracker = IdleTracker::new(source.clone(), start);
+
+        let end_only = IdleSettings {
+            pause_after_minutes: None,
+            end_after_minutes: Some(30),
+        };
+        assert_eq!(tracker.poll(start + minutes(29), &end_only, true), None);
+        assert_eq!(
+            tracker.poll(start + minutes(30), &end_only, true),
+            Some(IdleEvent::Ended { idle_since: start })
+        );
+
+        // Both are off by default
+        let never = IdleSettings::default();
+        source.move_mouse(1);
+        tracker.poll(start + minutes(31), &never, true);
+        assert_eq!(tracker.poll(start + minutes(600), &never, true), None);
+    }
+}
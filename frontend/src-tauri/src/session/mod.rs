@@ -1,10 +1,24 @@
 use chrono::{NaiveDateTime, Utc};
+use sea_orm::{DatabaseConnection, DbErr};
 use serde::{Deserialize, Serialize};
-use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use uuid::Uuid;
 
+use crate::db::entities::sessions;
+use crate::db::services::{self, SessionImport, StudyContext};
+use file::SessionFileError;
+
+mod file;
+mod focus;
+mod idle;
+
+pub use focus::{
+    FocusCommand, FocusPhase, FocusStatus, FocusTimer, FocusTimerState, FocusTransition,
+};
+pub(crate) use focus::{control_focus_timer, start_focus_timer_loop};
+pub(crate) use idle::start_idle_monitor;
+
 /// Represents an active study session with folder/course/set context
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct SessionState {
@@ -62,8 +76,42 @@ impl SessionState {
         }
     }
 
-    pub fn update_last_used(&mut self) {
-        self.last_used = Utc::now().naive_utc();
+    /// What study time in this session is recorded against
+    pub fn study_context(&self) -> StudyContext {
+        StudyContext {
+            session_id: self.id,
+            folder_id: self.folder_id,
+            course_id: self.course_id,
+            set_id: self.set_id,
+        }
+    }
+}
+
+impl From<sessions::Model> for SessionState {
+    fn from(model: sessions::Model) -> Self {
+        Self {
+            id: model.id,
+            name: model.name,
+            folder_id: model.folder_id,
+            course_id: model.course_id,
+            set_id: model.set_id,
+            created_at: model.created_at,
+            last_used: model.last_used,
+        }
+    }
+}
+
+impl From<SessionState> for sessions::Model {
+    fn from(session: SessionState) -> Self {
+        Self {
+            id: session.id,
+            name: session.name,
+            folder_id: session.folder_id,
+            course_id: session.course_id,
+            set_id: session.set_id,
+            created_at: session.created_at,
+            last_used: session.last_used,
+        }
     }
 }
 
@@ -83,29 +131,30 @@ impl SessionManager {
     }
 
     /// Load session manager from JSON file
-    pub fn load_from_file(path: &PathBuf) -> Result<Self, Box<dyn std::error::Error>> {
+    pub fn load_from_file(path: &Path) -> Result<Self, SessionFileError> {
         if !path.exists() {
             log::info!("Session file does not exist, creating new SessionManager");
             return Ok(Self::new());
         }
 
-        let contents = fs::read_to_string(path)?;
-        let manager: SessionManager = serde_json::from_str(&contents)?;
+        let manager = file::read(path)?;
         log::info!("Loaded {} sessions from file", manager.sessions.len());
         Ok(manager)
     }
 
-    /// Save session manager to JSON file
-    pub fn save_to_file(&self, path: &PathBuf) -> Result<(), Box<dyn std::error::Error>> {
-        // Ensure parent directory exists
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)?;
-        }
-
-        let json = serde_json::to_string_pretty(self)?;
-        fs::write(path, json)?;
-        log::info!("Saved {} sessions to file", self.sessions.len());
-        Ok(())
+    /// Load sessions and the active session from the database
+    pub async fn load_from_db(db: &DatabaseConnection) -> Result<Self, DbErr> {
+        let sessions: Vec<SessionState> = services::get_all_sessions(db)
+            .await?
+            .into_iter()
+            .map(SessionState::from)
+            .collect();
+        let active_session_id = services::get_app_state(db).await?.active_session_id;
+        log::info!("Loaded {} sessions from the database", sessions.len());
+        Ok(Self {
+            sessions,
+            active_session_id,
+        })
     }
 
     /// Get the currently active session
@@ -120,70 +169,6 @@ impl SessionManager {
         self.sessions.iter_mut().find(|s| s.id == active_id)
     }
 
-    /// Start a session by ID
-    pub fn start_session(&mut self, session_id: Uuid) -> Result<(), String> {
-        // Check if session exists
-        let session = self
-            .sessions
-            .iter_mut()
-            .find(|s| s.id == session_id)
-            .ok_or_else(|| format!("Session with id {} not found", session_id))?;
-
-        // Update last used time
-        session.update_last_used();
-        self.active_session_id = Some(session_id);
-        log::info!("Started session: {}", session.name);
-        Ok(())
-    }
-
-    /// End the current session
-    pub fn end_session(&mut self) {
-        if let Some(id) = self.active_session_id {
-            log::info!("Ended session with id: {}", id);
-        }
-        self.active_session_id = None;
-    }
-
-    /// Create a new session and optionally start it
-    pub fn create_session(
-        &mut self,
-        name: String,
-        folder_id: Uuid,
-        course_id: Uuid,
-        set_id: Uuid,
-        start_immediately: bool,
-    ) -> SessionState {
-        let session = SessionState::new(name, folder_id, course_id, set_id);
-        let session_id = session.id;
-        self.sessions.push(session.clone());
-
-        if start_immediately {
-            self.active_session_id = Some(session_id);
-        }
-
-        log::info!("Created session: {}", session.name);
-        session
-    }
-
-    /// Delete a session by ID
-    pub fn delete_session(&mut self, session_id: Uuid) -> Result<(), String> {
-        let index = self
-            .sessions
-            .iter()
-            .position(|s| s.id == session_id)
-            .ok_or_else(|| format!("Session with id {} not found", session_id))?;
-
-        let session = self.sessions.remove(index);
-        log::info!("Deleted session: {}", session.name);
-
-        // If we deleted the active session, clear active_session_id
-        if self.active_session_id == Some(session_id) {
-            self.active_session_id = None;
-        }
-
-        Ok(())
-    }
-
     /// Get all sessions
     pub fn get_all_sessions(&self) -> &[SessionState] {
         &self.sessions
@@ -205,108 +190,65 @@ impl SessionManager {
 /// Wrapper for SessionManager to be used as Tauri state
 pub type SessionManagerState = Arc<Mutex<SessionManager>>;
 
+/// Result of [`import_sessions_file`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum SessionFileImport {
+    Imported(SessionImport),
+    /// The file was damaged and moved here. Nothing was imported, so a
+    /// repaired copy put back in its place is imported on a later call.
+    Quarantined(PathBuf),
+}
 
-#[cfg(test)]
-mod unit_tests {
-    use super::*;
-    use tempfile::tempdir;
-
-    #[test]
-    fn test_create_session() {
-        let mut manager = SessionManager::new();
-        let folder_id = Uuid::new_v4();
-        let course_id = Uuid::new_v4();
-        let set_id = Uuid::new_v4();
-
-        let session = manager.create_session(
-            "Test Session".to_string(),
-            folder_id,
-            course_id,
-            set_id,
-            false,
-        );
-
-        assert_eq!(manager.sessions.len(), 1);
-        assert_eq!(session.name, "Test Session");
-        assert_eq!(session.folder_id, folder_id);
+/// Imports `sessions.json`, where sessions were kept before they moved into
+/// the database. Only the first call that finds a readable file does
+/// anything, and that file is left in place. A damaged one is moved aside.
+pub async fn import_sessions_file(
+    db: &DatabaseConnection,
+    path: &Path,
+) -> Result<Option<SessionFileImport>, Box<dyn std::error::Error>> {
+    if services::get_app_state(db).await?.sessions_imported_at.is_some() || !path.exists() {
+        return Ok(None);
     }
 
-    #[test]
-    fn test_start_and_end_session() {
-        let mut manager = SessionManager::new();
-        let folder_id = Uuid::new_v4();
-        let course_id = Uuid::new_v4();
-        let set_id = Uuid::new_v4();
-
-        let session = manager.create_session(
-            "Test Session".to_string(),
-            folder_id,
-            course_id,
-            set_id,
-            false,
-        );
-
-        assert!(manager.get_active_session().is_none());
-
-        manager.start_session(session.id).unwrap();
-        assert!(manager.get_active_session().is_some());
-        assert_eq!(manager.get_active_session().unwrap().id, session.id);
-
-        manager.end_session();
-        assert!(manager.get_active_session().is_none());
+    let recovered = file::read_or_recover(path)?;
+    if let Some(quarantined) = recovered.quarantined {
+        return Ok(Some(SessionFileImport::Quarantined(quarantined)));
     }
+    let manager = recovered.manager;
+    let sessions = manager.sessions.into_iter().map(sessions::Model::from).collect();
+    let imported = services::import_sessions(db, sessions, manager.active_session_id).await?;
 
-    #[test]
-    fn test_delete_session() {
-        let mut manager = SessionManager::new();
-        let folder_id = Uuid::new_v4();
-        let course_id = Uuid::new_v4();
-        let set_id = Uuid::new_v4();
-
-        let session = manager.create_session(
-            "Test Session".to_string(),
-            folder_id,
-            course_id,
-            set_id,
-            true,
-        );
-
-        assert_eq!(manager.sessions.len(), 1);
-        assert!(manager.get_active_session().is_some());
+    Ok(imported.map(SessionFileImport::Imported))
+}
 
-        manager.delete_session(session.id).unwrap();
-        assert_eq!(manager.sessions.len(), 0);
-        assert!(manager.get_active_session().is_none());
-    }
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+    use tempfile::tempdir;
 
     #[test]
     fn test_persistence() {
         let dir = tempdir().unwrap();
         let file_path = dir.path().join("test_sessions.json");
 
-        // Create and save
+        // Create and save the way earlier versions did
         let mut manager = SessionManager::new();
         let folder_id = Uuid::new_v4();
         let course_id = Uuid::new_v4();
         let set_id = Uuid::new_v4();
         let folder_id_2 = Uuid::new_v4();
 
-        manager.create_session(
-            "Test Session 1".to_string(),
-            folder_id,
-            course_id,
-            set_id,
-            true,
-        );
-        manager.create_session(
+        let session = SessionState::new("Test Session 1".to_string(), folder_id, course_id, set_id);
+        manager.active_session_id = Some(session.id);
+        manager.sessions.push(session);
+        manager.sessions.push(SessionState::new(
             "Test Session 2".to_string(),
             folder_id_2,
             course_id,
             set_id,
-            false,
-        );
+        ));
 
-        manager.save_to_file(&file_path).unwrap();
+        std::fs::write(&file_path, serde_json::to_string(&manager).unwrap()).unwrap();
         assert!(file_path.exists());
 
         // Load and verify
@@ -319,6 +261,119 @@ mod unit_tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_import_sessions_file_and_load_from_db() {
+        use migration::{Migrator, MigratorTrait};
+        use sea_orm::Database;
+
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+        let user_id = services::get_or_create_default_user(&db).await.unwrap();
+        let folder = services::find_or_create_folder(&db, user_id, "Math".to_string())
+            .await
+            .unwrap();
+        let course = services::find_or_create_course(&db, folder.id, "Calculus".to_string())
+            .await
+            .unwrap();
+        let set = services::find_or_create_set(&db, course.id, "Week 1".to_string())
+            .await
+            .unwrap();
+
+        // One session that still resolves, and one whose set is gone
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("sessions.json");
+        let mut manager = SessionManager::new();
+        let kept = SessionState::new("Kept".to_string(), folder.id, course.id, set.id);
+        manager.active_session_id = Some(kept.id);
+        manager.sessions.push(kept.clone());
+        manager.sessions.push(SessionState::new(
+            "Stale".to_string(),
+            folder.id,
+            course.id,
+            Uuid::new_v4(),
+        ));
+        std::fs::write(&file_path, serde_json::to_string(&manager).unwrap()).unwrap();
+
+        let result = import_sessions_file(&db, &file_path).await.unwrap();
+        assert_eq!(
+            result,
+            Some(SessionFileImport::Imported(SessionImport {
+                imported: 1,
+                skipped: 1,
+            }))
+        );
+
+        let loaded = SessionManager::load_from_db(&db).await.unwrap();
+        assert_eq!(loaded.sessions, vec![kept.clone()]);
+        assert_eq!(loaded.active_session_id, Some(kept.id));
+
+        // Later launches leave the database alone
+        assert!(import_sessions_file(&db, &file_path).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_import_retries_after_a_damaged_file() {
+        use migration::{Migrator, MigratorTrait};
+        use sea_orm::Database;
+
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("sessions.json");
+
+        // Nothing to import yet
+        assert!(import_sessions_file(&db, &file_path).await.unwrap().is_none());
+
+        std::fs::write(&file_path, r#"{"sessions": [{"id": "#).unwrap();
+        let Some(SessionFileImport::Quarantined(quarantined)) =
+            import_sessions_file(&db, &file_path).await.unwrap()
+        else {
+            panic!("damaged file wasn't quarantined");
+        };
+        assert!(quarantined.exists());
+        assert!(services::get_app_state(&db)
+            .await
+            .unwrap()
+            .sessions_imported_at
+            .is_none());
+        assert!(import_sessions_file(&db, &file_path).await.unwrap().is_none());
+
+        // The repaired file is put back
+        let set = services::test_fixtures::create_set(&db, "Calculus", "Week 1").await;
+        let mut manager = SessionManager::new();
+        let session =
+            SessionState::new("Week 1".to_string(), set.folder_id, set.course_id, set.set_id);
+        manager.active_session_id = Some(session.id);
+        manager.sessions.push(session.clone());
+        std::fs::write(&file_path, serde_json::to_string(&manager).unwrap()).unwrap();
+
+        // Meanwhile another session was started, and stays active
+        let week2 = services::test_fixtures::create_set(&db, "Calculus", "Week 2").await;
+        let started = SessionState::new(
+            "Week 2".to_string(),
+            week2.folder_id,
+            week2.course_id,
+            week2.set_id,
+        );
+        services::create_session(&db, started.clone().into(), true)
+            .await
+            .unwrap();
+
+        let result = import_sessions_file(&db, &file_path).await.unwrap();
+        assert_eq!(
+            result,
+            Some(SessionFileImport::Imported(SessionImport {
+                imported: 1,
+                skipped: 0,
+            }))
+        );
+        let loaded = SessionManager::load_from_db(&db).await.unwrap();
+        assert_eq!(loaded.sessions.len(), 2);
+        assert!(loaded.sessions.contains(&session));
+        assert_eq!(loaded.active_session_id, Some(started.id));
+        assert!(import_sessions_file(&db, &file_path).await.unwrap().is_none());
+    }
+
     #[test]
     fn test_session_exists_for_context() {
         let mut manager = SessionManager::new();
@@ -329,14 +384,13 @@ mod unit_tests {
         // No session exists initially
         assert!(!manager.session_exists_for_context(folder_id, course_id, set_id));
 
-        // Create a session
-        manager.create_session(
+        // Add a session
+        manager.sessions.push(SessionState::new(
             "Test Session".to_string(),
             folder_id,
             course_id,
             set_id,
-            false,
-        );
+        ));
 
         // Session should now exist for this context
         assert!(manager.session_exists_for_context(folder_id, course_id, set_id));
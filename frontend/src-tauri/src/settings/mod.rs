@@ -0,0 +1,503 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+/// Settings for the local LLM used to summarise OCR text into problem titles.
+/// Any Ollama-compatible `/api/generate` endpoint works.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct LlmSettings {
+    pub enabled: bool,
+    pub endpoint: String,
+    pub model: String,
+    /// Hard timeout for the whole request, in milliseconds
+    pub timeout_ms: u64,
+}
+
+impl Default for LlmSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: "http://127.0.0.1:11434".to_string(),
+            model: "llama3.2".to_string(),
+            timeout_ms: 5000,
+        }
+    }
+}
+
+/// How new problems are named
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum NamingStrategy {
+    /// OCR → LLM pipeline with timestamp fallback
+    #[default]
+    Ai,
+    /// `problem-YYYYMMDD-HHMMSS`
+    Timestamp,
+    /// `{prefix}1`, `{prefix}2`, … within the set
+    Sequence { prefix: String },
+    /// Template with `{folder}`, `{course}`, `{set}`, `{n}`, `{date}`, `{time}`
+    Template { template: String },
+}
+
+/// Settings for the problem naming pipeline
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct NamingSettings {
+    /// Global default strategy
+    pub strategy: NamingStrategy,
+    /// Per-set overrides, keyed by set id
+    pub set_strategies: HashMap<Uuid, NamingStrategy>,
+    pub llm: LlmSettings,
+}
+
+impl NamingSettings {
+    /// Strategy for a set: its override if any, otherwise the global default
+    pub fn strategy_for_set(&self, set_id: Uuid) -> &NamingStrategy {
+        self.set_strategies.get(&set_id).unwrap_or(&self.strategy)
+    }
+}
+
+/// Which part of the screen a capture covers
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CaptureMode {
+    /// The monitor the mouse is on
+    #[default]
+    MonitorUnderMouse,
+    /// A specific monitor, by its xcap id
+    Monitor { id: u32 },
+    /// Every monitor stitched into one image at its virtual-desktop offset
+    AllMonitors,
+}
+
+/// Settings for screen capture
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct CaptureSettings {
+    /// Mode used by the default screenshot shortcut
+    pub mode: CaptureMode,
+    /// Registers `Ctrl+Shift+A` to capture every monitor, whatever `mode`
+    /// is. Off by default so the shortcut stays free for other apps.
+    pub all_monitors_shortcut: bool,
+}
+
+/// File format problem images are saved in
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageFormat {
+    #[default]
+    Png,
+    /// Lossless WebP
+    Webp,
+    Jpeg,
+}
+
+/// How captures are encoded before they are written to disk
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct EncodingSettings {
+    pub format: ImageFormat,
+    /// JPEG quality, 1–100; PNG and WebP are lossless and ignore it
+    pub quality: u8,
+    /// Downscale so the longer side is at most this many pixels
+    pub max_long_edge: Option<u32>,
+    pub greyscale: bool,
+}
+
+impl Default for EncodingSettings {
+    fn default() -> Self {
+        Self {
+            format: ImageFormat::Png,
+            quality: 85,
+            max_long_edge: None,
+            greyscale: false,
+        }
+    }
+}
+
+impl EncodingSettings {
+    /// Full-resolution colour PNG, whatever the user's settings. Originals
+    /// kept for undoing a cleanup are stored this way, so nothing is lost
+    /// before the cleanup runs.
+    pub fn lossless() -> Self {
+        Self {
+            format: ImageFormat::Png,
+            max_long_edge: None,
+            greyscale: false,
+            ..Self::default()
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=100).contains(&self.quality) {
+            return Err("quality must be between 1 and 100".to_string());
+        }
+        if self.max_long_edge == Some(0) {
+            return Err("max_long_edge must be greater than 0".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Processing applied to a capture after it is taken, in this order. The
+/// capture as taken is kept, so every step can be undone.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct CleanupSteps {
+    /// Turn light-on-dark captures into dark-on-light
+    pub invert_dark: bool,
+    /// Cut away uniform borders
+    pub trim_borders: bool,
+    /// Straighten text photographed or scanned at a slight angle
+    pub deskew: bool,
+    /// Stretch levels so the darkest ink is black and the page white
+    pub boost_contrast: bool,
+    pub greyscale: bool,
+}
+
+impl CleanupSteps {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Settings for the post-capture cleanup pipeline
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct CleanupSettings {
+    /// Steps per course, keyed by course id; courses not listed get none
+    pub course_steps: HashMap<Uuid, CleanupSteps>,
+}
+
+impl CleanupSettings {
+    pub fn steps_for_course(&self, course_id: Uuid) -> CleanupSteps {
+        self.course_steps.get(&course_id).copied().unwrap_or_default()
+    }
+}
+
+/// When the active session stops counting study time for lack of mouse and
+/// keyboard input; `None` turns a step off, as both are until the user opts in
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct IdleSettings {
+    /// Minutes without input before the session's timer pauses
+    pub pause_after_minutes: Option<u32>,
+    /// Minutes without input before the session is ended
+    pub end_after_minutes: Option<u32>,
+}
+
+impl IdleSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.pause_after_minutes == Some(0) {
+            return Err("pause_after_minutes must be greater than 0".to_string());
+        }
+        if self.end_after_minutes == Some(0) {
+            return Err("end_after_minutes must be greater than 0".to_string());
+        }
+        if let (Some(pause), Some(end)) = (self.pause_after_minutes, self.end_after_minutes) {
+            if end <= pause {
+                return Err("end_after_minutes must be greater than pause_after_minutes".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Lengths of the focus timer's phases
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct FocusSettings {
+    pub work_minutes: u32,
+    pub short_break_minutes: u32,
+    pub long_break_minutes: u32,
+    /// A long break instead of a short one after every this many work phases
+    pub long_break_every: u32,
+}
+
+impl Default for FocusSettings {
+    fn default() -> Self {
+        Self {
+            work_minutes: 25,
+            short_break_minutes: 5,
+            long_break_minutes: 15,
+            long_break_every: 4,
+        }
+    }
+}
+
+impl FocusSettings {
+    /// Longest a phase can be set to run
+    pub const MAX_PHASE_MINUTES: u32 = 24 * 60;
+
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, value) in [
+            ("work_minutes", self.work_minutes),
+            ("short_break_minutes", self.short_break_minutes),
+            ("long_break_minutes", self.long_break_minutes),
+            ("long_break_every", self.long_break_every),
+        ] {
+            if value == 0 {
+                return Err(format!("{} must be greater than 0", name));
+            }
+        }
+        for (name, value) in [
+            ("work_minutes", self.work_minutes),
+            ("short_break_minutes", self.short_break_minutes),
+            ("long_break_minutes", self.long_break_minutes),
+        ] {
+            if value > Self::MAX_PHASE_MINUTES {
+                return Err(format!(
+                    "{} must be at most {}",
+                    name,
+                    Self::MAX_PHASE_MINUTES
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// User-configurable application settings, persisted as JSON.
+/// Missing fields fall back to their defaults so older files keep loading.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct AppSettings {
+    pub naming: NamingSettings,
+    pub capture: CaptureSettings,
+    pub encoding: EncodingSettings,
+    pub cleanup: CleanupSettings,
+    pub idle: IdleSettings,
+    pub focus: FocusSettings,
+}
+
+impl AppSettings {
+    /// Load settings from JSON file
+    pub fn load_from_file(path: &PathBuf) -> Result<Self, Box<dyn std::error::Error>> {
+        if !path.exists() {
+            log::info!("Settings file does not exist, using defaults");
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(path)?;
+        let settings: AppSettings = serde_json::from_str(&contents)?;
+        log::info!("Loaded settings from file");
+        Ok(settings)
+    }
+
+    /// Save settings to JSON file
+    pub fn save_to_file(&self, path: &PathBuf) -> Result<(), Box<dyn std::error::Error>> {
+        // Ensure parent directory exists
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json)?;
+        log::info!("Saved settings to file");
+        Ok(())
+    }
+}
+
+/// Wrapper for AppSettings to be used as Tauri state
+pub type SettingsState = Arc<Mutex<AppSettings>>;
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_load_missing_file_returns_defaults() {
+        let dir = tempdir().unwrap();
+        let settings = AppSettings::load_from_file(&dir.path().join("settings.json")).unwrap();
+        assert_eq!(settings, AppSettings::default());
+        assert!(!settings.naming.llm.enabled);
+    }
+
+    #[test]
+    fn test_persistence() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("settings.json");
+
+        let mut settings = AppSettings::default();
+        settings.naming.llm.enabled = true;
+        settings.naming.llm.model = "qwen2.5:3b".to_string();
+        settings.save_to_file(&file_path).unwrap();
+
+        let loaded = AppSettings::load_from_file(&file_path).unwrap();
+        assert_eq!(loaded, settings);
+    }
+
+    #[test]
+    fn test_strategy_for_set() {
+        let mut naming = NamingSettings {
+            strategy: NamingStrategy::Timestamp,
+            ..Default::default()
+        };
+        let set_id = Uuid::new_v4();
+        assert_eq!(naming.strategy_for_set(set_id), &NamingStrategy::Timestamp);
+
+        naming.set_strategies.insert(
+            set_id,
+            NamingStrategy::Sequence {
+                prefix: "Q".to_string(),
+            },
+        );
+        assert_eq!(
+            naming.strategy_for_set(set_id),
+            &NamingStrategy::Sequence {
+                prefix: "Q".to_string()
+            }
+        );
+        assert_eq!(
+            naming.strategy_for_set(Uuid::new_v4()),
+            &NamingStrategy::Timestamp
+        );
+    }
+
+    #[test]
+    fn test_naming_strategy_serialization() {
+        let strategy = NamingStrategy::Template {
+            template: "{set}-{n}".to_string(),
+        };
+        let json = serde_json::to_string(&strategy).unwrap();
+        assert_eq!(json, r#"{"kind":"template","template":"{set}-{n}"}"#);
+        assert_eq!(
+            serde_json::from_str::<NamingStrategy>(r#"{"kind":"ai"}"#).unwrap(),
+            NamingStrategy::Ai
+        );
+    }
+
+    #[test]
+    fn test_capture_mode_serialization() {
+        assert_eq!(
+            serde_json::to_string(&CaptureMode::Monitor { id: 7 }).unwrap(),
+            r#"{"kind":"monitor","id":7}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<CaptureMode>(r#"{"kind":"all_monitors"}"#).unwrap(),
+            CaptureMode::AllMonitors
+        );
+        assert_eq!(AppSettings::default().capture.mode, CaptureMode::MonitorUnderMouse);
+        assert!(!AppSettings::default().capture.all_monitors_shortcut);
+    }
+
+    #[test]
+    fn test_encoding_settings_validate() {
+        assert!(EncodingSettings::default().validate().is_ok());
+
+        let invalid_quality = EncodingSettings {
+            quality: 0,
+            ..Default::default()
+        };
+        assert!(invalid_quality.validate().is_err());
+
+        let invalid_edge = EncodingSettings {
+            max_long_edge: Some(0),
+            ..Default::default()
+        };
+        assert!(invalid_edge.validate().is_err());
+
+        assert_eq!(
+            serde_json::from_str::<EncodingSettings>(r#"{"format":"webp","greyscale":true}"#).unwrap(),
+            EncodingSettings {
+                format: ImageFormat::Webp,
+                greyscale: true,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_idle_settings_validate() {
+        assert!(IdleSettings::default().validate().is_ok());
+        assert!(IdleSettings {
+            pause_after_minutes: None,
+            end_after_minutes: None,
+        }
+        .validate()
+        .is_ok());
+
+        let zero = IdleSettings {
+            pause_after_minutes: Some(0),
+            ..Default::default()
+        };
+        assert!(zero.validate().is_err());
+
+        let end_before_pause = IdleSettings {
+            pause_after_minutes: Some(30),
+            end_after_minutes: Some(10),
+        };
+        assert!(end_before_pause.validate().is_err());
+    }
+
+    #[test]
+    fn test_focus_settings_validate() {
+        assert!(FocusSettings::default().validate().is_ok());
+
+        let no_long_breaks = FocusSettings {
+            long_break_every: 0,
+            ..Default::default()
+        };
+        assert!(no_long_breaks.validate().is_err());
+
+        let too_long = FocusSettings {
+            work_minutes: u32::MAX,
+            ..Default::default()
+        };
+        assert!(too_long.validate().is_err());
+        assert!(FocusSettings {
+            long_break_minutes: FocusSettings::MAX_PHASE_MINUTES,
+            ..Default::default()
+        }
+        .validate()
+        .is_ok());
+
+        assert_eq!(
+            serde_json::from_str::<FocusSettings>(r#"{"work_minutes":50}"#).unwrap(),
+            FocusSettings {
+                work_minutes: 50,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_steps_for_course() {
+        let mut cleanup = CleanupSettings::default();
+        let course_id = Uuid::new_v4();
+        assert!(cleanup.steps_for_course(course_id).is_empty());
+
+        let steps = CleanupSteps {
+            invert_dark: true,
+            greyscale: true,
+            ..Default::default()
+        };
+        cleanup.course_steps.insert(course_id, steps);
+        assert_eq!(cleanup.steps_for_course(course_id), steps);
+        assert!(cleanup.steps_for_course(Uuid::new_v4()).is_empty());
+
+        assert_eq!(
+            serde_json::from_str::<CleanupSteps>(r#"{"trim_borders":true}"#).unwrap(),
+            CleanupSteps {
+                trim_borders: true,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_partial_file_uses_defaults() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("settings.json");
+        fs::write(&file_path, r#"{ "naming": { "llm": { "enabled": true } } }"#).unwrap();
+
+        let loaded = AppSettings::load_from_file(&file_path).unwrap();
+        assert!(loaded.naming.llm.enabled);
+        assert_eq!(loaded.naming.llm.endpoint, LlmSettings::default().endpoint);
+    }
+}
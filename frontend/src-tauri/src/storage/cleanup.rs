@@ -0,0 +1,191 @@
+//! Deleting image files once the rows that used them are gone.
+//!
+//! Files go only after the database change has committed, so a failure here
+//! can leave a stray file but never a problem without its image. Files that
+//! can't be removed are recorded in [`PENDING_FILE`] and retried at startup.
+//! Files written within the GC grace period are left alone: a capture may be
+//! about to reuse them, and garbage collection picks them up later.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::SystemTime;
+
+use super::gc::is_recent;
+use super::{remove_empty_dirs, resolve_image_file};
+
+/// Retry record, in the app data dir
+pub const PENDING_FILE: &str = "pending_deletions.json";
+
+/// An image file that couldn't be deleted
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct PendingDeletion {
+    /// Relative to the app data dir, as it was in `problem_images.path`
+    pub path: String,
+    pub error: String,
+    pub failed_at: DateTime<Utc>,
+}
+
+/// Deletes each image file, then any directories left empty by it, up to the
+/// app data dir. Files that are already gone count as deleted, and ones
+/// written within [`GRACE_PERIOD`](super::gc::GRACE_PERIOD) of `now` are skipped. Returns the ones
+/// that couldn't be removed.
+pub(crate) fn delete_image_files(
+    app_data_dir: &Path,
+    paths: &[String],
+    now: SystemTime,
+) -> Vec<PendingDeletion> {
+    let mut failed = Vec::new();
+    for path in paths {
+        let Some(file) = resolve_image_file(app_data_dir, path) else {
+            continue;
+        };
+        if fs::metadata(&file).is_ok_and(|metadata| is_recent(&metadata, now)) {
+            continue;
+        }
+        match fs::remove_file(&file) {
+            Ok(()) => {
+                if let Some(parent) = file.parent() {
+                    remove_empty_dirs(parent, app_data_dir);
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => failed.push(PendingDeletion {
+                path: path.clone(),
+                error: e.to_string(),
+                failed_at: Utc::now(),
+            }),
+        }
+    }
+    failed
+}
+
+/// Reads the retry record. A missing or unreadable record is empty.
+pub(crate) fn read_pending(app_data_dir: &Path) -> Vec<PendingDeletion> {
+    fs::read_to_string(app_data_dir.join(PENDING_FILE))
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// Replaces the retry record, removing it when nothing is pending
+pub(crate) fn write_pending(app_data_dir: &Path, pending: &[PendingDeletion]) -> io::Result<()> {
+    let path = app_data_dir.join(PENDING_FILE);
+    if pending.is_empty() {
+        return match fs::remove_file(&path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        };
+    }
+
+    let json = serde_json::to_string_pretty(pending)?;
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, json)?;
+    fs::rename(&tmp, &path)
+}
+
+/// Adds failures to the retry record, one entry per path
+pub(crate) fn record_pending(app_data_dir: &Path, failed: Vec<PendingDeletion>) -> io::Result<()> {
+    if failed.is_empty() {
+        return Ok(());
+    }
+    let mut pending = read_pending(app_data_dir);
+    pending.retain(|p| !failed.iter().any(|f| f.path == p.path));
+    pending.extend(failed);
+    write_pending(app_data_dir, &pending)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::gc::GRACE_PERIOD;
+    use crate::storage::put_blob;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_delete_image_files() {
+        let dir = tempdir().unwrap();
+        let set_dir = dir.path().join("Math/Calculus/Week_1");
+        fs::create_dir_all(&set_dir).unwrap();
+        fs::write(set_dir.join("Q1.png"), b"q1").unwrap();
+        fs::write(set_dir.join("Q2.png"), b"q2").unwrap();
+        fs::write(dir.path().join("plutodesk.db"), b"").unwrap();
+
+        let failed = delete_image_files(
+            dir.path(),
+            &[
+                // Legacy path without extension
+                "Math/Calculus/Week_1/Q1".to_string(),
+                "Math/Calculus/Week_1/Q2.png".to_string(),
+                // Already gone
+                "Math/Calculus/Week_1/Q3.png".to_string(),
+            ],
+            SystemTime::now() + GRACE_PERIOD * 2,
+        );
+
+        assert!(failed.is_empty());
+        // The emptied hierarchy goes, the app data dir stays
+        assert!(!dir.path().join("Math").exists());
+        assert!(dir.path().join("plutodesk.db").exists());
+    }
+
+    #[test]
+    fn test_delete_image_files_keeps_recent_blobs() {
+        let dir = tempdir().unwrap();
+        let blob = put_blob(dir.path(), b"bytes", "png").unwrap();
+        let file = dir.path().join(&blob.path);
+        let later = SystemTime::now() + GRACE_PERIOD * 2;
+
+        // Storing the same bytes again counts as a fresh write
+        fs::File::options()
+            .write(true)
+            .open(&file)
+            .unwrap()
+            .set_modified(SystemTime::now() - GRACE_PERIOD * 2)
+            .unwrap();
+        put_blob(dir.path(), b"bytes", "png").unwrap();
+
+        let failed = delete_image_files(
+            dir.path(),
+            std::slice::from_ref(&blob.path),
+            SystemTime::now(),
+        );
+        assert!(failed.is_empty());
+        assert!(file.exists());
+
+        delete_image_files(dir.path(), &[blob.path], later);
+        assert!(!file.exists());
+    }
+
+    #[test]
+    fn test_pending_record() {
+        let dir = tempdir().unwrap();
+        assert!(read_pending(dir.path()).is_empty());
+
+        let pending = |path: &str, error: &str| PendingDeletion {
+            path: path.to_string(),
+            error: error.to_string(),
+            failed_at: Utc::now(),
+        };
+        record_pending(
+            dir.path(),
+            vec![pending("a.png", "busy"), pending("b.png", "busy")],
+        )
+        .unwrap();
+        // A second failure for the same path replaces the first
+        record_pending(dir.path(), vec![pending("a.png", "denied")]).unwrap();
+
+        let record = read_pending(dir.path());
+        assert_eq!(record.len(), 2);
+        assert_eq!(
+            record.iter().find(|p| p.path == "a.png").unwrap().error,
+            "denied"
+        );
+
+        write_pending(dir.path(), &[]).unwrap();
+        assert!(!dir.path().join(PENDING_FILE).exists());
+        write_pending(dir.path(), &[]).unwrap();
+    }
+}
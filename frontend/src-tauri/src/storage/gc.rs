@@ -0,0 +1,157 @@
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use super::{blob_hash, list_files, remove_empty_dirs, BLOB_DIR};
+
+/// Files younger than this are left alone: a capture writes its blob a moment
+/// before the row that references it.
+pub const GRACE_PERIOD: Duration = Duration::from_secs(10 * 60);
+
+/// Whether a file was written within [`GRACE_PERIOD`] of `now`. A file whose
+/// time can't be read counts as recent.
+pub(crate) fn is_recent(metadata: &fs::Metadata, now: SystemTime) -> bool {
+    let age = metadata
+        .modified()
+        .ok()
+        .and_then(|modified| now.duration_since(modified).ok())
+        .unwrap_or_default();
+    age < GRACE_PERIOD
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GcFailure {
+    pub path: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GcReport {
+    pub dry_run: bool,
+    /// Blobs in the store
+    pub scanned: usize,
+    /// Blobs still used by a problem
+    pub referenced: usize,
+    /// Unreferenced blobs kept because they were written within the grace period
+    pub recent: usize,
+    /// Unreferenced blobs and stale temporary files, removed unless this is a dry run
+    pub removed: Vec<String>,
+    pub bytes_freed: u64,
+    pub failed: Vec<GcFailure>,
+}
+
+/// Removes every blob whose hash is not in `referenced`, and temporary files
+/// left by interrupted writes. With `dry_run`, only reports what would be
+/// removed.
+pub(crate) fn collect_garbage(
+    app_data_dir: &Path,
+    referenced: &HashSet<String>,
+    dry_run: bool,
+    now: SystemTime,
+) -> GcReport {
+    let mut report = GcReport {
+        dry_run,
+        ..Default::default()
+    };
+
+    let files = list_files(&app_data_dir.join(BLOB_DIR));
+
+    for file in files {
+        let Ok(relative) = file.strip_prefix(app_data_dir) else {
+            continue;
+        };
+        let hash = blob_hash(&file);
+        let is_blob = hash.is_some();
+        if let Some(hash) = hash {
+            report.scanned += 1;
+            if referenced.contains(hash) {
+                report.referenced += 1;
+                continue;
+            }
+        }
+
+        let Ok(metadata) = fs::metadata(&file) else {
+            continue;
+        };
+        if is_recent(&metadata, now) {
+            if is_blob {
+                report.recent += 1;
+            }
+            continue;
+        }
+
+        if !dry_run {
+            if let Err(e) = fs::remove_file(&file) {
+                report.failed.push(GcFailure {
+                    path: relative.to_string_lossy().to_string(),
+                    error: e.to_string(),
+                });
+                continue;
+            }
+            if let Some(parent) = file.parent() {
+                remove_empty_dirs(parent, &app_data_dir.join(BLOB_DIR));
+            }
+        }
+        report.removed.push(relative.to_string_lossy().to_string());
+        report.bytes_freed += metadata.len();
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::put_blob;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_collect_garbage() {
+        let dir = tempdir().unwrap();
+        let kept = put_blob(dir.path(), b"kept", "png").unwrap();
+        let orphan = put_blob(dir.path(), b"orphan", "png").unwrap();
+        let referenced: HashSet<String> = [kept.hash.clone()].into_iter().collect();
+        let later = SystemTime::now() + GRACE_PERIOD * 2;
+
+        // Nothing is old enough yet
+        let report = collect_garbage(dir.path(), &referenced, false, SystemTime::now());
+        assert_eq!(
+            (report.scanned, report.referenced, report.recent),
+            (2, 1, 1)
+        );
+        assert!(report.removed.is_empty());
+
+        // A dry run reports without deleting
+        let report = collect_garbage(dir.path(), &referenced, true, later);
+        assert_eq!(report.removed, vec![orphan.path.clone()]);
+        assert_eq!(report.bytes_freed, b"orphan".len() as u64);
+        assert!(dir.path().join(&orphan.path).exists());
+
+        let report = collect_garbage(dir.path(), &referenced, false, later);
+        assert_eq!(report.removed, vec![orphan.path.clone()]);
+        assert!(!dir.path().join(&orphan.path).exists());
+        // Its now-empty shard directory goes too
+        assert!(!dir.path().join(&orphan.path).parent().unwrap().exists());
+        assert!(dir.path().join(&kept.path).exists());
+    }
+
+    #[test]
+    fn test_collect_garbage_removes_stale_temp_files() {
+        let dir = tempdir().unwrap();
+        let shard = dir.path().join(BLOB_DIR).join("ab");
+        fs::create_dir_all(&shard).unwrap();
+        fs::write(shard.join("abc.1234.tmp"), b"partial").unwrap();
+
+        let report = collect_garbage(
+            dir.path(),
+            &HashSet::new(),
+            false,
+            SystemTime::now() + GRACE_PERIOD * 2,
+        );
+        assert_eq!(report.scanned, 0);
+        assert_eq!(report.removed.len(), 1);
+        assert!(!shard.exists());
+    }
+}
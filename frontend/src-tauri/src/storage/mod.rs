@@ -0,0 +1,416 @@
+//! Content-addressed image store.
+//!
+//! Every image file lives at `blobs/<aa>/<sha256>.<ext>` under the app data
+//! dir, where `<aa>` is the first two hex digits of the hash. Images are
+//! hashed by their pixels rather than their bytes, so identical images are
+//! stored once even though each PNG carries the metadata of the problem it
+//! was saved for; names of folders, courses and sets never appear in a path.
+//! `blob_refs` records which problem images use which blob; blobs nothing
+//! points at are removed by [`gc`]. What every image using a blob belongs to
+//! is kept up to date in [`sidecar`]s. Images saved under the old name-based
+//! layout are moved in by a schema migration.
+
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use uuid::Uuid;
+
+pub(crate) mod cleanup;
+pub(crate) mod gc;
+pub(crate) mod recovery;
+pub(crate) mod sidecar;
+
+/// Directory of the store, relative to the app data dir
+pub const BLOB_DIR: &str = "blobs";
+
+/// A stored image
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct StoredBlob {
+    /// Hex SHA-256 from [`content_hash`]
+    pub hash: String,
+    /// Relative to the app data dir, as stored in `problem_images.path`
+    pub path: String,
+}
+
+pub(crate) fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Hex SHA-256 of the extension, dimensions and RGBA pixels of an image, or
+/// of the bytes of anything that doesn't decode as one. Files differing only
+/// in metadata hash the same.
+pub(crate) fn content_hash(bytes: &[u8], extension: &str) -> String {
+    let Ok(image) = image::load_from_memory(bytes) else {
+        return sha256_hex(bytes);
+    };
+    let pixels = image.to_rgba8();
+    Sha256::new()
+        .chain_update(extension.as_bytes())
+        .chain_update(pixels.width().to_le_bytes())
+        .chain_update(pixels.height().to_le_bytes())
+        .chain_update(pixels.as_raw())
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// `blobs/<aa>/<hash>.<extension>`
+pub(crate) fn blob_path(hash: &str, extension: &str) -> PathBuf {
+    PathBuf::from(BLOB_DIR)
+        .join(&hash[..2])
+        .join(format!("{}.{}", hash, extension))
+}
+
+/// Hash of a blob file, from its name. `None` for anything that isn't one.
+pub(crate) fn blob_hash(path: &Path) -> Option<&str> {
+    let stem = path.file_stem()?.to_str()?;
+    (stem.len() == 64 && stem.bytes().all(|b| b.is_ascii_hexdigit())).then_some(stem)
+}
+
+/// True for paths inside the store, as opposed to the old name-based layout
+pub(crate) fn is_blob_path(image_path: &str) -> bool {
+    Path::new(image_path).starts_with(BLOB_DIR) && blob_hash(Path::new(image_path)).is_some()
+}
+
+/// Stores `bytes` and returns where. If the same image is already stored,
+/// nothing is written and the file already there is kept, but the file's modified time is refreshed so cleanup
+/// treats it as just written until the row that uses it is saved. New files
+/// are written to a temporary name and renamed, so a blob is never seen
+/// half-written.
+pub(crate) fn put_blob(
+    app_data_dir: &Path,
+    bytes: &[u8],
+    extension: &str,
+) -> io::Result<StoredBlob> {
+    let hash = content_hash(bytes, extension);
+    let relative = blob_path(&hash, extension);
+    let full = app_data_dir.join(&relative);
+
+    if !full.is_file() {
+        let dir = full.parent().expect("blob paths have a parent");
+        fs::create_dir_all(dir)?;
+
+        let tmp = dir.join(format!("{}.{}.tmp", hash, Uuid::new_v4()));
+        let written = fs::File::create(&tmp).and_then(|mut file| {
+            file.write_all(bytes)?;
+            file.sync_all()
+        });
+        if let Err(e) = written.and_then(|_| fs::rename(&tmp, &full)) {
+            let _ = fs::remove_file(&tmp);
+            return Err(e);
+        }
+    } else {
+        fs::File::options()
+            .write(true)
+            .open(&full)?
+            .set_modified(SystemTime::now())?;
+    }
+
+    Ok(StoredBlob {
+        hash,
+        path: relative.to_string_lossy().to_string(),
+    })
+}
+
+/// File for a stored `image_path`. Paths saved before the extension was kept
+/// in `image_path` point at a `.png` next to them.
+pub(crate) fn resolve_image_file(app_dir: &Path, image_path: &str) -> Option<PathBuf> {
+    let path = app_dir.join(image_path);
+    if path.is_file() {
+        return Some(path);
+    }
+
+    let legacy = app_dir.join(format!("{}.png", image_path));
+    legacy.is_file().then_some(legacy)
+}
+
+/// Every file under `dir`, recursively, sorted by path
+pub(crate) fn list_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path.is_dir() {
+                pending.push(path);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+/// Removes `dir` and then each of its parents while they are empty, stopping at `root`
+pub(crate) fn remove_empty_dirs(dir: &Path, root: &Path) {
+    let mut current = Some(dir);
+    while let Some(dir) = current {
+        if dir == root || !dir.starts_with(root) || fs::remove_dir(dir).is_err() {
+            break;
+        }
+        current = dir.parent();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_put_blob_deduplicates() {
+        let dir = tempdir().unwrap();
+
+        let first = put_blob(dir.path(), b"same bytes", "png").unwrap();
+        let second = put_blob(dir.path(), b"same bytes", "png").unwrap();
+        let other = put_blob(dir.path(), b"other bytes", "png").unwrap();
+
+        assert_eq!(first, second);
+        assert_ne!(first.hash, other.hash);
+        // Not images, so hashed by their bytes
+        assert_eq!(first.hash, sha256_hex(b"same bytes"));
+        assert_eq!(
+            PathBuf::from(&first.path),
+            PathBuf::from("blobs")
+                .join(&first.hash[..2])
+                .join(format!("{}.png", first.hash))
+        );
+        assert_eq!(
+            fs::read(dir.path().join(&first.path)).unwrap(),
+            b"same bytes"
+        );
+
+        // No temporary files are left behind
+        let shard = dir.path().join(&first.path).parent().unwrap().to_path_buf();
+        assert!(fs::read_dir(shard)
+            .unwrap()
+            .all(|e| e.unwrap().path().extension().unwrap() == "png"));
+    }
+
+    #[test]
+    fn test_images_are_hashed_by_pixels() {
+        use image::{ImageFormat, Rgba, RgbaImage};
+        use std::io::Cursor;
+
+        let png = |shade: u8| {
+            let mut bytes = Vec::new();
+            RgbaImage::from_pixel(2, 2, Rgba([shade, 0, 0, 255]))
+                .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+                .unwrap();
+            bytes
+        };
+        let metadata = crate::storage::sidecar::ImageMetadata {
+            problem_id: Uuid::new_v4(),
+            title: "Q1".to_string(),
+            role: "question".to_string(),
+            folder: "Math".to_string(),
+            course: "Calculus".to_string(),
+            set: "Week 1".to_string(),
+            captured_at: chrono::Utc::now(),
+        };
+        let tagged = crate::imaging::png_meta::embed(&png(1), &metadata).unwrap();
+        assert_ne!(tagged, png(1));
+
+        assert_eq!(content_hash(&tagged, "png"), content_hash(&png(1), "png"));
+        assert_ne!(content_hash(&png(2), "png"), content_hash(&png(1), "png"));
+        assert_ne!(content_hash(&png(1), "webp"), content_hash(&png(1), "png"));
+
+        // The first file stored for an image stays
+        let dir = tempdir().unwrap();
+        let first = put_blob(dir.path(), &tagged, "png").unwrap();
+        assert_eq!(put_blob(dir.path(), &png(1), "png").unwrap(), first);
+        assert_eq!(fs::read(dir.path().join(&first.path)).unwrap(), tagged);
+    }
+
+    #[test]
+    fn test_blob_paths() {
+        let hash = sha256_hex(b"x");
+        let path = blob_path(&hash, "webp");
+        assert_eq!(blob_hash(&path), Some(hash.as_str()));
+        assert!(is_blob_path(&path.to_string_lossy()));
+
+        assert!(!is_blob_path("Math/Calculus/Week_1/Q1.png"));
+        assert!(!is_blob_path("blobs/ab/not-a-hash.png"));
+        assert_eq!(blob_hash(Path::new("Q1.png")), None);
+    }
+
+    #[test]
+    fn test_resolve_image_file() {
+        let dir = tempdir().unwrap();
+        let set_dir = dir.path().join("Math/Calculus/Week_1");
+        fs::create_dir_all(&set_dir).unwrap();
+        fs::write(set_dir.join("Q1.png"), b"png").unwrap();
+        fs::write(set_dir.join("Q2.webp"), b"webp").unwrap();
+
+        // Legacy path without extension
+        assert_eq!(
+            resolve_image_file(dir.path(), "Math/Calculus/Week_1/Q1"),
+            Some(set_dir.join("Q1.png"))
+        );
+        // Path with its extension
+        assert_eq!(
+            resolve_image_file(dir.path(), "Math/Calculus/Week_1/Q2.webp"),
+            Some(set_dir.join("Q2.webp"))
+        );
+        assert_eq!(
+            resolve_image_file(dir.path(), "Math/Calculus/Week_1/Q3"),
+            None
+        );
+    }
+
+    /// Migrations up to, but not including, the one that moves legacy images
+    const BEFORE_BLOB_REFS: u32 = 10;
+
+    async fn problem_with_legacy_image(
+        db: &sea_orm::DatabaseConnection,
+        title: &str,
+        image_path: &str,
+    ) -> uuid::Uuid {
+        use crate::db::entities::problem_images::{self, ImageRole};
+        use crate::db::services;
+        use crate::db::services::test_fixtures::create_set;
+        use sea_orm::{ActiveModelTrait, Set};
+
+        let set = create_set(db, "Calculus", "Week 1").await;
+        let problem = services::create_problem(db, set.set_id, title.to_string(), None, None, None)
+            .await
+            .unwrap();
+        problem_images::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            problem_id: Set(problem.id),
+            role: Set(ImageRole::Question),
+            path: Set(image_path.to_string()),
+            original_path: Set(None),
+            sort_order: Set(0),
+            created_at: Set(chrono::Utc::now().naive_utc()),
+        }
+        .insert(db)
+        .await
+        .unwrap();
+        problem.id
+    }
+
+    #[tokio::test]
+    async fn test_migration_moves_legacy_images_into_store() {
+        use crate::db::services::{get_problem_images, get_referenced_blob_hashes};
+        use migration::{Migrator, MigratorTrait};
+        use sea_orm::Database;
+
+        let dir = tempdir().unwrap();
+        let db_url = format!("sqlite://{}?mode=rwc", dir.path().join("plutodesk.db").display());
+        let db = Database::connect(&db_url).await.unwrap();
+        Migrator::up(&db, Some(BEFORE_BLOB_REFS)).await.unwrap();
+
+        let set_dir = dir.path().join("Math/Calculus/Week_1");
+        fs::create_dir_all(&set_dir).unwrap();
+        fs::write(set_dir.join("Q1.png"), b"q1").unwrap();
+        fs::write(set_dir.join("Q2.webp"), b"q2").unwrap();
+
+        // An extensionless legacy path, the same file saved twice, a path with
+        // its extension, and a missing file
+        let q1 = problem_with_legacy_image(&db, "Q1", "Math/Calculus/Week_1/Q1").await;
+        let q1_again = problem_with_legacy_image(&db, "Q1", "Math/Calculus/Week_1/Q1.png").await;
+        let q2 = problem_with_legacy_image(&db, "Q2", "Math/Calculus/Week_1/Q2.webp").await;
+        let q3 = problem_with_legacy_image(&db, "Q3", "Math/Calculus/Week_1/Q3.png").await;
+
+        Migrator::up(&db, None).await.unwrap();
+
+        let q1_image = &get_problem_images(&db, q1).await.unwrap()[0];
+        assert!(is_blob_path(&q1_image.path));
+        assert!(q1_image.path.contains(&sha256_hex(b"q1")));
+        assert!(q1_image.path.ends_with(".png"));
+        assert_eq!(fs::read(dir.path().join(&q1_image.path)).unwrap(), b"q1");
+
+        // Every row that shared the old file follows it
+        let q1_again_image = &get_problem_images(&db, q1_again).await.unwrap()[0];
+        assert_eq!(q1_again_image.path, q1_image.path);
+
+        let q2_image = &get_problem_images(&db, q2).await.unwrap()[0];
+        assert!(q2_image.path.ends_with(".webp"));
+
+        let q3_image = &get_problem_images(&db, q3).await.unwrap()[0];
+        assert_eq!(q3_image.path, "Math/Calculus/Week_1/Q3.png");
+
+        assert_eq!(
+            get_referenced_blob_hashes(&db).await.unwrap(),
+            [sha256_hex(b"q1"), sha256_hex(b"q2")].into_iter().collect()
+        );
+
+        // Old files and the emptied name-based directories are gone
+        assert!(!dir.path().join("Math").exists());
+    }
+
+    #[tokio::test]
+    async fn test_migration_reruns_after_failing_partway() {
+        use crate::db::entities::blob_refs::{self, BlobKind};
+        use crate::db::services::{get_problem_images, get_referenced_blob_hashes};
+        use migration::{Migrator, MigratorTrait};
+        use sea_orm::{ActiveModelTrait, ConnectionTrait, Database, EntityTrait, Schema, Set};
+
+        let dir = tempdir().unwrap();
+        let db_url = format!("sqlite://{}?mode=rwc", dir.path().join("plutodesk.db").display());
+        let db = Database::connect(&db_url).await.unwrap();
+        Migrator::up(&db, Some(BEFORE_BLOB_REFS)).await.unwrap();
+
+        let set_dir = dir.path().join("Math/Calculus/Week_1");
+        fs::create_dir_all(&set_dir).unwrap();
+        fs::write(set_dir.join("Q2.png"), b"q2").unwrap();
+
+        // The first run created the table and its index, moved Q1 and
+        // recorded its blob, then stopped before Q2
+        let backend = db.get_database_backend();
+        db.execute(backend.build(&Schema::new(backend).create_table_from_entity(blob_refs::Entity)))
+            .await
+            .unwrap();
+        db.execute_unprepared("CREATE INDEX idx_blob_refs_hash ON blob_refs (hash)")
+            .await
+            .unwrap();
+        let q1_blob = put_blob(dir.path(), b"q1", "png").unwrap();
+        let q1 = problem_with_legacy_image(&db, "Q1", &q1_blob.path).await;
+        let q1_image = get_problem_images(&db, q1).await.unwrap().remove(0);
+        blob_refs::ActiveModel {
+            image_id: Set(q1_image.id),
+            kind: Set(BlobKind::Image),
+            problem_id: Set(q1),
+            hash: Set(q1_blob.hash.clone()),
+            path: Set(q1_blob.path.clone()),
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+        let q2 = problem_with_legacy_image(&db, "Q2", "Math/Calculus/Week_1/Q2.png").await;
+
+        Migrator::up(&db, None).await.unwrap();
+
+        assert_eq!(get_problem_images(&db, q1).await.unwrap()[0].path, q1_blob.path);
+        assert!(is_blob_path(&get_problem_images(&db, q2).await.unwrap()[0].path));
+        assert_eq!(blob_refs::Entity::find().all(&db).await.unwrap().len(), 2);
+        assert_eq!(
+            get_referenced_blob_hashes(&db).await.unwrap(),
+            [q1_blob.hash, sha256_hex(b"q2")].into_iter().collect()
+        );
+    }
+
+    #[test]
+    fn test_remove_empty_dirs() {
+        let dir = tempdir().unwrap();
+        let set_dir = dir.path().join("Math/Calculus/Week_1");
+        fs::create_dir_all(&set_dir).unwrap();
+        fs::write(dir.path().join("Math/keep.txt"), b"").unwrap();
+
+        remove_empty_dirs(&set_dir, dir.path());
+
+        assert!(!dir.path().join("Math/Calculus").exists());
+        // Stops at the first directory that still has something in it
+        assert!(dir.path().join("Math").exists());
+    }
+}
@@ -0,0 +1,218 @@
+//! Rebuilding folders, courses, sets and problems from the image store, its
+//! [`sidecar`]s and the metadata in its PNGs, for when the database is lost.
+
+use sea_orm::{ActiveEnum, DatabaseConnection, DbErr};
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+use super::sidecar::{self, ImageMetadata};
+use super::{blob_hash, list_files, BLOB_DIR};
+use crate::db::entities::problem_images::ImageRole;
+use crate::db::services;
+use crate::imaging::png_meta;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RecoveryReport {
+    /// Blobs in the store
+    pub scanned: usize,
+    /// Blobs with neither a sidecar nor embedded metadata, e.g. WebP and JPEG
+    /// images whose sidecar was lost
+    pub without_metadata: usize,
+    pub restored_problems: usize,
+    pub restored_images: usize,
+    /// Images whose problem and image rows both still exist
+    pub already_present: usize,
+}
+
+/// Every image the store's sidecars describe, with its blob path relative to
+/// the app data dir, oldest capture first. Blobs without a sidecar fall back
+/// to the metadata embedded in PNGs, which names the problem the file was
+/// first saved for. Blocking.
+pub(crate) fn scan_images(
+    app_data_dir: &Path,
+    report: &mut RecoveryReport,
+) -> Vec<(String, ImageMetadata)> {
+    let mut found = Vec::new();
+
+    for file in list_files(&app_data_dir.join(BLOB_DIR)) {
+        let (Some(hash), Ok(relative)) = (blob_hash(&file), file.strip_prefix(app_data_dir)) else {
+            continue;
+        };
+        let path = relative.to_string_lossy().to_string();
+        report.scanned += 1;
+
+        if let Some(sidecar) = sidecar::read_sidecar(app_data_dir, hash) {
+            found.extend(sidecar.images.into_iter().map(|image| (path.clone(), image)));
+            continue;
+        }
+        match fs::read(&file).ok().and_then(|bytes| png_meta::read(&bytes)) {
+            Some(metadata) => found.push((path, metadata)),
+            None => report.without_metadata += 1,
+        }
+    }
+
+    found.sort_by_key(|(path, metadata)| (metadata.captured_at, path.clone()));
+    found
+}
+
+/// Recreates whatever is missing for each scanned image: its folder, course
+/// and set by name, its problem under its old id, and its image row. Rows
+/// that exist are left as they are, so running this twice changes nothing.
+pub(crate) async fn restore_from_images(
+    db: &DatabaseConnection,
+    images: Vec<(String, ImageMetadata)>,
+    report: &mut RecoveryReport,
+) -> Result<(), DbErr> {
+    let user_id = services::get_or_create_default_user(db).await?;
+
+    for (path, metadata) in images {
+        if services::get_problem_by_id(db, metadata.problem_id)
+            .await?
+            .is_none()
+        {
+            let folder = services::find_or_create_folder(db, user_id, metadata.folder).await?;
+            let course = services::find_or_create_course(db, folder.id, metadata.course).await?;
+            let set = services::find_or_create_set(db, course.id, metadata.set).await?;
+            services::restore_problem(
+                db,
+                metadata.problem_id,
+                set.id,
+                metadata.title,
+                metadata.captured_at.naive_utc(),
+            )
+            .await?;
+            report.restored_problems += 1;
+        }
+
+        let has_image = services::get_problem_images(db, metadata.problem_id)
+            .await?
+            .iter()
+            .any(|image| image.path == path);
+        if has_image {
+            report.already_present += 1;
+            continue;
+        }
+
+        let role = ImageRole::try_from_value(&metadata.role).unwrap_or(ImageRole::Question);
+        services::add_problem_image(db, metadata.problem_id, role, path).await?;
+        report.restored_images += 1;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::services::test_fixtures::{create_set, setup_test_db};
+    use crate::db::services::{get_images_in_scope, ImageScope};
+    use crate::imaging::thumbnail;
+    use crate::storage::put_blob;
+    use crate::storage::sidecar::BlobUse;
+    use chrono::{DateTime, Utc};
+    use std::collections::HashSet;
+    use tempfile::tempdir;
+    use uuid::Uuid;
+
+    /// A 2×2 PNG of `shade` carrying `metadata`
+    fn tagged_png(shade: u8, metadata: &ImageMetadata) -> Vec<u8> {
+        let mut png = Vec::new();
+        image::GrayImage::from_pixel(2, 2, image::Luma([shade]))
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        png_meta::embed(&png, metadata).unwrap()
+    }
+
+    fn metadata(problem_id: Uuid, title: &str, role: &str, at: &str) -> ImageMetadata {
+        ImageMetadata {
+            problem_id,
+            title: title.to_string(),
+            role: role.to_string(),
+            folder: "Math".to_string(),
+            course: "Calculus".to_string(),
+            set: "Week 1".to_string(),
+            captured_at: DateTime::parse_from_rfc3339(at)
+                .unwrap()
+                .with_timezone(&Utc),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rebuild_from_images() {
+        let dir = tempdir().unwrap();
+        let q1 = Uuid::new_v4();
+        let q2 = Uuid::new_v4();
+        let q1_question = metadata(q1, "Q1", "question", "2026-03-01T09:00:00Z");
+
+        // Q1 and Q2 were captured with the same pixels and share a blob
+        let shared = put_blob(dir.path(), &tagged_png(1, &q1_question), "png").unwrap();
+        let uses = [
+            q1_question.clone(),
+            metadata(q2, "Q2", "question", "2026-03-01T09:10:00Z"),
+        ]
+        .into_iter()
+        .map(|metadata| BlobUse {
+            hash: shared.hash.clone(),
+            path: shared.path.clone(),
+            metadata,
+        })
+        .collect();
+        let hashes: HashSet<String> = [shared.hash.clone()].into_iter().collect();
+        sidecar::update_sidecars(dir.path(), &hashes, uses).unwrap();
+
+        // Its sidecar is lost, so only the metadata in the PNG is left
+        put_blob(
+            dir.path(),
+            &tagged_png(2, &metadata(q1, "Q1", "solution", "2026-03-01T09:05:00Z")),
+            "png",
+        )
+        .unwrap();
+        // A WebP without a sidecar, and a thumbnail, which isn't scanned
+        put_blob(dir.path(), b"RIFF\0\0\0\0WEBPold", "webp").unwrap();
+        let cache = thumbnail::cache_dir(dir.path());
+        fs::create_dir_all(&cache).unwrap();
+        fs::write(cache.join("thumb.png"), tagged_png(3, &q1_question)).unwrap();
+
+        let db = setup_test_db().await;
+        let mut report = RecoveryReport::default();
+        let images = scan_images(dir.path(), &mut report);
+        restore_from_images(&db, images, &mut report).await.unwrap();
+
+        assert_eq!(
+            report,
+            RecoveryReport {
+                scanned: 3,
+                without_metadata: 1,
+                restored_problems: 2,
+                restored_images: 3,
+                already_present: 0,
+            }
+        );
+
+        let problem = services::get_problem_by_id(&db, q1).await.unwrap().unwrap();
+        assert_eq!(problem.title, "Q1");
+        assert_eq!(problem.created_at, q1_question.captured_at.naive_utc());
+        let images = services::get_problem_images(&db, q1).await.unwrap();
+        let roles: Vec<ImageRole> = images.iter().map(|i| i.role).collect();
+        assert_eq!(roles, vec![ImageRole::Question, ImageRole::Solution]);
+
+        // Restored into Math/Calculus/Week 1, found by name like any other set
+        let set = create_set(&db, "Calculus", "Week 1").await;
+        assert_eq!(problem.set_id, set.set_id);
+        assert_eq!(
+            get_images_in_scope(&db, ImageScope::Set(set.set_id))
+                .await
+                .unwrap()
+                .len(),
+            3
+        );
+
+        // A second run finds everything in place
+        let mut report = RecoveryReport::default();
+        let images = scan_images(dir.path(), &mut report);
+        restore_from_images(&db, images, &mut report).await.unwrap();
+        assert_eq!((report.restored_problems, report.restored_images), (0, 0));
+        assert_eq!(report.already_present, 3);
+    }
+}
@@ -0,0 +1,182 @@
+//! What each stored image is, kept next to the store so the database can be
+//! rebuilt from the app data dir alone.
+//!
+//! Blobs hold only image bytes, so identical captures share one file. The
+//! problem, title and hierarchy names of the images using a blob live in
+//! `blob_index/<aa>/<hash>.json` instead, rewritten from the database when
+//! those images or names change and brought up to date at startup.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+use super::{blob_hash, list_files, remove_empty_dirs};
+
+/// Directory of the sidecars, relative to the app data dir
+pub const SIDECAR_DIR: &str = "blob_index";
+
+/// One image using a blob, with what recovery needs to rebuild its rows
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImageMetadata {
+    pub problem_id: Uuid,
+    pub title: String,
+    /// `problem_images.role`: question, solution or work
+    pub role: String,
+    pub folder: String,
+    pub course: String,
+    pub set: String,
+    pub captured_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Sidecar {
+    /// The blob, relative to the app data dir
+    pub path: String,
+    pub images: Vec<ImageMetadata>,
+}
+
+/// A blob's current file use, as read from the database
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlobUse {
+    pub hash: String,
+    pub path: String,
+    pub metadata: ImageMetadata,
+}
+
+/// `blob_index/<aa>/<hash>.json`
+pub(crate) fn sidecar_path(hash: &str) -> PathBuf {
+    PathBuf::from(SIDECAR_DIR)
+        .join(&hash[..2])
+        .join(format!("{}.json", hash))
+}
+
+/// The sidecar of a blob, if it has a readable one
+pub(crate) fn read_sidecar(app_data_dir: &Path, hash: &str) -> Option<Sidecar> {
+    let json = fs::read_to_string(app_data_dir.join(sidecar_path(hash))).ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+/// Brings the sidecar of each of `hashes` in line with `uses`: written when
+/// it differs, removed when no image uses the blob. Returns how many files
+/// changed.
+pub(crate) fn update_sidecars(
+    app_data_dir: &Path,
+    hashes: &HashSet<String>,
+    uses: Vec<BlobUse>,
+) -> io::Result<usize> {
+    let mut sidecars: BTreeMap<String, Sidecar> = BTreeMap::new();
+    for blob_use in uses {
+        sidecars
+            .entry(blob_use.hash)
+            .or_insert_with(|| Sidecar {
+                path: blob_use.path,
+                images: Vec::new(),
+            })
+            .images
+            .push(blob_use.metadata);
+    }
+
+    let mut changed = 0;
+    for hash in hashes {
+        let file = app_data_dir.join(sidecar_path(hash));
+        match sidecars.remove(hash) {
+            Some(mut sidecar) => {
+                sidecar
+                    .images
+                    .sort_by_key(|image| (image.captured_at, image.problem_id));
+                if read_sidecar(app_data_dir, hash).as_ref() == Some(&sidecar) {
+                    continue;
+                }
+                let dir = file.parent().expect("sidecar paths have a parent");
+                fs::create_dir_all(dir)?;
+                let tmp = dir.join(format!("{}.{}.tmp", hash, Uuid::new_v4()));
+                let written = fs::write(&tmp, serde_json::to_string_pretty(&sidecar)?)
+                    .and_then(|_| fs::rename(&tmp, &file));
+                if let Err(e) = written {
+                    let _ = fs::remove_file(&tmp);
+                    return Err(e);
+                }
+            }
+            None => match fs::remove_file(&file) {
+                Ok(()) => {
+                    if let Some(parent) = file.parent() {
+                        remove_empty_dirs(parent, &app_data_dir.join(SIDECAR_DIR));
+                    }
+                }
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            },
+        }
+        changed += 1;
+    }
+    Ok(changed)
+}
+
+/// Hashes of every blob that has a sidecar
+pub(crate) fn sidecar_hashes(app_data_dir: &Path) -> HashSet<String> {
+    list_files(&app_data_dir.join(SIDECAR_DIR))
+        .iter()
+        .filter(|file| file.extension().is_some_and(|e| e == "json"))
+        .filter_map(|file| blob_hash(file).map(str::to_string))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn blob_use(hash: &str, problem_id: Uuid, set: &str) -> BlobUse {
+        BlobUse {
+            hash: hash.to_string(),
+            path: format!("blobs/{}/{}.png", &hash[..2], hash),
+            metadata: ImageMetadata {
+                problem_id,
+                title: "Q1".to_string(),
+                role: "question".to_string(),
+                folder: "Math".to_string(),
+                course: "Calculus".to_string(),
+                set: set.to_string(),
+                captured_at: Utc::now(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_update_sidecars() {
+        let dir = tempdir().unwrap();
+        let shared = "ab".repeat(32);
+        let single = "cd".repeat(32);
+        let hashes: HashSet<String> = [shared.clone(), single.clone()].into_iter().collect();
+        let (q1, q2) = (Uuid::new_v4(), Uuid::new_v4());
+
+        // Two problems captured with the same bytes share a sidecar
+        let uses = vec![
+            blob_use(&shared, q1, "Week 1"),
+            blob_use(&shared, q2, "Week 1"),
+            blob_use(&single, q1, "Week 1"),
+        ];
+        assert_eq!(update_sidecars(dir.path(), &hashes, uses.clone()).unwrap(), 2);
+        let sidecar = read_sidecar(dir.path(), &shared).unwrap();
+        assert_eq!(sidecar.path, uses[0].path);
+        assert_eq!(sidecar.images.len(), 2);
+        assert_eq!(sidecar_hashes(dir.path()), hashes);
+
+        // Unchanged sidecars aren't rewritten
+        assert_eq!(update_sidecars(dir.path(), &hashes, uses.clone()).unwrap(), 0);
+
+        // A rename rewrites; a blob nothing uses loses its sidecar
+        let renamed = vec![blob_use(&single, q1, "Week 1 (limits)")];
+        assert_eq!(update_sidecars(dir.path(), &hashes, renamed).unwrap(), 2);
+        assert!(read_sidecar(dir.path(), &shared).is_none());
+        assert!(!dir.path().join(SIDECAR_DIR).join("ab").exists());
+        assert_eq!(
+            read_sidecar(dir.path(), &single).unwrap().images[0].set,
+            "Week 1 (limits)"
+        );
+    }
+}
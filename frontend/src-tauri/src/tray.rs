@@ -1,11 +1,12 @@
 use tauri::{
-    menu::{Menu, MenuItem},
+    menu::{Menu, MenuItem, PredefinedMenuItem},
     tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
     AppHandle, Emitter, Manager, Runtime, WebviewUrl, WebviewWindowBuilder,
 };
 
-use crate::session::SessionManagerState;
-use tauri_plugin_notification::NotificationExt;
+use crate::commands::sessions::end_active_session;
+use crate::notify::notify;
+use crate::session::{control_focus_timer, FocusCommand, SessionManagerState};
 
 fn any_ui_window_visible<R: Runtime>(app: &AppHandle<R>) -> bool {
     app.webview_windows()
@@ -47,12 +48,22 @@ pub fn create_tray<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<()> {
     let quit_item = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
     let start_session_item = MenuItem::with_id(app, "start_session", "Start/Switch Session", true, None::<&str>)?;
     let end_session_item = MenuItem::with_id(app, "end_session", "End Session", true, None::<&str>)?;
+    let focus_start_item = MenuItem::with_id(app, "focus_start", "Start Focus Timer", true, None::<&str>)?;
+    let focus_pause_item = MenuItem::with_id(app, "focus_pause", "Pause Focus Timer", true, None::<&str>)?;
+    let focus_skip_item = MenuItem::with_id(app, "focus_skip", "Skip Focus Phase", true, None::<&str>)?;
+    let session_separator = PredefinedMenuItem::separator(app)?;
+    let focus_separator = PredefinedMenuItem::separator(app)?;
     
     let menu = Menu::with_items(
         app,
         &[
             &start_session_item,
             &end_session_item,
+            &session_separator,
+            &focus_start_item,
+            &focus_pause_item,
+            &focus_skip_item,
+            &focus_separator,
             &quit_item,
         ],
     )?;
@@ -76,26 +87,26 @@ pub fn create_tray<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<()> {
                 log::info!("End Session menu item clicked");
                 // End the current session
                 if let Some(session_manager) = app.try_state::<SessionManagerState>() {
-                    let mut manager = session_manager.lock().unwrap();
-                    let ended_name = manager.get_active_session().map(|s| s.name.clone());
-                    manager.end_session();
-                    
-                    // Persist to file
-                    if let Ok(app_data_dir) = app.path().app_data_dir() {
-                        let sessions_path = app_data_dir.join("sessions.json");
-                        if let Err(e) = manager.save_to_file(&sessions_path) {
-                            log::error!("Failed to save sessions: {}", e);
+                    let ended_name = session_manager
+                        .lock()
+                        .unwrap()
+                        .get_active_session()
+                        .map(|s| s.name.clone());
+
+                    // Ended in the database; the session manager follows
+                    let app_handle = app.clone();
+                    tauri::async_runtime::spawn(async move {
+                        if let Err(e) = end_active_session(&app_handle).await {
+                            log::error!("{}", e);
                         }
-                    }
+                    });
 
                     // Notify user without stealing focus
                     let (title, body) = match ended_name {
                         Some(name) => ("Session Ended", format!("Ended: {name}")),
                         None => ("No Active Session", "There was no active session to end.".to_string()),
                     };
-                    if let Err(e) = app.notification().builder().title(title).body(body).show() {
-                        log::error!("Failed to show notification: {}", e);
-                    }
+                    notify(app, title, body);
 
                     // If UI is open/visible, notify frontend to refresh session state
                     if any_ui_window_visible(app) {
@@ -103,6 +114,18 @@ pub fn create_tray<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<()> {
                     }
                 }
             }
+            "focus_start" | "focus_pause" | "focus_skip" => {
+                let command = match event.id.as_ref() {
+                    "focus_start" => FocusCommand::Start,
+                    "focus_pause" => FocusCommand::Pause,
+                    _ => FocusCommand::Skip,
+                };
+                log::info!("Focus timer menu item clicked: {:?}", command);
+                // Transitions notify on their own; only failures need telling here
+                if let Err(message) = control_focus_timer(app, command) {
+                    notify(app, "Focus Timer", message);
+                }
+            }
             _ => {}
         })
         .on_tray_icon_event(|_tray, event| {
@@ -211,8 +211,11 @@ describe('ScreenshotOverlay', () => {
             });
 
             await waitFor(() => {
-                expect(mockInvoke).toHaveBeenCalledWith('receive_screenshot_data', {
-                    imageUrl: expect.any(String),
+                expect(mockInvoke).toHaveBeenCalledWith('save_screenshot_selection', {
+                    selection: { x: 100, y: 100, width: 100, height: 100 },
+                    folderId: null,
+                    courseId: null,
+                    setId: null,
                 });
                 expect(mockOnClose).toHaveBeenCalled();
             });
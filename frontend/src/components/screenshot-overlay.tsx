@@ -20,6 +20,11 @@ interface ScreenshotOverlayProps {
   onClose: () => void;
 }
 
+// pluto-capture://localhost/current, or http://pluto-capture.localhost/current on Windows
+function isCaptureUrl(data: string) {
+  return data.startsWith("pluto-capture:") || data.startsWith("http://pluto-capture.localhost");
+}
+
 export function ScreenshotOverlay({ onClose }: ScreenshotOverlayProps) {
   const canvasRef = useRef<HTMLCanvasElement>(null);
 
@@ -60,17 +65,27 @@ export function ScreenshotOverlay({ onClose }: ScreenshotOverlayProps) {
     };
   }, []);
 
-  // Helper function to process screenshot data (used by both command and event)
-  const handleScreenshotData = useCallback(async (base64: string) => {
+  // Helper function to process screenshot data (used by both command and event).
+  // The backend sends a pluto-capture:// URL; a raw base64 PNG is still accepted.
+  const handleScreenshotData = useCallback(async (data: string) => {
     try {
-      // Convert base64 to blob to avoid data URL size limits
-      const byteCharacters = atob(base64);
-      const byteNumbers = new Array(byteCharacters.length);
-      for (let i = 0; i < byteCharacters.length; i++) {
-        byteNumbers[i] = byteCharacters.charCodeAt(i);
+      let blob: Blob;
+      if (isCaptureUrl(data)) {
+        const response = await fetch(data);
+        if (!response.ok) {
+          throw new Error(`Failed to load capture: ${response.status}`);
+        }
+        blob = await response.blob();
+      } else {
+        // Convert base64 to blob to avoid data URL size limits
+        const byteCharacters = atob(data);
+        const byteNumbers = new Array(byteCharacters.length);
+        for (let i = 0; i < byteCharacters.length; i++) {
+          byteNumbers[i] = byteCharacters.charCodeAt(i);
+        }
+        const byteArray = new Uint8Array(byteNumbers);
+        blob = new Blob([byteArray], { type: 'image/png' });
       }
-      const byteArray = new Uint8Array(byteNumbers);
-      const blob = new Blob([byteArray], { type: 'image/png' });
 
       // Create image bitmap from blob
       const imageBitmap = await createImageBitmap(blob);
@@ -169,54 +184,24 @@ export function ScreenshotOverlay({ onClose }: ScreenshotOverlayProps) {
     [isSelecting, selection],
   );
 
-  // Helper function for scaling selection coordinates
-  function getScaledSelection(
-    canvas: HTMLCanvasElement,
-    selection: SelectionArea,
-  ) {
-    const rect = canvas.getBoundingClientRect();
-    const scaleX = canvas.width / rect.width;
-    const scaleY = canvas.height / rect.height;
-
-    return {
-      x: Math.min(selection.startX, selection.endX) * scaleX,
-      y: Math.min(selection.startY, selection.endY) * scaleY,
-      width: Math.abs(selection.endX - selection.startX) * scaleX,
-      height: Math.abs(selection.endY - selection.startY) * scaleY,
-    };
-  }
-
-  // Define cropSelection before handlePointerUp so it can be referenced
-  const cropSelection = useCallback(() => {
-    if (!canvasRef.current || !selection) return;
+  // Selection in overlay (logical) pixels; the backend scales and crops the held frame
+  const getSelectionRect = useCallback(() => {
+    if (!selection) return null;
 
-    const canvas = canvasRef.current;
-    const ctx = canvas.getContext("2d");
-    if (!ctx) return;
-
-    // Apply scaling to selection
-    const { x, y, width, height } = getScaledSelection(canvas, selection);
-
-    // Get image data
+    const width = Math.abs(selection.endX - selection.startX);
+    const height = Math.abs(selection.endY - selection.startY);
     if (width < 1 || height < 1) {
       console.warn("[screenshot-overlay] Invalid selection area");
       setSelection(null);
-      return;
+      return null;
     }
-    const imageData = ctx.getImageData(x, y, width, height);
 
-    // Create new canvas for cropped image
-    const cropCanvas = document.createElement("canvas");
-    cropCanvas.width = width;
-    cropCanvas.height = height;
-    const cropCtx = cropCanvas.getContext("2d");
-    if (!cropCtx) return;
-
-    cropCtx.putImageData(imageData, 0, 0);
-
-    // Export cropped image as base64 PNG
-    const croppedDataUrl = cropCanvas.toDataURL("image/png");
-    return croppedDataUrl;
+    return {
+      x: Math.min(selection.startX, selection.endX),
+      y: Math.min(selection.startY, selection.endY),
+      width,
+      height,
+    };
   }, [selection]);
 
   const handlePointerUp = useCallback(
@@ -233,13 +218,14 @@ export function ScreenshotOverlay({ onClose }: ScreenshotOverlayProps) {
 
       if (isSelecting && selection) {
         setIsSelecting(false);
-        const croppedDataUrl = cropSelection();
+        const selectionRect = getSelectionRect();
+        if (!selectionRect) return;
 
         // Session check is now done before screenshot is taken
         // If we reach here, a session must be active
         try {
-          await invoke("receive_screenshot_data", {
-            imageUrl: croppedDataUrl,
+          await invoke("save_screenshot_selection", {
+            selection: selectionRect,
             folderId: null,
             courseId: null,
             setId: null,
@@ -252,7 +238,7 @@ export function ScreenshotOverlay({ onClose }: ScreenshotOverlayProps) {
         }
       }
     },
-    [isSelecting, selection, cropSelection, onClose],
+    [isSelecting, selection, getSelectionRect, onClose],
   );
 
   const handleKeyDown = useCallback(